@@ -0,0 +1,37 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use redshirt_syscalls::InterfaceHash;
+
+// TODO: this has been randomly generated; instead should be a hash or something
+pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0xf8, 0x53, 0x31, 0xbc, 0xd8, 0x57, 0xc7, 0x19, 0xe5, 0xe8, 0xf7, 0x43, 0xec, 0x73, 0x66, 0xee,
+    0x7a, 0xe6, 0x55, 0x96, 0x28, 0xa6, 0xbd, 0x94, 0x08, 0x64, 0x14, 0x68, 0x74, 0x09, 0x17, 0xb1,
+]);
+
+#[derive(Debug, Encode, Decode)]
+pub enum BootArgsMessage {
+    /// Must respond with a [`GetResponse`].
+    Get,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetResponse {
+    /// Command line the kernel has been started with, normally in UTF-8. Empty if there isn't
+    /// any.
+    pub command_line: Vec<u8>,
+}
@@ -0,0 +1,71 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Boot arguments.
+//!
+//! Allows learning the command line that the kernel has been started with, for example in order
+//! to determine which programs to start or which debugging options to enable.
+//!
+//! This interface is read-only, and is available from the very beginning of the boot process.
+//!
+//! The command line is a list of arguments separated with spaces. Each argument is either a
+//! flag (e.g. `debug`), or a key and a value separated with `=` (e.g. `loglevel=3`). See
+//! [`parse`].
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use futures::prelude::*;
+
+pub mod ffi;
+
+/// Returns the command line that the kernel has been started with.
+///
+/// The string is empty if the kernel has been started without any command line, or if the
+/// platform doesn't support passing one.
+pub fn command_line() -> impl Future<Output = String> {
+    unsafe {
+        let msg = ffi::BootArgsMessage::Get;
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::GetResponse = response.unwrap();
+                String::from_utf8_lossy(&response.command_line).into_owned()
+            })
+    }
+}
+
+/// Splits a command line into a list of arguments.
+///
+/// Each argument is returned as a key and an optional value. Arguments without a `=` are
+/// returned with a value of `None`.
+///
+/// ```
+/// let mut args = redshirt_bootargs_interface::parse("debug  loglevel=3 root=");
+/// assert_eq!(args.next(), Some(("debug", None)));
+/// assert_eq!(args.next(), Some(("loglevel", Some("3"))));
+/// assert_eq!(args.next(), Some(("root", Some(""))));
+/// assert_eq!(args.next(), None);
+/// ```
+pub fn parse(command_line: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    command_line
+        .split_whitespace()
+        .map(|arg| match arg.find('=') {
+            Some(pos) => (&arg[..pos], Some(&arg[pos + 1..])),
+            None => (arg, None),
+        })
+}
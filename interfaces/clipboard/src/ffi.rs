@@ -0,0 +1,48 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use redshirt_syscalls::InterfaceHash;
+
+// TODO: this has been randomly generated; instead should be a hash or something
+pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0xbe, 0x0a, 0x31, 0xb6, 0x17, 0x8d, 0xe8, 0xa5, 0xce, 0x81, 0xe4, 0xcb, 0x99, 0xe7, 0x96, 0xbc,
+    0xa1, 0x2b, 0xc7, 0xbe, 0xd0, 0xee, 0x92, 0x1c, 0xec, 0xa0, 0x30, 0x69, 0x09, 0xb1, 0x35, 0x44,
+]);
+
+#[derive(Debug, Encode, Decode)]
+pub enum ClipboardMessage {
+    /// Ask for the text content of the clipboard. Must respond with a [`GetTextResponse`].
+    GetText,
+    /// Replace the content of the clipboard with the given UTF-8 text. Doesn't expect any
+    /// response.
+    SetText(Vec<u8>),
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct GetTextResponse {
+    pub content: ClipboardContent,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub enum ClipboardContent {
+    /// The clipboard is empty.
+    Empty,
+    /// The clipboard contains the given UTF-8 text.
+    Text(Vec<u8>),
+    /// The clipboard contains something that can't be represented as text, such as an image.
+    Unsupported,
+}
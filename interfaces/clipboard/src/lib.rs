@@ -0,0 +1,58 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Clipboard.
+//!
+//! Allows reading and writing the text content of the clipboard shared between programs.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use futures::prelude::*;
+
+pub mod ffi;
+
+/// Returns the text content of the clipboard.
+///
+/// Returns `None` if the clipboard is empty or contains something other than text.
+///
+/// Returns an error if the handler of the interface answered with an error, a malformed
+/// response, or text that isn't valid UTF-8.
+pub fn get_text() -> impl Future<Output = Result<Option<String>, ()>> {
+    unsafe {
+        let msg = ffi::ClipboardMessage::GetText;
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::GetTextResponse = response.map_err(|_| ())?;
+                match response.content {
+                    ffi::ClipboardContent::Text(text) => {
+                        String::from_utf8(text).map(Some).map_err(|_| ())
+                    }
+                    ffi::ClipboardContent::Empty | ffi::ClipboardContent::Unsupported => Ok(None),
+                }
+            })
+    }
+}
+
+/// Replaces the content of the clipboard with the given text.
+pub fn set_text(text: &str) {
+    unsafe {
+        let msg = ffi::ClipboardMessage::SetText(text.as_bytes().to_vec());
+        redshirt_syscalls::emit_message_without_response(&ffi::INTERFACE, msg).unwrap();
+    }
+}
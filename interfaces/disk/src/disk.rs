@@ -100,10 +100,25 @@ impl DiskRegistration {
     /// >           `Future` finishes first.
     pub async fn next_command(&self) -> Command {
         let mut commands = self.commands.lock().await;
-        let data = commands.next().await.unwrap();
-        commands.push(build_commands_future(self.id));
-        // TODO: extra copy when decoding :-/
-        let decoded = ffi::DiskCommand::decode(EncodedMessage(data)).unwrap();
+        let decoded = loop {
+            let response = commands.next().await.unwrap();
+            commands.push(build_commands_future(self.id));
+
+            // Commands that can't be decoded are ignored.
+            let data = match response {
+                Ok(d) => d,
+                Err(err) => {
+                    log::warn!("Ignoring bad disk command: {}", err);
+                    continue;
+                }
+            };
+            // TODO: extra copy when decoding :-/
+            match ffi::DiskCommand::decode(EncodedMessage(data)) {
+                Ok(decoded) => break decoded,
+                Err(_) => log::warn!("Ignoring undecodable disk command"),
+            }
+        };
+
         match decoded {
             ffi::DiskCommand::StartRead {
                 id,
@@ -109,7 +109,7 @@ impl NetInterfaceRegistration {
         // Wait for the previous send to be finished.
         let mut packet_from_net = self.packet_from_net.lock().await;
         if let Some(fut) = packet_from_net.as_mut() {
-            fut.await;
+            let _ = fut.await;
         }
         *packet_from_net = None;
 
@@ -126,9 +126,15 @@ impl NetInterfaceRegistration {
     /// >           `Future` finishes first.
     pub async fn packet_to_send(&self) -> Vec<u8> {
         let mut packet_to_net = self.packet_to_net.lock().await;
-        let data = packet_to_net.next().await.unwrap();
-        packet_to_net.push(build_packet_to_net(self.id));
-        data
+        loop {
+            let response = packet_to_net.next().await.unwrap();
+            packet_to_net.push(build_packet_to_net(self.id));
+            // Packets that can't be decoded are ignored.
+            match response {
+                Ok(data) => break data,
+                Err(err) => log::warn!("Ignoring bad packet to send: {}", err),
+            }
+        }
     }
 }
 
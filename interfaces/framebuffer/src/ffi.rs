@@ -27,11 +27,58 @@
 //! height values. The rest is RGB triplets.
 //! - 3: Send back the next input event. Next 4 bytes are the framebuffer ID. The answer consists
 //! in an input event whose format is a SCALE-encoding of the [`Event`] struct below.
+//! - 4: Grab the pointer. Next 4 bytes are the framebuffer ID. The cursor is locked, and mouse
+//! movements are reported as [`Event::RawMouseMotion`] instead of [`Event::CursorMoved`]. Whether
+//! the cursor is also hidden is up to the handler. The grab is automatically released if the
+//! framebuffer loses focus, in which case [`Event::PointerGrabReleased`] is reported. Ignored if
+//! the framebuffer doesn't have focus.
+//! - 5: Release the pointer grab. Next 4 bytes are the framebuffer ID.
+//! - 6: Give focus to the framebuffer, bringing it to the front. Next 4 bytes are the framebuffer
+//! ID.
+//! - 7: Minimize the framebuffer. Next 4 bytes are the framebuffer ID.
+//! - 8: Maximize the framebuffer. Next 4 bytes are the framebuffer ID.
+//! - 9: Set the content of parts of multiple framebuffers at once. Next 4 bytes are the number of
+//! updates in little endian. Then, for each update, 4 bytes for the framebuffer ID, then the x
+//! coordinate, y coordinate, width and height of the area to update, each 4 bytes in little
+//! endian, then 3 * width * height values of RGB triplets. See [`encode_batched_update`] and
+//! [`decode_batched_update`].
+//!
+//! - 10: List the video outputs. No other bytes. The answer is a SCALE-encoding of a
+//! `Vec<`[`OutputInfo`]`>`.
+//! - 11: Get the video output the framebuffer is displayed on. Next 4 bytes are the framebuffer
+//! ID. The answer is a SCALE-encoding of an `Option<`[`OutputInfo`]`>`, which is `None` if the
+//! framebuffer isn't visible on any video output. If it spans multiple video outputs, the one
+//! where the largest area of the framebuffer is visible is returned.
+//! - 12: Confirm presentation. Next 4 bytes are the framebuffer ID. The answer is an empty
+//! SCALE-encoded `()`, sent once all the content updates emitted before this message have been
+//! drawn and handed to the video outputs. No answer is sent while the framebuffer isn't visible
+//! on any video output.
+//! - 13: Switch the video mode of a video output. Next 8 bytes are the `handler_pid` of the
+//! [`OutputId`], next 8 bytes its `handler_id`, then the width, height and refresh rate of the
+//! mode, each 4 bytes in little endian. The mode must be one of [`OutputInfo::modes`]. Only the
+//! process that owns the framebuffer that has focus can switch the mode of the video output this
+//! framebuffer is displayed on. The answer is an empty SCALE-encoded `()`, or an error if the
+//! video output doesn't exist, doesn't support this mode, or if the emitter isn't allowed to
+//! switch its mode. The framebuffers whose dimensions change as a result are reported an
+//! [`Event::Resized`]. See [`encode_set_video_mode`] and [`decode_set_video_mode`].
+//!
+//! All the updates of a message 9 must be applied before the next redraw, so that they appear
+//! in the same frame. Each update whose area isn't within its framebuffer is ignored, without
+//! affecting the other updates of the message.
+//!
+//! Messages 6 to 8 are hints, and the handler is free to ignore them. For example, a handler
+//! that shows framebuffers full screen has nothing to do when asked to maximize.
+//!
+//! The handler can change the dimensions of a framebuffer, in which case [`Event::Resized`] is
+//! reported. Contrary to the other events, it is kept until a message 3 is available to report
+//! it.
 //!
 //! There actually exists two interfaces that use the same messages format: with events, or without
 //! events. Messages whose first byte is `3` are invalid in the "without events" interface.
 
-use redshirt_syscalls::InterfaceHash;
+use alloc::vec::Vec;
+use core::convert::TryFrom as _;
+use redshirt_syscalls::{read_le, write_le, InterfaceHash};
 
 // TODO: this has been randomly generated; instead should be a hash or something
 pub const INTERFACE_WITH_EVENTS: InterfaceHash = InterfaceHash::from_raw_hash([
@@ -81,6 +128,45 @@ pub enum Event {
         /// New state of the given button.
         new_state: ElementState,
     },
+
+    /// A character has been typed.
+    ///
+    /// Contrary to [`Event::KeyboardChange`], the keyboard layout has been taken into account.
+    /// Keeping a key pressed repeatedly generates this event, according to the key-repeat
+    /// settings of the handler.
+    Character {
+        /// Unicode scalar value of the character.
+        character: u32,
+    },
+
+    /// The mouse has moved while the pointer is grabbed.
+    RawMouseMotion {
+        /// Horizontal movement, in device-specific units. Positive means towards the right.
+        dx: i32,
+        /// Vertical movement, in device-specific units. Positive means towards the bottom.
+        dy: i32,
+    },
+
+    /// The pointer grab has been released without being requested, for example because the
+    /// framebuffer has lost focus.
+    PointerGrabReleased,
+
+    /// The framebuffer has gained or lost focus. Keyboard events are only reported to the
+    /// framebuffer that has focus.
+    FocusChanged {
+        /// True if the framebuffer now has focus.
+        focused: bool,
+    },
+
+    /// The handler has changed the dimensions of the framebuffer, for example because it has
+    /// been maximized. Its content has been reset and must be set again with the new
+    /// dimensions. Messages that set content for the previous dimensions are ignored.
+    Resized {
+        /// New width of the framebuffer in pixels.
+        width: u32,
+        /// New height of the framebuffer in pixels.
+        height: u32,
+    },
 }
 
 #[derive(Debug, Clone, parity_scale_codec::Encode, parity_scale_codec::Decode)]
@@ -96,3 +182,271 @@ pub enum ElementState {
     Pressed,
     Released,
 }
+
+/// Description of a video output, as returned by messages of type 10 and 11.
+#[derive(Debug, Clone, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode)]
+pub struct OutputInfo {
+    /// Identifier of the video output. Stays the same for as long as the video output exists.
+    pub id: OutputId,
+    /// Horizontal position of the top-left hand corner of the video output on the desktop.
+    pub x: u32,
+    /// Vertical position of the top-left hand corner of the video output on the desktop.
+    pub y: u32,
+    /// Width of the video output in pixels.
+    pub width: u32,
+    /// Height of the video output in pixels.
+    pub height: u32,
+    /// Format of the pixels of the video output.
+    pub format: OutputFormat,
+    /// Width and height of the visible area of the monitor in millimeters, if known.
+    pub physical_size_mm: Option<(u32, u32)>,
+    /// Width and height in pixels of the preferred video mode of the monitor, if known. Can be
+    /// different from `width` and `height`.
+    pub native_resolution: Option<(u32, u32)>,
+    /// Refresh rate of the video output in millihertz, or 0 if unknown.
+    pub refresh_rate_mhz: u32,
+    /// Video modes the video output can be switched to with a message of type 13. Empty if the
+    /// mode of the video output can't be changed.
+    pub modes: Vec<VideoMode>,
+}
+
+/// Video mode of a video output.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode,
+)]
+pub struct VideoMode {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Refresh rate in millihertz, or 0 if unknown.
+    pub refresh_rate_mhz: u32,
+}
+
+/// Identifier of a video output.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, Hash, parity_scale_codec::Encode, parity_scale_codec::Decode,
+)]
+pub struct OutputId {
+    /// Process that has registered the video output.
+    pub handler_pid: u64,
+    /// Identifier of the video output chosen by its handler.
+    pub handler_id: u64,
+}
+
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode,
+)]
+pub enum OutputFormat {
+    R8G8B8X8,
+}
+
+/// Update of an area of a framebuffer, as part of a message of type 9.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramebufferUpdate<'a> {
+    /// Identifier of the framebuffer to update.
+    pub framebuffer_id: u32,
+    /// Horizontal coordinate of the top-left hand corner of the area to update.
+    pub x: u32,
+    /// Vertical coordinate of the top-left hand corner of the area to update.
+    pub y: u32,
+    /// Width of the area to update.
+    pub width: u32,
+    /// Height of the area to update.
+    pub height: u32,
+    /// RGB triplets, row by row. Must be of size `3 * width * height`.
+    pub data: &'a [u8],
+}
+
+/// Builds a message of type 9 containing the given updates.
+///
+/// # Panics
+///
+/// Panics if the size of the data of an update doesn't match its width and height.
+///
+pub fn encode_batched_update<'a>(
+    updates: impl ExactSizeIterator<Item = FramebufferUpdate<'a>>,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5);
+    out.push(9);
+    write_le(&mut out, u32::try_from(updates.len()).unwrap());
+
+    for update in updates {
+        assert_eq!(
+            Some(update.data.len()),
+            update_data_len(update.width, update.height)
+        );
+        out.reserve(20 + update.data.len());
+        write_le(&mut out, update.framebuffer_id);
+        write_le(&mut out, update.x);
+        write_le(&mut out, update.y);
+        write_le(&mut out, update.width);
+        write_le(&mut out, update.height);
+        out.extend_from_slice(update.data);
+    }
+
+    out
+}
+
+/// Decodes a message of type 9. Returns `None` if the message is malformed.
+///
+/// > **Note**: This function doesn't verify whether the areas of the updates fit within their
+/// >           framebuffers, as this is out of its knowledge.
+pub fn decode_batched_update(message: &[u8]) -> Option<Vec<FramebufferUpdate>> {
+    if message.get(0) != Some(&9) {
+        return None;
+    }
+
+    let num_updates = usize::try_from(read_le::<u32>(message, 1)?).ok()?;
+    let mut message = &message[5..];
+
+    // Each update is at least 20 bytes long. Checking this before allocating prevents a
+    // malicious sender from triggering a large allocation.
+    if num_updates > message.len() / 20 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(num_updates);
+    for _ in 0..num_updates {
+        let width = read_le(message, 12)?;
+        let height = read_le(message, 16)?;
+        let data_len = update_data_len(width, height)?;
+        let data = message.get(20..20usize.checked_add(data_len)?)?;
+
+        out.push(FramebufferUpdate {
+            framebuffer_id: read_le(message, 0)?,
+            x: read_le(message, 4)?,
+            y: read_le(message, 8)?,
+            width,
+            height,
+            data,
+        });
+
+        message = &message[20 + data_len..];
+    }
+
+    if !message.is_empty() {
+        return None;
+    }
+
+    Some(out)
+}
+
+/// Builds a message of type 13.
+pub fn encode_set_video_mode(output: &OutputId, mode: &VideoMode) -> Vec<u8> {
+    let mut out = Vec::with_capacity(29);
+    out.push(13);
+    write_le(&mut out, output.handler_pid);
+    write_le(&mut out, output.handler_id);
+    write_le(&mut out, mode.width);
+    write_le(&mut out, mode.height);
+    write_le(&mut out, mode.refresh_rate_mhz);
+    out
+}
+
+/// Decodes a message of type 13. Returns `None` if the message is malformed.
+pub fn decode_set_video_mode(message: &[u8]) -> Option<(OutputId, VideoMode)> {
+    if message.len() != 29 || message[0] != 13 {
+        return None;
+    }
+
+    let output = OutputId {
+        handler_pid: read_le(message, 1)?,
+        handler_id: read_le(message, 9)?,
+    };
+    let mode = VideoMode {
+        width: read_le(message, 17)?,
+        height: read_le(message, 21)?,
+        refresh_rate_mhz: read_le(message, 25)?,
+    };
+    Some((output, mode))
+}
+
+/// Returns the size in bytes of the RGB data of an area, or `None` if it overflows.
+fn update_data_len(width: u32, height: u32) -> Option<usize> {
+    usize::try_from(width)
+        .ok()?
+        .checked_mul(usize::try_from(height).ok()?)?
+        .checked_mul(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_batched_update, decode_set_video_mode, encode_batched_update, encode_set_video_mode,
+        FramebufferUpdate, OutputId, VideoMode,
+    };
+
+    #[test]
+    fn batched_update_encode_decode() {
+        let updates = [
+            FramebufferUpdate {
+                framebuffer_id: 12,
+                x: 3,
+                y: 4,
+                width: 2,
+                height: 1,
+                data: &[1, 2, 3, 4, 5, 6],
+            },
+            FramebufferUpdate {
+                framebuffer_id: 7,
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 5,
+                data: &[],
+            },
+        ];
+
+        let encoded = encode_batched_update(updates.iter().cloned());
+        assert_eq!(decode_batched_update(&encoded).unwrap(), updates);
+        assert!(decode_batched_update(&encoded[..encoded.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn set_video_mode_encode_decode() {
+        let output = OutputId {
+            handler_pid: 0x1122334455667788,
+            handler_id: 42,
+        };
+        let mode = VideoMode {
+            width: 1920,
+            height: 1080,
+            refresh_rate_mhz: 59940,
+        };
+
+        let encoded = encode_set_video_mode(&output, &mode);
+        assert_eq!(decode_set_video_mode(&encoded), Some((output, mode)));
+        assert!(decode_set_video_mode(&encoded[..encoded.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn set_video_mode_layout() {
+        let output = OutputId {
+            handler_pid: 1,
+            handler_id: 2,
+        };
+        let mode = VideoMode {
+            width: 3,
+            height: 4,
+            refresh_rate_mhz: 5,
+        };
+
+        let encoded = encode_set_video_mode(&output, &mode);
+        assert_eq!(
+            encoded,
+            [
+                13, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0, 5, 0,
+                0, 0
+            ]
+        );
+
+        // Other message types and trailing bytes are rejected.
+        let mut wrong_type = encoded.clone();
+        wrong_type[0] = 12;
+        assert!(decode_set_video_mode(&wrong_type).is_none());
+        let mut trailing = encoded;
+        trailing.push(0);
+        assert!(decode_set_video_mode(&trailing).is_none());
+    }
+}
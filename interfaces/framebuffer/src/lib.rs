@@ -24,7 +24,7 @@
 
 extern crate alloc;
 
-use alloc::collections::VecDeque;
+use alloc::{collections::VecDeque, vec::Vec};
 use core::convert::TryFrom as _;
 use redshirt_syscalls::{InterfaceHash, MessageId};
 
@@ -53,12 +53,10 @@ pub struct Framebuffer {
 
 impl Framebuffer {
     /// Initializes a new framebuffer of the given width and height.
-    pub async fn new(with_events: bool, width: u32, height: u32) -> Self {
-        let id = unsafe {
-            let mut out = [0; 4];
-            redshirt_random_interface::generate_in(&mut out).await;
-            u32::from_le_bytes(out)
-        };
+    ///
+    /// Returns an error if no identifier could be generated for the framebuffer.
+    pub async fn new(with_events: bool, width: u32, height: u32) -> Result<Self, ()> {
+        let id = redshirt_random_interface::generate_u32().await?;
 
         let interface = if with_events {
             &ffi::INTERFACE_WITH_EVENTS
@@ -89,7 +87,7 @@ impl Framebuffer {
             event_messages: VecDeque::with_capacity(num_events_queue),
         };
         fb.fill_event_messages();
-        fb
+        Ok(fb)
     }
 
     /// Sets the data in the framebuffer.
@@ -119,14 +117,130 @@ impl Framebuffer {
         }
     }
 
+    /// Sets the data of an area of the framebuffer.
+    ///
+    /// The size of `data` must be `width * height * 3`, and the area must be within the
+    /// framebuffer.
+    ///
+    /// To update multiple framebuffers, or multiple areas of the same framebuffer, at once, see
+    /// [`set_data_batched`].
+    pub fn set_area_data(&self, x: u32, y: u32, width: u32, height: u32, data: &[u8]) {
+        set_data_batched(&[FramebufferUpdate {
+            framebuffer: self,
+            x,
+            y,
+            width,
+            height,
+            data,
+        }])
+    }
+
+    /// Grabs or releases the pointer.
+    ///
+    /// While the pointer is grabbed, the cursor is locked and mouse movements are reported as
+    /// relative motions. See the documentation of the [`ffi`] module.
+    pub fn set_pointer_grab(&self, grab: bool) {
+        unsafe {
+            let id_le_bytes = self.id.to_le_bytes();
+            redshirt_syscalls::MessageBuilder::new()
+                .add_data_raw(if grab { &[4] } else { &[5] })
+                .add_data_raw(&id_le_bytes[..])
+                .emit_without_response(self.interface)
+                .unwrap();
+        }
+    }
+
+    /// Asks for the framebuffer to be given focus and brought to the front.
+    pub fn focus(&self) {
+        self.send_window_hint(6)
+    }
+
+    /// Asks for the framebuffer to be minimized.
+    pub fn minimize(&self) {
+        self.send_window_hint(7)
+    }
+
+    /// Asks for the framebuffer to be maximized.
+    pub fn maximize(&self) {
+        self.send_window_hint(8)
+    }
+
+    fn send_window_hint(&self, message_ty: u8) {
+        unsafe {
+            let id_le_bytes = self.id.to_le_bytes();
+            redshirt_syscalls::MessageBuilder::new()
+                .add_data_raw(&[message_ty])
+                .add_data_raw(&id_le_bytes[..])
+                .emit_without_response(self.interface)
+                .unwrap();
+        }
+    }
+
+    /// Waits until all the content previously passed to [`Framebuffer::set_data`],
+    /// [`Framebuffer::set_area_data`] or [`set_data_batched`] has been presented.
+    ///
+    /// Awaiting this before producing the next frame paces the rendering to the refresh rate of
+    /// the display. While the framebuffer isn't visible on any video output, for example because
+    /// it is minimized, the returned future doesn't resolve.
+    ///
+    /// Returns an error if the handler answered with an error or a malformed response.
+    pub async fn present_with_ack(&self) -> Result<(), ()> {
+        unsafe {
+            let id_le_bytes = self.id.to_le_bytes();
+            redshirt_syscalls::MessageBuilder::new()
+                .add_data_raw(&[12])
+                .add_data_raw(&id_le_bytes[..])
+                .emit_with_response::<()>(self.interface)
+                .unwrap()
+                .await
+                .map_err(|_| ())
+        }
+    }
+
+    /// Returns the video output the framebuffer is displayed on, or `None` if it isn't visible
+    /// on any video output.
+    ///
+    /// If the framebuffer spans multiple video outputs, the one where the largest area of the
+    /// framebuffer is visible is returned.
+    ///
+    /// Returns an error if the handler answered with an error or a malformed response.
+    pub async fn video_output(&self) -> Result<Option<ffi::OutputInfo>, ()> {
+        unsafe {
+            let id_le_bytes = self.id.to_le_bytes();
+            redshirt_syscalls::MessageBuilder::new()
+                .add_data_raw(&[11])
+                .add_data_raw(&id_le_bytes[..])
+                .emit_with_response::<Option<ffi::OutputInfo>>(self.interface)
+                .unwrap()
+                .await
+                .map_err(|_| ())
+        }
+    }
+
+    /// Returns the width and height of the framebuffer.
+    ///
+    /// The dimensions are updated when [`Framebuffer::next_event`] returns
+    /// [`ffi::Event::Resized`].
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
     /// Returns the next event that the framebuffer receives.
-    // TODO: proper return type
-    pub async fn next_event(&mut self) -> u32 {
+    ///
+    /// Returns an error if the handler has failed to report an event, for example because the
+    /// framebuffer no longer exists.
+    pub async fn next_event(&mut self) -> Result<ffi::Event, ()> {
         if let Some(first_event) = self.event_messages.front() {
-            let event: u32 = redshirt_syscalls::message_response(*first_event).await; // TODO: proper event type
+            let event: Result<ffi::Event, _> =
+                redshirt_syscalls::message_response(*first_event).await;
             self.event_messages.pop_front();
             self.fill_event_messages();
-            event
+            let event = event.map_err(|_| ())?;
+            if let ffi::Event::Resized { width, height } = event {
+                self.width = width;
+                self.height = height;
+            }
+            Ok(event)
         } else {
             futures::future::pending().await
         }
@@ -148,6 +262,91 @@ impl Framebuffer {
     }
 }
 
+/// Update of an area of a [`Framebuffer`]. Passed to [`set_data_batched`].
+#[derive(Clone)]
+pub struct FramebufferUpdate<'a> {
+    /// Framebuffer to update.
+    pub framebuffer: &'a Framebuffer,
+    /// Horizontal coordinate of the top-left hand corner of the area to update.
+    pub x: u32,
+    /// Vertical coordinate of the top-left hand corner of the area to update.
+    pub y: u32,
+    /// Width of the area to update.
+    pub width: u32,
+    /// Height of the area to update.
+    pub height: u32,
+    /// RGB triplets, row by row. The size must be `width * height * 3`.
+    pub data: &'a [u8],
+}
+
+/// Updates areas of multiple framebuffers in one message. All the updates are guaranteed to
+/// appear on the same frame.
+///
+/// # Panics
+///
+/// Panics if an area isn't within its framebuffer, if the size of the data doesn't match an
+/// area, or if the framebuffers don't all use the same interface.
+///
+pub fn set_data_batched(updates: &[FramebufferUpdate]) {
+    let interface = match updates.first() {
+        Some(update) => update.framebuffer.interface,
+        None => return,
+    };
+
+    for update in updates {
+        assert!(*update.framebuffer.interface == *interface);
+        assert!(update.x.saturating_add(update.width) <= update.framebuffer.width);
+        assert!(update.y.saturating_add(update.height) <= update.framebuffer.height);
+    }
+
+    let message = ffi::encode_batched_update(updates.iter().map(|update| ffi::FramebufferUpdate {
+        framebuffer_id: update.framebuffer.id,
+        x: update.x,
+        y: update.y,
+        width: update.width,
+        height: update.height,
+        data: update.data,
+    }));
+
+    unsafe {
+        redshirt_syscalls::MessageBuilder::new()
+            .add_data_raw(&message)
+            .emit_without_response(interface)
+            .unwrap();
+    }
+}
+
+/// Returns the list of video outputs that framebuffers can be displayed on.
+///
+/// Returns an error if the handler answered with an error or a malformed response.
+pub async fn video_outputs() -> Result<Vec<ffi::OutputInfo>, ()> {
+    unsafe {
+        redshirt_syscalls::MessageBuilder::new()
+            .add_data_raw(&[10])
+            .emit_with_response::<Vec<ffi::OutputInfo>>(&ffi::INTERFACE_WITH_EVENTS)
+            .unwrap()
+            .await
+            .map_err(|_| ())
+    }
+}
+
+/// Switches the video mode of a video output. The mode must be one of the modes found in
+/// [`ffi::OutputInfo::modes`].
+///
+/// Only allowed if one of the framebuffers of this process has focus and is displayed on this
+/// video output. Returns an error if that isn't the case, or if the video output doesn't exist
+/// or doesn't support this mode.
+pub async fn set_video_mode(output: ffi::OutputId, mode: ffi::VideoMode) -> Result<(), ()> {
+    unsafe {
+        redshirt_syscalls::MessageBuilder::new()
+            .add_data_raw(&ffi::encode_set_video_mode(&output, &mode))
+            .emit_with_response::<()>(&ffi::INTERFACE_WITH_EVENTS)
+            .unwrap()
+            .await
+            .map_err(|_| ())
+    }
+}
+
 impl Drop for Framebuffer {
     fn drop(&mut self) {
         unsafe {
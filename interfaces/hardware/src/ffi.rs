@@ -48,8 +48,9 @@ pub enum HardwareMessage {
     /// All operations must be performed in order.
     ///
     /// If there is at least one memory or port read, the response must be a
-    /// `Vec<HardwareAccessResponse>` where each element corresponds to a read. No response is
-    /// expected if there are only writes.
+    /// `Vec<HardwareAccessResponseEntry>` where each element corresponds to a read and indicates
+    /// the index within the list of operations of the read in question. No response is expected
+    /// if there are only writes.
     // TODO: should we enforce some limits in the amount of data that can be returned in a response?
     HardwareAccess(Vec<Operation>),
 
@@ -136,9 +137,35 @@ pub enum Operation {
     PortReadU32 {
         port: u32,
     },
+    /// Fills the memory with a repeating `u32`, using the platform's native endianess.
+    PhysicalMemoryMemsetU32 {
+        address: u64,
+        /// Number of `u32`s to write.
+        len: u64,
+        value: u32,
+    },
+    /// Reads 64 bits of data from a port.
+    ///
+    /// Since ports can't be accessed 64 bits at a time, this is performed as a 32 bits read of
+    /// `port` for the lower bits, followed with a 32 bits read of `port + 4` for the upper bits.
+    ///
+    /// If the hardware doesn't support this operation, then `0` is produced.
+    PortReadU64 {
+        port: u32,
+    },
+}
+
+/// Element of the response to a [`HardwareMessage::HardwareAccess`].
+#[derive(Debug, Encode, Decode)]
+pub struct HardwareAccessResponseEntry {
+    /// Index, within the list of operations of the [`HardwareMessage::HardwareAccess`], of the
+    /// read operation this entry corresponds to.
+    pub operation_index: u32,
+    /// Outcome of the read.
+    pub response: HardwareAccessResponse,
 }
 
-/// Response to a [`HardwareMessage::HardwareAccess`].
+/// Outcome of a read operation of a [`HardwareMessage::HardwareAccess`].
 #[derive(Debug, Encode, Decode)]
 pub enum HardwareAccessResponse {
     /// Sent back in response to a [`Operation::PhysicalMemoryReadU8`].
@@ -153,4 +180,6 @@ pub enum HardwareAccessResponse {
     PortReadU16(u16),
     /// Sent back in response to a [`Operation::PortReadU32`].
     PortReadU32(u32),
+    /// Sent back in response to a [`Operation::PortReadU64`].
+    PortReadU64(u64),
 }
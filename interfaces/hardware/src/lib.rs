@@ -22,7 +22,7 @@
 extern crate alloc;
 
 use alloc::{vec, vec::Vec};
-use core::convert::TryFrom as _;
+use core::{convert::TryFrom as _, fmt};
 use futures::prelude::*;
 
 pub mod ffi;
@@ -54,6 +54,16 @@ impl HardwareWriteOperationsBuilder {
         });
     }
 
+    /// Writes `len` times the given `u32` starting at the given address.
+    pub unsafe fn memset_u32(&mut self, address: u64, len: u64, value: u32) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryMemsetU32 {
+                address,
+                len,
+                value,
+            });
+    }
+
     pub unsafe fn write(&mut self, address: u64, data: impl Into<Vec<u8>>) {
         self.operations.push(ffi::Operation::PhysicalMemoryWriteU8 {
             address,
@@ -61,6 +71,24 @@ impl HardwareWriteOperationsBuilder {
         });
     }
 
+    /// Writes `u16`s starting at the given address, using the platform's native endianess.
+    pub unsafe fn write_u16(&mut self, address: u64, data: impl Into<Vec<u16>>) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryWriteU16 {
+                address,
+                data: data.into(),
+            });
+    }
+
+    /// Writes `u32`s starting at the given address, using the platform's native endianess.
+    pub unsafe fn write_u32(&mut self, address: u64, data: impl Into<Vec<u32>>) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryWriteU32 {
+                address,
+                data: data.into(),
+            });
+    }
+
     pub unsafe fn write_one_u32(&mut self, address: u64, data: u32) {
         self.operations
             .push(ffi::Operation::PhysicalMemoryWriteU32 {
@@ -104,31 +132,37 @@ pub unsafe fn write(address: u64, data: impl Into<Vec<u8>>) {
 }
 
 /// Reads memory to a buffer.
-pub async unsafe fn read_to(address: u64, mut out: &mut [u8]) {
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn read_to(address: u64, mut out: &mut [u8]) -> Result<(), ResponseMismatchError> {
     let mut ops = HardwareOperationsBuilder::new();
     ops.read(address, &mut out);
-    ops.send().await;
+    ops.send().await
 }
 
 /// Reads memory.
-pub async unsafe fn read(address: u64, len: u32) -> Vec<u8> {
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn read(address: u64, len: u32) -> Result<Vec<u8>, ResponseMismatchError> {
     let len_usize = usize::try_from(len).unwrap();
     let mut out = Vec::with_capacity(len_usize);
     out.set_len(len_usize);
     let mut ops = HardwareOperationsBuilder::new();
     ops.read(address, &mut out);
-    ops.send().await;
-    out
+    ops.send().await?;
+    Ok(out)
 }
 
 /// Reads a single `u32` from the given memory address.
+///
+/// Returns an error if the response of the handler doesn't match the request.
 #[cfg(feature = "std")]
-pub async unsafe fn read_one_u32(address: u64) -> u32 {
+pub async unsafe fn read_one_u32(address: u64) -> Result<u32, ResponseMismatchError> {
     let mut ops = HardwareOperationsBuilder::new();
     let mut out = [0];
     ops.read_u32(address, &mut out);
-    ops.send().await;
-    out[0]
+    ops.send().await?;
+    Ok(out[0])
 }
 
 pub unsafe fn write_one_u32(address: u64, data: u32) {
@@ -156,34 +190,55 @@ pub unsafe fn port_write_u32(port: u32, data: u32) {
 }
 
 /// Reads the given port.
-pub async unsafe fn port_read_u8(port: u32) -> u8 {
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn port_read_u8(port: u32) -> Result<u8, ResponseMismatchError> {
     let mut builder = HardwareOperationsBuilder::with_capacity(1);
     let mut out = 0;
     builder.port_read_u8(port, &mut out);
-    builder.send().await;
-    out
+    builder.send().await?;
+    Ok(out)
 }
 
-pub async unsafe fn port_read_u16(port: u32) -> u16 {
+/// Reads the given port.
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn port_read_u16(port: u32) -> Result<u16, ResponseMismatchError> {
     let mut builder = HardwareOperationsBuilder::with_capacity(1);
     let mut out = 0;
     builder.port_read_u16(port, &mut out);
-    builder.send().await;
-    out
+    builder.send().await?;
+    Ok(out)
 }
 
-pub async unsafe fn port_read_u32(port: u32) -> u32 {
+/// Reads the given port.
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn port_read_u32(port: u32) -> Result<u32, ResponseMismatchError> {
     let mut builder = HardwareOperationsBuilder::with_capacity(1);
     let mut out = 0;
     builder.port_read_u32(port, &mut out);
-    builder.send().await;
-    out
+    builder.send().await?;
+    Ok(out)
+}
+
+/// Reads 64 bits from the given port. See [`ffi::Operation::PortReadU64`].
+///
+/// Returns an error if the response of the handler doesn't match the request.
+pub async unsafe fn port_read_u64(port: u32) -> Result<u64, ResponseMismatchError> {
+    let mut builder = HardwareOperationsBuilder::with_capacity(1);
+    let mut out = 0;
+    builder.port_read_u64(port, &mut out);
+    builder.send().await?;
+    Ok(out)
 }
 
 /// Builder for read and write hardware operations.
 pub struct HardwareOperationsBuilder<'a> {
     operations: Vec<ffi::Operation>,
-    out: Vec<Out<'a>>,
+    /// For each read operation, its index within `operations` and where to write the result.
+    /// Always ordered by index. The `Option` is set to `None` once a response has been received.
+    out: Vec<(u32, Option<Out<'a>>)>,
 }
 
 enum Out<'a> {
@@ -193,6 +248,7 @@ enum Out<'a> {
     PortU8(&'a mut u8),
     PortU16(&'a mut u16),
     PortU32(&'a mut u32),
+    PortU64(&'a mut u64),
     Discard,
 }
 
@@ -211,31 +267,43 @@ impl<'a> HardwareOperationsBuilder<'a> {
         }
     }
 
+    fn push_read(&mut self, operation: ffi::Operation, out: Out<'a>) {
+        let index = u32::try_from(self.operations.len()).unwrap();
+        self.operations.push(operation);
+        self.out.push((index, Some(out)));
+    }
+
     pub unsafe fn read(&mut self, address: u64, out: &'a mut (impl ?Sized + AsMut<[u8]>)) {
         let out = out.as_mut();
-        self.operations.push(ffi::Operation::PhysicalMemoryReadU8 {
-            address,
-            len: out.len() as u32, // TODO: don't use `as`
-        });
-        self.out.push(Out::MemReadU8(out));
+        self.push_read(
+            ffi::Operation::PhysicalMemoryReadU8 {
+                address,
+                len: out.len() as u32, // TODO: don't use `as`
+            },
+            Out::MemReadU8(out),
+        );
     }
 
     pub unsafe fn read_u16(&mut self, address: u64, out: &'a mut (impl ?Sized + AsMut<[u16]>)) {
         let out = out.as_mut();
-        self.operations.push(ffi::Operation::PhysicalMemoryReadU16 {
-            address,
-            len: out.len() as u32, // TODO: don't use `as`
-        });
-        self.out.push(Out::MemReadU16(out));
+        self.push_read(
+            ffi::Operation::PhysicalMemoryReadU16 {
+                address,
+                len: out.len() as u32, // TODO: don't use `as`
+            },
+            Out::MemReadU16(out),
+        );
     }
 
     pub unsafe fn read_u32(&mut self, address: u64, out: &'a mut (impl ?Sized + AsMut<[u32]>)) {
         let out = out.as_mut();
-        self.operations.push(ffi::Operation::PhysicalMemoryReadU32 {
-            address,
-            len: out.len() as u32, // TODO: don't use `as`
-        });
-        self.out.push(Out::MemReadU32(out));
+        self.push_read(
+            ffi::Operation::PhysicalMemoryReadU32 {
+                address,
+                len: out.len() as u32, // TODO: don't use `as`
+            },
+            Out::MemReadU32(out),
+        );
     }
 
     pub unsafe fn memset(&mut self, address: u64, len: u64, value: u8) {
@@ -246,6 +314,16 @@ impl<'a> HardwareOperationsBuilder<'a> {
         });
     }
 
+    /// Writes `len` times the given `u32` starting at the given address.
+    pub unsafe fn memset_u32(&mut self, address: u64, len: u64, value: u32) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryMemsetU32 {
+                address,
+                len,
+                value,
+            });
+    }
+
     pub unsafe fn write(&mut self, address: u64, data: impl Into<Vec<u8>>) {
         self.operations.push(ffi::Operation::PhysicalMemoryWriteU8 {
             address,
@@ -253,6 +331,24 @@ impl<'a> HardwareOperationsBuilder<'a> {
         });
     }
 
+    /// Writes `u16`s starting at the given address, using the platform's native endianess.
+    pub unsafe fn write_u16(&mut self, address: u64, data: impl Into<Vec<u16>>) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryWriteU16 {
+                address,
+                data: data.into(),
+            });
+    }
+
+    /// Writes `u32`s starting at the given address, using the platform's native endianess.
+    pub unsafe fn write_u32(&mut self, address: u64, data: impl Into<Vec<u32>>) {
+        self.operations
+            .push(ffi::Operation::PhysicalMemoryWriteU32 {
+                address,
+                data: data.into(),
+            });
+    }
+
     pub unsafe fn write_one_u32(&mut self, address: u64, data: u32) {
         self.operations
             .push(ffi::Operation::PhysicalMemoryWriteU32 {
@@ -277,72 +373,233 @@ impl<'a> HardwareOperationsBuilder<'a> {
     }
 
     pub unsafe fn port_read_u8(&mut self, port: u32, out: &'a mut u8) {
-        self.operations.push(ffi::Operation::PortReadU8 { port });
-        self.out.push(Out::PortU8(out));
+        self.push_read(ffi::Operation::PortReadU8 { port }, Out::PortU8(out));
     }
 
     pub unsafe fn port_read_u16(&mut self, port: u32, out: &'a mut u16) {
-        self.operations.push(ffi::Operation::PortReadU16 { port });
-        self.out.push(Out::PortU16(out));
+        self.push_read(ffi::Operation::PortReadU16 { port }, Out::PortU16(out));
     }
 
     pub unsafe fn port_read_u32(&mut self, port: u32, out: &'a mut u32) {
-        self.operations.push(ffi::Operation::PortReadU32 { port });
-        self.out.push(Out::PortU32(out));
+        self.push_read(ffi::Operation::PortReadU32 { port }, Out::PortU32(out));
+    }
+
+    /// Reads 64 bits from the given port. See [`ffi::Operation::PortReadU64`].
+    pub unsafe fn port_read_u64(&mut self, port: u32, out: &'a mut u64) {
+        self.push_read(ffi::Operation::PortReadU64 { port }, Out::PortU64(out));
     }
 
     pub unsafe fn port_read_u8_discard(&mut self, port: u32) {
-        self.operations.push(ffi::Operation::PortReadU8 { port });
-        self.out.push(Out::Discard);
+        self.push_read(ffi::Operation::PortReadU8 { port }, Out::Discard);
     }
 
     pub unsafe fn port_read_u16_discard(&mut self, port: u32) {
-        self.operations.push(ffi::Operation::PortReadU16 { port });
-        self.out.push(Out::Discard);
+        self.push_read(ffi::Operation::PortReadU16 { port }, Out::Discard);
     }
 
     pub unsafe fn port_read_u32_discard(&mut self, port: u32) {
-        self.operations.push(ffi::Operation::PortReadU32 { port });
-        self.out.push(Out::Discard);
+        self.push_read(ffi::Operation::PortReadU32 { port }, Out::Discard);
+    }
+
+    pub unsafe fn port_read_u64_discard(&mut self, port: u32) {
+        self.push_read(ffi::Operation::PortReadU64 { port }, Out::Discard);
     }
 
-    pub fn send(self) -> impl Future<Output = ()> + 'a {
+    /// Sends the operations to the handler.
+    ///
+    /// The returned future produces an error if the response doesn't match the read operations
+    /// that have been requested, in which case the content of the output buffers is unspecified.
+    pub fn send(self) -> impl Future<Output = Result<(), ResponseMismatchError>> + 'a {
         unsafe {
             let msg = ffi::HardwareMessage::HardwareAccess(self.operations);
-            let out = self.out;
+            let mut out = self.out;
             redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
                 .unwrap()
-                .then(move |response: Vec<ffi::HardwareAccessResponse>| {
-                    for (response_elem, out) in response.into_iter().zip(out) {
-                        match (response_elem, out) {
-                            (_, Out::Discard) => {}
-                            (ffi::HardwareAccessResponse::PortReadU8(val), Out::PortU8(out)) => {
-                                *out = val
-                            }
-                            (ffi::HardwareAccessResponse::PortReadU16(val), Out::PortU16(out)) => {
-                                *out = val
-                            }
-                            (ffi::HardwareAccessResponse::PortReadU32(val), Out::PortU32(out)) => {
-                                *out = val
-                            }
-                            (
-                                ffi::HardwareAccessResponse::PhysicalMemoryReadU8(val),
-                                Out::MemReadU8(out),
-                            ) => out.copy_from_slice(&val),
-                            (
-                                ffi::HardwareAccessResponse::PhysicalMemoryReadU16(val),
-                                Out::MemReadU16(out),
-                            ) => out.copy_from_slice(&val),
-                            (
-                                ffi::HardwareAccessResponse::PhysicalMemoryReadU32(val),
-                                Out::MemReadU32(out),
-                            ) => out.copy_from_slice(&val),
-                            _ => unreachable!(),
-                        }
-                    }
-
-                    future::ready(())
+                .then(move |response| {
+                    future::ready(match response {
+                        Ok(response) => dispatch_response(response, &mut out),
+                        Err(_) => Err(ResponseMismatchError::Undecodable),
+                    })
                 })
         }
     }
 }
+
+/// Writes each entry of `response` to the corresponding element of `out`.
+fn dispatch_response(
+    response: Vec<ffi::HardwareAccessResponseEntry>,
+    out: &mut [(u32, Option<Out>)],
+) -> Result<(), ResponseMismatchError> {
+    for entry in response {
+        let out = match out.binary_search_by_key(&entry.operation_index, |(idx, _)| *idx) {
+            Ok(pos) => out[pos].1.take(),
+            Err(_) => None,
+        };
+
+        match (entry.response, out) {
+            (_, Some(Out::Discard)) => {}
+            (ffi::HardwareAccessResponse::PortReadU8(val), Some(Out::PortU8(out))) => *out = val,
+            (ffi::HardwareAccessResponse::PortReadU16(val), Some(Out::PortU16(out))) => *out = val,
+            (ffi::HardwareAccessResponse::PortReadU32(val), Some(Out::PortU32(out))) => *out = val,
+            (ffi::HardwareAccessResponse::PortReadU64(val), Some(Out::PortU64(out))) => *out = val,
+            (ffi::HardwareAccessResponse::PhysicalMemoryReadU8(val), Some(Out::MemReadU8(out)))
+                if val.len() == out.len() =>
+            {
+                out.copy_from_slice(&val)
+            }
+            (
+                ffi::HardwareAccessResponse::PhysicalMemoryReadU16(val),
+                Some(Out::MemReadU16(out)),
+            ) if val.len() == out.len() => out.copy_from_slice(&val),
+            (
+                ffi::HardwareAccessResponse::PhysicalMemoryReadU32(val),
+                Some(Out::MemReadU32(out)),
+            ) if val.len() == out.len() => out.copy_from_slice(&val),
+            _ => {
+                return Err(ResponseMismatchError::UnexpectedEntry(
+                    entry.operation_index,
+                ))
+            }
+        }
+    }
+
+    if let Some((index, _)) = out.iter().find(|(_, o)| o.is_some()) {
+        return Err(ResponseMismatchError::MissingEntry(*index));
+    }
+
+    Ok(())
+}
+
+/// Error that can happen when the response to a [`HardwareOperationsBuilder`] doesn't match
+/// the operations that have been sent.
+#[derive(Debug)]
+pub enum ResponseMismatchError {
+    /// The response contains an entry that doesn't correspond to a read operation, whose type
+    /// or length doesn't match the operation, or that has already been answered.
+    UnexpectedEntry(u32),
+    /// The response doesn't contain any entry for the read operation with the given index.
+    MissingEntry(u32),
+    /// The response couldn't be decoded.
+    Undecodable,
+}
+
+impl fmt::Display for ResponseMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseMismatchError::UnexpectedEntry(idx) => {
+                write!(f, "Unexpected response entry for operation #{}", idx)
+            }
+            ResponseMismatchError::MissingEntry(idx) => {
+                write!(f, "Missing response entry for operation #{}", idx)
+            }
+            ResponseMismatchError::Undecodable => write!(f, "Failed to decode response"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        operation_index: u32,
+        response: ffi::HardwareAccessResponse,
+    ) -> ffi::HardwareAccessResponseEntry {
+        ffi::HardwareAccessResponseEntry {
+            operation_index,
+            response,
+        }
+    }
+
+    #[test]
+    fn matching_response_fills_outputs() {
+        let mut mem = [0u8; 3];
+        let mut port = 0u16;
+        let mut port64 = 0u64;
+        let response = vec![
+            entry(2, ffi::HardwareAccessResponse::PortReadU16(0x1234)),
+            entry(3, ffi::HardwareAccessResponse::PortReadU64(0x1_0000_0002)),
+            entry(
+                0,
+                ffi::HardwareAccessResponse::PhysicalMemoryReadU8(vec![1, 2, 3]),
+            ),
+            entry(1, ffi::HardwareAccessResponse::PortReadU8(5)),
+        ];
+
+        dispatch_response(
+            response,
+            &mut [
+                (0, Some(Out::MemReadU8(&mut mem))),
+                (1, Some(Out::Discard)),
+                (2, Some(Out::PortU16(&mut port))),
+                (3, Some(Out::PortU64(&mut port64))),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(mem, [1, 2, 3]);
+        assert_eq!(port, 0x1234);
+        assert_eq!(port64, 0x1_0000_0002);
+    }
+
+    #[test]
+    fn mismatched_response_rejected() {
+        // Wrong type of read.
+        let mut port = 0u8;
+        let response = vec![entry(0, ffi::HardwareAccessResponse::PortReadU32(1))];
+        assert!(matches!(
+            dispatch_response(response, &mut [(0, Some(Out::PortU8(&mut port)))]),
+            Err(ResponseMismatchError::UnexpectedEntry(0))
+        ));
+
+        // Wrong length.
+        let mut mem = [0u32; 2];
+        let response = vec![entry(
+            0,
+            ffi::HardwareAccessResponse::PhysicalMemoryReadU32(vec![1, 2, 3]),
+        )];
+        assert!(matches!(
+            dispatch_response(response, &mut [(0, Some(Out::MemReadU32(&mut mem)))]),
+            Err(ResponseMismatchError::UnexpectedEntry(0))
+        ));
+
+        // Unknown operation index.
+        let mut port = 0u8;
+        let response = vec![
+            entry(0, ffi::HardwareAccessResponse::PortReadU8(1)),
+            entry(7, ffi::HardwareAccessResponse::PortReadU8(2)),
+        ];
+        assert!(matches!(
+            dispatch_response(response, &mut [(0, Some(Out::PortU8(&mut port)))]),
+            Err(ResponseMismatchError::UnexpectedEntry(7))
+        ));
+
+        // Same operation answered twice.
+        let mut port = 0u8;
+        let response = vec![
+            entry(0, ffi::HardwareAccessResponse::PortReadU8(1)),
+            entry(0, ffi::HardwareAccessResponse::PortReadU8(2)),
+        ];
+        assert!(matches!(
+            dispatch_response(response, &mut [(0, Some(Out::PortU8(&mut port)))]),
+            Err(ResponseMismatchError::UnexpectedEntry(0))
+        ));
+    }
+
+    #[test]
+    fn missing_entry_rejected() {
+        let mut port1 = 0u8;
+        let mut port2 = 0u32;
+        let response = vec![entry(0, ffi::HardwareAccessResponse::PortReadU8(1))];
+        assert!(matches!(
+            dispatch_response(
+                response,
+                &mut [
+                    (0, Some(Out::PortU8(&mut port1))),
+                    (3, Some(Out::PortU32(&mut port2))),
+                ]
+            ),
+            Err(ResponseMismatchError::MissingEntry(3))
+        ));
+    }
+}
@@ -18,7 +18,9 @@
 //! There are situations where it is necessary to pass to a device a pointer to a region of
 //! memory. This is where this module comes into play.
 
-use crate::{ffi, HardwareOperationsBuilder, HardwareWriteOperationsBuilder};
+use crate::{
+    ffi, HardwareOperationsBuilder, HardwareWriteOperationsBuilder, Out, ResponseMismatchError,
+};
 
 use alloc::{boxed::Box, vec, vec::Vec};
 use core::{convert::TryFrom, marker::PhantomData, mem, ptr, slice};
@@ -41,11 +43,12 @@ impl<T> PhysicalBuffer<T> {
     /// >           if you called `std::mem::forget(data)`. You should preferably not pass anything
     /// >           else than plain data, or call [`PhysicalBuffer::take`].
     ///
-    pub fn new(data: T) -> impl Future<Output = Self> {
+    /// Returns an error if the allocation fails. See [`malloc`].
+    pub fn new(data: T) -> impl Future<Output = Result<Self, ()>> {
         let size = u64::try_from(mem::size_of_val(&data)).unwrap();
         let align = u64::try_from(mem::align_of_val(&data)).unwrap();
 
-        malloc(size, align).map(move |ptr| {
+        malloc(size, align).map_ok(move |ptr| {
             let buf = PhysicalBuffer {
                 ptr,
                 size,
@@ -73,12 +76,18 @@ impl<T> PhysicalBuffer<T> {
     }
 
     /// Reads back the content of the buffer and destroys the buffer.
-    pub fn take(self) -> impl Future<Output = T> {
+    ///
+    /// Returns an error if the handler of the interface answered with an error or a malformed
+    /// response.
+    pub fn take(self) -> impl Future<Output = Result<T, ()>> {
         unsafe { self.read_inner() }
     }
 
     /// Returns a copy of the content of the buffer.
-    pub fn read(&self) -> impl Future<Output = T>
+    ///
+    /// Returns an error if the handler of the interface answered with an error or a malformed
+    /// response.
+    pub fn read(&self) -> impl Future<Output = Result<T, ()>>
     where
         T: Copy,
     {
@@ -93,7 +102,7 @@ impl<T> PhysicalBuffer<T> {
     /// implements `Copy`, or if you guarantee that no multiple copies of the same object are
     /// being read. In other words, this function is meant to be called from within
     /// [`PhysicalBuffer::take`] or [`PhysicalBuffer::read`].
-    unsafe fn read_inner(&self) -> impl Future<Output = T> {
+    unsafe fn read_inner(&self) -> impl Future<Output = Result<T, ()>> {
         // Note: we can't use `HardwareOperationsBuilder`, as this would require an `async`
         // function or block, which aren't available in `no_std` environments at the time of
         // writing.
@@ -106,39 +115,42 @@ impl<T> PhysicalBuffer<T> {
 
         redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
             .unwrap()
-            .map(move |mut response: Vec<ffi::HardwareAccessResponse>| {
-                debug_assert_eq!(response.len(), 1);
-                let buf = match response.remove(0) {
-                    ffi::HardwareAccessResponse::PhysicalMemoryReadU8(val) => val,
-                    _ => unreachable!(),
-                };
-                ptr::read_unaligned(buf.as_ptr() as *const T)
+            .map(move |response| {
+                let response: Vec<ffi::HardwareAccessResponseEntry> = response.map_err(|_| ())?;
+                let mut buf = vec![0; mem::size_of::<T>()];
+                crate::dispatch_response(response, &mut [(0, Some(Out::MemReadU8(&mut buf)))])
+                    .map_err(|_| ())?;
+                Ok(ptr::read_unaligned(buf.as_ptr() as *const T))
             })
     }
 }
 
 impl<T> PhysicalBuffer<[T]> {
     /// Allocates a new buffer with uninitialized contents.
-    pub async fn new_uninit_slice(len: usize) -> PhysicalBuffer<[mem::MaybeUninit<T>]> {
+    ///
+    /// Returns an error if the allocation fails. See [`malloc`].
+    pub async fn new_uninit_slice(len: usize) -> Result<PhysicalBuffer<[mem::MaybeUninit<T>]>, ()> {
         Self::new_uninit_slice_with_align(len, mem::align_of::<T>()).await
     }
 
     /// Allocates a new buffer with uninitialized contents.
+    ///
+    /// Returns an error if the allocation fails. See [`malloc`].
     pub async fn new_uninit_slice_with_align(
         len: usize,
         align: usize,
-    ) -> PhysicalBuffer<[mem::MaybeUninit<T>]> {
+    ) -> Result<PhysicalBuffer<[mem::MaybeUninit<T>]>, ()> {
         let size = u64::try_from(mem::size_of::<T>())
             .unwrap()
             .checked_mul(u64::try_from(len).unwrap())
             .unwrap();
         let align = u64::try_from(align).unwrap();
 
-        PhysicalBuffer {
-            ptr: malloc(size, align).await,
+        Ok(PhysicalBuffer {
+            ptr: malloc(size, align).await?,
             size,
             marker: PhantomData,
-        }
+        })
     }
 
     /// Returns the number of elements in the buffer.
@@ -166,10 +178,13 @@ impl<T> PhysicalBuffer<[T]> {
 }
 
 impl<T: Copy> PhysicalBuffer<[T]> {
-    pub async fn read_one(&self, idx: usize) -> Option<T> {
+    /// Returns a copy of the element at the given index, or `None` if the index is out of range.
+    ///
+    /// Returns an error if the response of the handler doesn't match the request.
+    pub async fn read_one(&self, idx: usize) -> Result<Option<T>, ResponseMismatchError> {
         unsafe {
             if idx >= self.len() {
-                return None;
+                return Ok(None);
             }
 
             let mut out = mem::MaybeUninit::<T>::uninit();
@@ -179,13 +194,22 @@ impl<T: Copy> PhysicalBuffer<[T]> {
                 self.ptr + u64::try_from(idx * mem::size_of::<T>()).unwrap(),
                 slice::from_raw_parts_mut(out.as_mut_ptr() as *mut u8, mem::size_of::<T>()),
             );
-            ops.send().await;
+            ops.send().await?;
 
-            Some(out.assume_init())
+            Ok(Some(out.assume_init()))
         }
     }
 
-    pub async fn read_slice(&self, idx: usize, out: &mut [T]) {
+    /// Copies the elements starting at the given index to `out`.
+    ///
+    /// Returns an error if the response of the handler doesn't match the request, in which case
+    /// the content of `out` is unspecified.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the range is out of bounds.
+    ///
+    pub async fn read_slice(&self, idx: usize, out: &mut [T]) -> Result<(), ResponseMismatchError> {
         unsafe {
             assert!(idx + out.len() <= self.len());
 
@@ -197,7 +221,7 @@ impl<T: Copy> PhysicalBuffer<[T]> {
                     out.len() * mem::size_of::<T>(),
                 ),
             );
-            ops.send().await;
+            ops.send().await
         }
     }
 
@@ -246,19 +270,20 @@ impl<T: ?Sized> Drop for PhysicalBuffer<T> {
 
 /// Allocates physical memory.
 ///
-/// # Panic
-///
-/// Panics if the allocation fails, for example if `size` is too large to be acceptable.
-///
-pub fn malloc(size: u64, alignment: u64) -> impl Future<Output = u64> {
+/// Returns an error if the allocation fails, for example if `size` is too large to be
+/// acceptable, or if the handler of the interface answered with a malformed response.
+pub fn malloc(size: u64, alignment: u64) -> impl Future<Output = Result<u64, ()>> {
     unsafe {
         let msg = ffi::HardwareMessage::Malloc { size, alignment };
         redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
             .unwrap()
-            .map(move |ptr: u64| {
-                assert_ne!(ptr, 0);
+            .map(move |ptr| {
+                let ptr: u64 = ptr.map_err(|_| ())?;
+                if ptr == 0 {
+                    return Err(());
+                }
                 debug_assert_eq!(ptr % alignment, 0);
-                ptr
+                Ok(ptr)
             })
     }
 }
@@ -23,11 +23,30 @@ pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
     0x7d, 0xd5, 0x70, 0x92, 0x4d, 0x4f, 0x70, 0xdf, 0xb3, 0xda, 0xf6, 0xfe, 0xdc, 0x65, 0x93, 0x8a,
 ]);
 
+/// Maximum length, in bytes, of the metadata passed to [`InterfaceMessage::Register`] and
+/// [`InterfaceMessage::RegisterOrWait`].
+pub const MAX_METADATA_LEN: usize = 1024;
+
 #[derive(Debug, parity_scale_codec::Encode, parity_scale_codec::Decode)]
 pub enum InterfaceMessage {
-    Register(InterfaceHash),
+    /// Registers the emitter as the handler of the given interface.
+    ///
+    /// The `Vec<u8>` is an opaque metadata blob, for example describing the capabilities of this
+    /// implementation of the interface, that is later returned to processes that send a
+    /// [`InterfaceMessage::QueryHandler`].
+    Register(InterfaceHash, Vec<u8>),
     NextMessage(NonZeroU64),
     Answer(MessageId, Result<Vec<u8>, ()>),
+    /// Requests that the given registration receives, through its `NextMessage` answers, a
+    /// notification whenever a handler registers itself for any interface.
+    WatchRegistrations(NonZeroU64),
+    /// Same as [`InterfaceMessage::Register`], except that if the interface already has a
+    /// handler, the answer is delayed until that handler's process has ended, at which point
+    /// the emitter becomes the new handler.
+    RegisterOrWait(InterfaceHash, Vec<u8>),
+    /// Requests information about the current handler of the given interface. Must be answered
+    /// with a [`QueryHandlerResponse`].
+    QueryHandler(InterfaceHash),
 }
 
 #[derive(Debug, parity_scale_codec::Encode, parity_scale_codec::Decode)]
@@ -35,10 +54,28 @@ pub struct InterfaceRegisterResponse {
     pub result: Result<NonZeroU64, InterfaceRegisterError>,
 }
 
+#[derive(Debug, Clone, parity_scale_codec::Encode, parity_scale_codec::Decode)]
+pub struct QueryHandlerResponse {
+    /// Information about the handler, or `None` if the interface has no handler.
+    pub handler: Option<HandlerInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, parity_scale_codec::Encode, parity_scale_codec::Decode)]
+pub struct HandlerInfo {
+    /// Process that handles the interface.
+    pub pid: Pid,
+    /// Metadata that the handler has passed when registering.
+    pub metadata: Vec<u8>,
+}
+
 #[derive(Debug, Clone, parity_scale_codec::Encode, parity_scale_codec::Decode)]
 pub enum InterfaceRegisterError {
     /// There already exists a process registered for this interface.
     AlreadyRegistered,
+    /// The process isn't allowed to register this interface.
+    Unauthorized,
+    /// The metadata is longer than [`MAX_METADATA_LEN`].
+    MetadataTooLarge,
 }
 
 /// Either a decoded interface notification or a decoded process destroyed notification.
@@ -48,6 +85,9 @@ pub enum DecodedInterfaceOrDestroyed {
     Interface(DecodedInterfaceNotification),
     /// Process destroyed notification.
     ProcessDestroyed(DecodedProcessDestroyedNotification),
+    /// Interface registered notification. Only ever received by registrations that have sent a
+    /// [`InterfaceMessage::WatchRegistrations`], and can otherwise be ignored.
+    InterfaceRegistered(DecodedInterfaceRegisteredNotification),
 }
 
 /// Attempt to decode a notification.
@@ -60,6 +100,8 @@ pub fn decode_notification(buffer: &[u8]) -> Result<DecodedInterfaceOrDestroyed,
         0 => decode_interface_notification(buffer).map(DecodedInterfaceOrDestroyed::Interface),
         2 => decode_process_destroyed_notification(buffer)
             .map(DecodedInterfaceOrDestroyed::ProcessDestroyed),
+        3 => decode_interface_registered_notification(buffer)
+            .map(DecodedInterfaceOrDestroyed::InterfaceRegistered),
         _ => Err(()),
     }
 }
@@ -207,3 +249,72 @@ pub struct DecodedProcessDestroyedNotification {
     /// Identifier of the process that got destroyed.
     pub pid: Pid,
 }
+
+pub fn build_interface_registered_notification(
+    interface: &InterfaceHash,
+) -> InterfaceRegisteredNotificationBuilder {
+    let mut buffer = Vec::with_capacity(1 + 32);
+    buffer.push(3);
+    buffer.extend_from_slice(interface.as_ref());
+
+    debug_assert_eq!(buffer.capacity(), buffer.len());
+    InterfaceRegisteredNotificationBuilder { data: buffer }
+}
+
+#[derive(Debug, Clone)]
+pub struct InterfaceRegisteredNotificationBuilder {
+    data: Vec<u8>,
+}
+
+impl InterfaceRegisteredNotificationBuilder {
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub fn decode_interface_registered_notification(
+    buffer: &[u8],
+) -> Result<DecodedInterfaceRegisteredNotification, ()> {
+    if buffer.len() != 1 + 32 {
+        return Err(());
+    }
+
+    if buffer[0] != 0x3 {
+        return Err(());
+    }
+
+    Ok(DecodedInterfaceRegisteredNotification {
+        interface: InterfaceHash::from({
+            let mut hash = [0; 32];
+            hash.copy_from_slice(&buffer[1..33]);
+            hash
+        }),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedInterfaceRegisteredNotification {
+    /// Interface that now has a handler.
+    pub interface: InterfaceHash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interface_registered_encode_decode() {
+        let interface = InterfaceHash::from_raw_hash([0xab; 32]);
+        let encoded = build_interface_registered_notification(&interface).into_bytes();
+        assert_eq!(
+            decode_notification(&encoded),
+            Ok(DecodedInterfaceOrDestroyed::InterfaceRegistered(
+                DecodedInterfaceRegisteredNotification { interface }
+            ))
+        );
+    }
+}
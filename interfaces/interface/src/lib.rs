@@ -19,11 +19,12 @@
 
 extern crate alloc;
 
-use core::{mem, num::NonZeroU64};
+use alloc::vec::Vec;
+use core::{cmp, mem, num::NonZeroU64, time::Duration};
 use futures::prelude::*;
 use redshirt_syscalls::{Encode, EncodedMessage, InterfaceHash, MessageId};
 
-pub use ffi::{DecodedInterfaceOrDestroyed, InterfaceRegisterError};
+pub use ffi::{DecodedInterfaceOrDestroyed, HandlerInfo, InterfaceRegisterError};
 
 pub mod ffi;
 
@@ -32,48 +33,198 @@ pub mod ffi;
 /// > **Note**: Interface hashes can be found in the various `ffi` modules of the crates in the
 /// >           `interfaces` directory, although that is subject to change.
 ///
-/// Returns an error if there was already a program registered for that interface.
+/// Returns an error if there was already a program registered for that interface. Use
+/// [`register_interface_with_config`] in order to wait for that program to go away instead.
 pub async fn register_interface(
     hash: InterfaceHash,
 ) -> Result<Registration, InterfaceRegisterError> {
-    let msg = ffi::InterfaceMessage::Register(hash);
-    // Unwrapping is ok because there's always something that handles interface registration.
+    register_interface_with_config(hash, RegistrationConfig::default()).await
+}
+
+/// Same as [`register_interface`], but allows configuring the returned [`Registration`].
+///
+/// # Panic
+///
+/// Panics if `config.min_pending_messages` is 0 or superior to `config.max_pending_messages`.
+///
+pub async fn register_interface_with_config(
+    hash: InterfaceHash,
+    config: RegistrationConfig,
+) -> Result<Registration, InterfaceRegisterError> {
+    assert!(config.min_pending_messages >= 1);
+    assert!(config.min_pending_messages <= config.max_pending_messages);
+
+    let msg = match config.on_conflict {
+        OnRegistrationConflict::Fail => ffi::InterfaceMessage::Register(hash, config.metadata),
+        OnRegistrationConflict::Wait => {
+            ffi::InterfaceMessage::RegisterOrWait(hash, config.metadata)
+        }
+    };
+    // Unwrapping is ok because there's always something that handles interface registration,
+    // and that something is the kernel.
     let id = {
         let msg: ffi::InterfaceRegisterResponse =
             unsafe { redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg) }
                 .unwrap()
-                .await;
+                .await
+                .unwrap();
         msg.result?
     };
 
     let mut registration = Registration {
         id,
         messages: stream::FuturesOrdered::new(),
+        target_pending_messages: cmp::min(
+            cmp::max(32, config.min_pending_messages),
+            config.max_pending_messages,
+        ),
+        min_pending_messages: config.min_pending_messages,
+        max_pending_messages: config.max_pending_messages,
+        immediately_available_streak: 0,
     };
 
-    for _ in 0..32 {
+    for _ in 0..registration.target_pending_messages {
         registration.add_message();
     }
 
     Ok(registration)
 }
 
+/// Returns the process currently handling the given interface and the metadata it has provided
+/// when registering, or `None` if the interface has no handler.
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub async fn query_handler(hash: InterfaceHash) -> Result<Option<HandlerInfo>, ()> {
+    // Unwrapping is ok because there's always something that handles the interface interface.
+    let response: ffi::QueryHandlerResponse = unsafe {
+        redshirt_syscalls::emit_message_with_response(
+            &ffi::INTERFACE,
+            ffi::InterfaceMessage::QueryHandler(hash),
+        )
+    }
+    .unwrap()
+    .await
+    .map_err(|_| ())?;
+    Ok(response.handler)
+}
+
+/// Configuration for [`register_interface_with_config`].
+///
+/// A [`Registration`] keeps a pool of `NextMessage` requests in flight towards the kernel, each
+/// of them being answered with a message emitted on the interface. The size of this pool adapts
+/// to the load: it grows when messages are consistently already available when asked for, and
+/// shrinks when waiting for messages.
+#[derive(Debug, Clone)]
+pub struct RegistrationConfig {
+    /// Minimum number of `NextMessage` requests kept in flight. Must be at least 1.
+    pub min_pending_messages: usize,
+    /// Maximum number of `NextMessage` requests kept in flight.
+    pub max_pending_messages: usize,
+    /// What to do if another program is already registered for the interface.
+    pub on_conflict: OnRegistrationConflict,
+    /// Opaque data returned by [`query_handler`]. Can be used to advertise, for example, which
+    /// optional features of the interface this handler supports.
+    ///
+    /// Registering fails with [`InterfaceRegisterError::MetadataTooLarge`] if this is longer
+    /// than [`ffi::MAX_METADATA_LEN`].
+    pub metadata: Vec<u8>,
+}
+
+/// See [`RegistrationConfig::on_conflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnRegistrationConflict {
+    /// Immediately return an [`InterfaceRegisterError::AlreadyRegistered`] error.
+    Fail,
+    /// Wait until the process of the current handler has ended, then become the handler.
+    ///
+    /// > **Note**: If multiple programs are waiting, they are registered in the order in which
+    /// >           they have started waiting.
+    Wait,
+}
+
+impl Default for RegistrationConfig {
+    fn default() -> Self {
+        RegistrationConfig {
+            min_pending_messages: 4,
+            max_pending_messages: 256,
+            on_conflict: OnRegistrationConflict::Fail,
+            metadata: Vec::new(),
+        }
+    }
+}
+
 /// Registered interface.
 pub struct Registration {
     /// Identifier of the interface registration.
     id: NonZeroU64,
     /// Futures that will resolve when a message is received on the interface.
     messages: stream::FuturesOrdered<redshirt_syscalls::MessageResponseFuture<EncodedMessage>>,
+    /// Number of entries that [`Registration::messages`] should contain.
+    target_pending_messages: usize,
+    /// See [`RegistrationConfig::min_pending_messages`].
+    min_pending_messages: usize,
+    /// See [`RegistrationConfig::max_pending_messages`].
+    max_pending_messages: usize,
+    /// Number of messages in a row that were already available when requested.
+    immediately_available_streak: usize,
 }
 
 impl Registration {
     /// Returns the next message received on this interface.
     pub async fn next_message_raw(&mut self) -> DecodedInterfaceOrDestroyed {
-        let message = self.messages.next().await.unwrap();
-        self.add_message();
+        let mut had_to_wait = false;
+        let message = future::poll_fn(|cx| {
+            let poll = self.messages.poll_next_unpin(cx);
+            if poll.is_pending() {
+                had_to_wait = true;
+            }
+            poll
+        })
+        .await
+        .unwrap()
+        // The kernel only rejects our message if the registration ID is invalid.
+        .unwrap();
+
+        if had_to_wait {
+            // The pool is larger than what the load requires.
+            self.immediately_available_streak = 0;
+            self.target_pending_messages =
+                cmp::max(self.target_pending_messages - 1, self.min_pending_messages);
+        } else {
+            // If a whole pool worth of messages was available without waiting, it is likely that
+            // messages are queuing up in the kernel.
+            self.immediately_available_streak += 1;
+            if self.immediately_available_streak >= self.target_pending_messages {
+                self.immediately_available_streak = 0;
+                self.target_pending_messages =
+                    cmp::min(self.target_pending_messages * 2, self.max_pending_messages);
+            }
+        }
+
+        while self.messages.len() < self.target_pending_messages {
+            self.add_message();
+        }
+
         ffi::decode_notification(&message.0).unwrap()
     }
 
+    /// Asks the kernel to also report, through [`Registration::next_message_raw`], whenever a
+    /// handler registers itself for any interface.
+    ///
+    /// These reports are returned as [`DecodedInterfaceOrDestroyed::InterfaceRegistered`].
+    pub fn watch_registrations(&self) {
+        unsafe {
+            // Unwrapping is ok because there's always something that handles the interface
+            // interface.
+            redshirt_syscalls::emit_message_without_response(
+                &ffi::INTERFACE,
+                ffi::InterfaceMessage::WatchRegistrations(self.id),
+            )
+            .unwrap();
+        }
+    }
+
     fn add_message(&mut self) {
         self.messages.push(unsafe {
             let message = ffi::InterfaceMessage::NextMessage(self.id).encode();
@@ -115,6 +266,38 @@ pub fn emit_answer(message_id: MessageId, msg: impl Encode) {
     imp(message_id, msg)
 }
 
+/// Answers the given message with the output of `answer`, or with the output of `on_timeout` if
+/// `answer` doesn't finish before `timeout` has elapsed.
+///
+/// This is meant to be used by interface handlers in order to put an upper bound on the time
+/// spent processing each message. The returned `Future` must be polled for the message to be
+/// answered, and `answer` is dropped without finishing in case of a timeout.
+pub async fn answer_with_timeout<T: Encode>(
+    message_id: MessageId,
+    timeout: Duration,
+    answer: impl Future<Output = T>,
+    on_timeout: impl FnOnce() -> T,
+) {
+    let delay = redshirt_time_interface::Delay::new(timeout);
+    emit_answer(
+        message_id,
+        answer_or_timeout(answer, delay, on_timeout).await,
+    )
+}
+
+/// Returns the output of `answer`, or the output of `on_timeout` if `delay` finishes first.
+async fn answer_or_timeout<T>(
+    answer: impl Future<Output = T>,
+    delay: impl Future<Output = ()>,
+    on_timeout: impl FnOnce() -> T,
+) -> T {
+    futures::pin_mut!(answer, delay);
+    match future::select(answer, delay).await {
+        future::Either::Left((answer, _)) => answer,
+        future::Either::Right(((), _)) => on_timeout(),
+    }
+}
+
 /// Answers the given message by notifying of an error in the message.
 pub fn emit_message_error(message_id: MessageId) {
     #[cfg(target_arch = "wasm32")] // TODO: we should have a proper operating system name instead
@@ -134,3 +317,20 @@ pub fn emit_message_error(message_id: MessageId) {
     }
     imp(message_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::prelude::*;
+
+    #[test]
+    fn answer_before_timeout() {
+        let answer = super::answer_or_timeout(future::ready(1), future::pending(), || 2);
+        assert_eq!(answer.now_or_never(), Some(1));
+    }
+
+    #[test]
+    fn timeout_before_answer() {
+        let answer = super::answer_or_timeout(future::pending(), future::ready(()), || 2);
+        assert_eq!(answer.now_or_never(), Some(2));
+    }
+}
@@ -41,7 +41,10 @@ pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
 ]);
 
 /// Loads metrics from the kernel, as a Prometheus-compatible UTF-8 string.
-pub async fn get_prometheus_metrics() -> String {
+///
+/// Returns an error if the handler of the interface answered with an error, or if the metrics
+/// aren't valid UTF-8.
+pub async fn get_prometheus_metrics() -> Result<String, ()> {
     unsafe {
         let response: redshirt_syscalls::EncodedMessage =
             redshirt_syscalls::emit_message_with_response(
@@ -49,8 +52,9 @@ pub async fn get_prometheus_metrics() -> String {
                 redshirt_syscalls::EncodedMessage(Vec::new()),
             )
             .unwrap()
-            .await;
+            .await
+            .map_err(|_| ())?;
 
-        String::from_utf8(response.0).unwrap()
+        String::from_utf8(response.0).map_err(|_| ())
     }
 }
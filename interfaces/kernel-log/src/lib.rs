@@ -57,7 +57,10 @@ pub fn log(msg: &[u8]) {
 }
 
 /// Sets how the kernel should log messages.
-pub async fn configure_kernel(method: KernelLogMethod) {
+///
+/// Returns an error if the handler of the interface rejected the configuration or answered
+/// with a malformed response.
+pub async fn configure_kernel(method: KernelLogMethod) -> Result<(), ()> {
     unsafe {
         let encoded = method.encode();
         redshirt_syscalls::MessageBuilder::new()
@@ -65,6 +68,7 @@ pub async fn configure_kernel(method: KernelLogMethod) {
             .add_data_raw(&encoded)
             .emit_with_response::<()>(&ffi::INTERFACE)
             .unwrap()
-            .await;
+            .await
+            .map_err(|_| ())
     }
 }
@@ -29,13 +29,18 @@ use futures::prelude::*;
 pub mod ffi;
 
 /// Returns the list of PCI devices available on the system.
-pub fn get_pci_devices() -> impl Future<Output = Vec<PciDeviceInfo>> {
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub fn get_pci_devices() -> impl Future<Output = Result<Vec<PciDeviceInfo>, ()>> {
     unsafe {
         let msg = ffi::PciMessage::GetDevicesList;
-        // TODO: don't unwrap?
         redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
             .unwrap()
-            .map(|response: ffi::GetDevicesListResponse| response.devices)
+            .map(|response| {
+                let response: ffi::GetDevicesListResponse = response.map_err(|_| ())?;
+                Ok(response.devices)
+            })
     }
 }
 
@@ -56,6 +61,7 @@ impl PciDeviceLock {
             redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
                 .unwrap()
                 .await
+                .map_err(|_| ())?
         };
 
         result?;
@@ -87,7 +93,10 @@ impl PciDeviceLock {
     /// >           interrupt happened. In order to not miss any follow-up interrupt, call this
     /// >           function *before* reading the reason, but only await on the returned Future
     /// >           *after* reading the reason.
-    pub fn next_interrupt(&self) -> impl Future<Output = ()> + Send + 'static {
+    ///
+    /// The `Future` yields an error if the handler of the interface answered with an error or a
+    /// malformed response.
+    pub fn next_interrupt(&self) -> impl Future<Output = Result<(), ()>> + Send + 'static {
         let bdf = self.device.clone();
 
         // We send the message outside of the `async` block in order to be sure that the message
@@ -99,8 +108,8 @@ impl PciDeviceLock {
 
         async move {
             response
-                .map(|response: ffi::NextInterruptResponse| match response {
-                    ffi::NextInterruptResponse::Interrupt => {}
+                .map(|response| match response.map_err(|_| ())? {
+                    ffi::NextInterruptResponse::Interrupt => Ok(()),
                     ffi::NextInterruptResponse::BadDevice => panic!(),
                     ffi::NextInterruptResponse::Unlocked => unreachable!(),
                 })
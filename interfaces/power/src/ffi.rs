@@ -0,0 +1,47 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use parity_scale_codec::{Decode, Encode};
+use redshirt_syscalls::InterfaceHash;
+
+// TODO: this has been randomly generated; instead should be a hash or something
+pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0x27, 0x6d, 0x5e, 0x79, 0x68, 0xdd, 0xec, 0x63, 0x67, 0x5b, 0xa4, 0x1f, 0xc9, 0x0b, 0x1e, 0x30,
+    0x34, 0x01, 0xbb, 0x99, 0xf5, 0xd1, 0x5e, 0xce, 0xed, 0x61, 0x1b, 0xad, 0xe1, 0x07, 0xce, 0x63,
+]);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum PowerMessage {
+    /// Turns the machine off. Answered with a [`PowerResponse`] only in case of failure.
+    Shutdown,
+    /// Restarts the machine. Answered with a [`PowerResponse`] only in case of failure.
+    Reboot,
+    /// Puts the machine in a low-power state. Answered with a [`PowerResponse`] once the machine
+    /// has woken up, or in case of failure.
+    Suspend,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct PowerResponse {
+    pub result: Result<(), PowerError>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum PowerError {
+    /// The emitter of the message isn't allowed to control the power of the machine.
+    Unauthorized,
+    /// The requested action isn't supported by the platform.
+    Unsupported,
+}
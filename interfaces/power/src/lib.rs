@@ -0,0 +1,89 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Power management.
+//!
+//! Allows turning off, restarting, or suspending the machine.
+//!
+//! This interface is handled by the kernel itself, and only the processes that have been granted
+//! the capability to do so are allowed to use it. Messages emitted by other processes are
+//! answered with [`PowerError::Unauthorized`].
+
+#![no_std]
+
+extern crate alloc;
+
+use core::fmt;
+use futures::prelude::*;
+
+pub mod ffi;
+
+pub use ffi::PowerError;
+
+/// Turns the machine off.
+///
+/// The returned `Future` only ever produces a value if the machine couldn't be turned off.
+pub fn shutdown() -> impl Future<Output = Result<(), RequestError>> {
+    send(ffi::PowerMessage::Shutdown)
+}
+
+/// Restarts the machine.
+///
+/// The returned `Future` only ever produces a value if the machine couldn't be restarted.
+pub fn reboot() -> impl Future<Output = Result<(), RequestError>> {
+    send(ffi::PowerMessage::Reboot)
+}
+
+/// Puts the machine in a low-power state.
+///
+/// The returned `Future` produces `Ok` once the machine has woken up.
+pub fn suspend() -> impl Future<Output = Result<(), RequestError>> {
+    send(ffi::PowerMessage::Suspend)
+}
+
+/// Error that can happen when requesting a change of the power state of the machine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    /// The kernel refused or failed to perform the requested action.
+    Power(PowerError),
+    /// The kernel rejected the message or answered with a malformed response.
+    BadResponse,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Power(PowerError::Unauthorized) => {
+                write!(f, "Not allowed to control the power of the machine")
+            }
+            RequestError::Power(PowerError::Unsupported) => {
+                write!(f, "Action not supported by the platform")
+            }
+            RequestError::BadResponse => write!(f, "Bad response from the kernel"),
+        }
+    }
+}
+
+fn send(msg: ffi::PowerMessage) -> impl Future<Output = Result<(), RequestError>> {
+    unsafe {
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::PowerResponse =
+                    response.map_err(|_| RequestError::BadResponse)?;
+                response.result.map_err(RequestError::Power)
+            })
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use redshirt_syscalls::{InterfaceHash, Pid};
+
+// TODO: this has been randomly generated; instead should be a hash or something
+pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0xde, 0xb5, 0x84, 0x53, 0xb7, 0x80, 0x25, 0x97, 0x7a, 0xe2, 0xf6, 0x63, 0x63, 0x38, 0x7f, 0x0b,
+    0xa1, 0x00, 0x58, 0xdc, 0xea, 0xda, 0x47, 0x5c, 0x49, 0xbb, 0x16, 0xb6, 0xb8, 0xd6, 0x2f, 0x9f,
+]);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum ProcessMessage {
+    /// Requests the list of all the processes currently running. Answered with a
+    /// [`ListResponse`].
+    List,
+    /// Requests information about the given process. Answered with an [`InfoResponse`].
+    Info(Pid),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct ListResponse {
+    pub processes: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct InfoResponse {
+    /// `None` if no process with this [`Pid`] exists.
+    pub process: Option<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct ProcessInfo {
+    /// Identifier of the process.
+    pub pid: Pid,
+    /// Hash of the module the process has been started from.
+    pub module_hash: [u8; 32],
+    /// Size, in bytes, of the memory of the process.
+    pub memory_size: u64,
+    /// Number of threads of the process.
+    pub num_threads: u32,
+    /// Number of messages emitted by the process that are waiting for an answer. Messages
+    /// emitted on interfaces handled by the kernel aren't included.
+    pub pending_messages: u32,
+    /// State the process is in.
+    pub state: ProcessState,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum ProcessState {
+    /// The process is running normally.
+    Running,
+    /// The process has been aborted or has exited, and is being cleaned up.
+    Terminating,
+}
@@ -0,0 +1,67 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Processes enumeration.
+//!
+//! Allows listing the processes running on the machine and querying information about them.
+//!
+//! This interface is handled by the kernel itself. Depending on the kernel configuration, only
+//! processes that have been granted the capability to do so are allowed to use it, in which
+//! case messages emitted by other processes are rejected.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use futures::prelude::*;
+use redshirt_syscalls::Pid;
+
+pub mod ffi;
+
+pub use ffi::{ProcessInfo, ProcessState};
+
+/// Returns the list of all the processes currently running.
+///
+/// Returns an error if the message has been rejected, for example because this process isn't
+/// allowed to use this interface, or if the response is malformed.
+pub fn list() -> impl Future<Output = Result<Vec<ProcessInfo>, ()>> {
+    unsafe {
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, ffi::ProcessMessage::List)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::ListResponse = response.map_err(|_| ())?;
+                Ok(response.processes)
+            })
+    }
+}
+
+/// Returns information about the given process, or `None` if it doesn't exist.
+///
+/// Returns an error if the message has been rejected, for example because this process isn't
+/// allowed to use this interface, or if the response is malformed.
+pub fn info(pid: Pid) -> impl Future<Output = Result<Option<ProcessInfo>, ()>> {
+    unsafe {
+        redshirt_syscalls::emit_message_with_response(
+            &ffi::INTERFACE,
+            ffi::ProcessMessage::Info(pid),
+        )
+        .unwrap()
+        .map(|response| {
+            let response: ffi::InfoResponse = response.map_err(|_| ())?;
+            Ok(response.process)
+        })
+    }
+}
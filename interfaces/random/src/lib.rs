@@ -25,17 +25,23 @@ use core::convert::TryFrom as _;
 pub mod ffi;
 
 /// Generate `len` bytes of random data and returns them.
-pub async fn generate(len: usize) -> Vec<u8> {
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub async fn generate(len: usize) -> Result<Vec<u8>, ()> {
     unsafe {
         let mut out = Vec::with_capacity(len);
         out.set_len(len);
-        generate_in(&mut out).await;
-        out
+        generate_in(&mut out).await?;
+        Ok(out)
     }
 }
 
 /// Fills `out` with randomly-generated data.
-pub async fn generate_in(out: &mut [u8]) {
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response, in which case the content of `out` is unspecified.
+pub async fn generate_in(out: &mut [u8]) -> Result<(), ()> {
     for chunk in out.chunks_mut(usize::from(u16::max_value())) {
         let msg = ffi::RandomMessage::Generate {
             len: u16::try_from(chunk.len()).unwrap(),
@@ -44,42 +50,41 @@ pub async fn generate_in(out: &mut [u8]) {
             redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
                 .unwrap()
                 .await
+                .map_err(|_| ())?
         };
+        if rep.result.len() != chunk.len() {
+            return Err(());
+        }
         chunk.copy_from_slice(&rep.result);
     }
+
+    Ok(())
 }
 
 /// Generates a random `u8`.
-pub async fn generate_u8() -> u8 {
+pub async fn generate_u8() -> Result<u8, ()> {
     let mut buf = [0; 1];
-    generate_in(&mut buf).await;
-    buf[0]
+    generate_in(&mut buf).await?;
+    Ok(buf[0])
 }
 
 /// Generates a random `u16`.
-pub async fn generate_u16() -> u16 {
+pub async fn generate_u16() -> Result<u16, ()> {
     let mut buf = [0; 2];
-    generate_in(&mut buf).await;
-    u16::from_ne_bytes(buf)
+    generate_in(&mut buf).await?;
+    Ok(u16::from_ne_bytes(buf))
 }
 
 /// Generates a random `u32`.
-pub async fn generate_u32() -> u32 {
+pub async fn generate_u32() -> Result<u32, ()> {
     let mut buf = [0; 4];
-    generate_in(&mut buf).await;
-    u32::from_ne_bytes(buf)
+    generate_in(&mut buf).await?;
+    Ok(u32::from_ne_bytes(buf))
 }
 
 /// Generates a random `u64`.
-pub async fn generate_u64() -> u64 {
+pub async fn generate_u64() -> Result<u64, ()> {
     let mut buf = [0; 8];
-    generate_in(&mut buf).await;
-    u64::from_ne_bytes(buf)
-}
-
-/// Generates a random `u128`.
-pub async fn generate_u128() -> u128 {
-    let mut buf = [0; 16];
-    generate_in(&mut buf).await;
-    u128::from_ne_bytes(buf)
+    generate_in(&mut buf).await?;
+    Ok(u64::from_ne_bytes(buf))
 }
@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{Decode, Encode, EncodedMessage, InterfaceHash, MessageId};
+use crate::{Decode, Encode, EncodedMessage, InterfaceHash, MessageId, ResponseError};
 use core::{
     convert::TryFrom as _,
     fmt,
@@ -32,6 +32,11 @@ use generic_array::{
 /// Prototype for a message in construction.
 ///
 /// Use this struct if you want to send out a message split between multiple slices.
+///
+/// The slices are concatenated by the kernel, and are never copied in the memory of the current
+/// program. When sending a large payload, this makes it possible to encode only the small
+/// header of the message and pass the payload as a separate slice, rather than copying
+/// everything into an [`EncodedMessage`] first.
 pub struct MessageBuilder<'a, TLen: ArrayLength<u32>> {
     /// Parameter for the FFI function.
     allow_delay: bool,
@@ -101,7 +106,7 @@ where
     pub unsafe fn emit_with_response<T>(
         self,
         interface: &InterfaceHash,
-    ) -> Result<impl Future<Output = T>, EmitErr>
+    ) -> Result<impl Future<Output = Result<T, ResponseError<T::Error>>>, EmitErr>
     where
         T: Decode,
     {
@@ -131,6 +136,24 @@ where
         Ok(())
     }
 
+    /// Emit the message. The message doesn't expect any response, but the returned `Future`
+    /// yields whether the message has been delivered to an interface handler or discarded.
+    ///
+    /// The returned future will stop tracking the message if it is dropped early. This doesn't
+    /// prevent the message from being delivered.
+    // TODO: could we remove the error type?
+    pub unsafe fn emit_with_delivery_report(
+        self,
+        interface: &InterfaceHash,
+    ) -> Result<impl Future<Output = Result<(), DeliveryErr>>, EmitErr> {
+        let msg_id = self.emit_raw_impl(interface, false, true)?.unwrap();
+        let report = EmitMessageWithResponse::<EncodedMessage> {
+            inner: Some(crate::message_response(msg_id)),
+            msg_id,
+        };
+        Ok(report.map(|result| result.map(|_| ()).map_err(|_| DeliveryErr::NoHandler)))
+    }
+
     /// Emit the message. You can decide at runtime whether or not the message expects a response.
     ///
     /// If `needs_answer` is `true`, then on success a `Some` will always be returned.
@@ -141,14 +164,17 @@ where
         interface: &InterfaceHash,
         needs_answer: bool,
     ) -> Result<Option<MessageId>, EmitErr> {
-        self.emit_raw_impl(interface, needs_answer)
+        self.emit_raw_impl(interface, needs_answer, false)
     }
 
+    /// If `needs_answer` or `report_delivery` is `true`, then on success a `Some` will always be
+    /// returned.
     #[cfg(target_arch = "wasm32")] // TODO: we should have a proper operating system name instead
     unsafe fn emit_raw_impl(
         self,
         interface: &InterfaceHash,
         needs_answer: bool,
+        report_delivery: bool,
     ) -> Result<Option<MessageId>, EmitErr> {
         let flags = {
             let mut flags = 0;
@@ -158,6 +184,9 @@ where
             if self.allow_delay {
                 flags |= 1 << 1;
             }
+            if report_delivery {
+                flags |= 1 << 2;
+            }
             flags
         };
 
@@ -175,7 +204,7 @@ where
             return Err(EmitErr::BadInterface);
         }
 
-        if needs_answer {
+        if needs_answer || report_delivery {
             Ok(Some(MessageId::from_u64_unchecked(
                 message_id_out.assume_init(),
             )))
@@ -189,6 +218,7 @@ where
         self,
         _: &InterfaceHash,
         _: bool,
+        _: bool,
     ) -> Result<Option<MessageId>, EmitErr> {
         unimplemented!()
     }
@@ -231,6 +261,34 @@ pub unsafe fn emit_message_without_response<'a>(
         .emit_without_response(interface)
 }
 
+/// Emits a message destined to the handler of the given interface, and returns a `Future` that
+/// yields whether the message has been delivered.
+///
+/// Contrary to [`emit_message_without_response`], the future yields an error if the message has
+/// been discarded, for example because the emitter isn't allowed to use this interface. If no
+/// handler is available, the message is delivered once one becomes available.
+///
+/// # Safety
+///
+/// While the action of sending a message is totally safe, the message itself might instruct the
+/// environment to perform actions that would lead to unsafety.
+///
+pub unsafe fn emit_message_with_delivery_report(
+    interface: &InterfaceHash,
+    msg: impl Encode,
+) -> impl Future<Output = Result<(), DeliveryErr>> {
+    let msg = msg.encode();
+    match MessageBuilder::new()
+        .add_data(&msg)
+        .emit_with_delivery_report(interface)
+    {
+        Ok(report) => future::Either::Left(report),
+        Err(EmitErr::BadInterface) => {
+            future::Either::Right(future::ready(Err(DeliveryErr::NoHandler)))
+        }
+    }
+}
+
 /// Emis a message, then waits for a response to come back.
 ///
 /// Returns `Ok` if the message has been successfully dispatched. Returns an error if no handler
@@ -238,7 +296,8 @@ pub unsafe fn emit_message_without_response<'a>(
 /// Whether this function succeeds only depends on whether an interface handler is available. This
 /// function doesn't perform any validity check on the message itself.
 ///
-/// The returned future will cancel the message if it is dropped early.
+/// The returned future will cancel the message if it is dropped early. It yields an error if
+/// the response can't be decoded, see [`ResponseError`].
 ///
 /// # Safety
 ///
@@ -248,7 +307,7 @@ pub unsafe fn emit_message_without_response<'a>(
 pub unsafe fn emit_message_with_response<'a, T: Decode>(
     interface: &InterfaceHash,
     msg: impl Encode,
-) -> Result<impl Future<Output = T>, EmitErr> {
+) -> Result<impl Future<Output = Result<T, ResponseError<T::Error>>>, EmitErr> {
     let msg = msg.encode();
     MessageBuilder::new()
         .add_data(&msg)
@@ -285,6 +344,21 @@ impl fmt::Display for EmitErr {
     }
 }
 
+/// Error that can be yielded when waiting for a message to be delivered.
+#[derive(Debug)]
+pub enum DeliveryErr {
+    /// The message hasn't been delivered to any interface handler and has been discarded.
+    NoHandler,
+}
+
+impl fmt::Display for DeliveryErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeliveryErr::NoHandler => write!(f, "The message hasn't been delivered to a handler"),
+        }
+    }
+}
+
 /// Future that drives [`emit_message_with_response`] to completion.
 #[must_use]
 #[pin_project::pin_project(PinnedDrop)]
@@ -296,7 +370,7 @@ pub struct EmitMessageWithResponse<T> {
 }
 
 impl<T: Decode> Future for EmitMessageWithResponse<T> {
-    type Output = T;
+    type Output = Result<T, ResponseError<T::Error>>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
         unsafe {
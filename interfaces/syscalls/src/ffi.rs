@@ -80,11 +80,14 @@ extern "C" {
     /// - Bit 1: the `allow_delay` flag. If set, the kernel is allowed to block the thread in
     /// order to lazily-load a handler for that interface if necessary. If this flag is not set,
     /// and no interface handler is available, then the function fails immediately.
+    /// - Bit 2: the `report_delivery` flag. Ignored if `needs_answer` is set. If set, the kernel
+    /// sends back an empty answer once the message has been delivered to an interface handler,
+    /// or an error if the message has been discarded.
     ///
     /// Returns `0` on success, and `1` in case of error.
     ///
-    /// On success, if `needs_answer` is true, will write the ID of new event into the memory
-    /// pointed by `message_id_out`.
+    /// On success, if `needs_answer` or `report_delivery` is true, will write the ID of new event
+    /// into the memory pointed by `message_id_out`.
     ///
     /// When this function is being called, a "lock" is being held on the memory pointed by
     /// `interface_hash`, `msg_bufs_ptrs`, `message_id_out`, and all the sub-buffers referred to
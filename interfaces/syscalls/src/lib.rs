@@ -36,6 +36,13 @@
 //! The two primary and recommended ways to emit a message are the
 //! [`emit_message_without_response`] and [`emit_message_with_response`] functions.
 //!
+//! # Endianness
+//!
+//! Multi-byte integers within messages and responses are always encoded in little endian. This
+//! is what the SCALE codec used by [`Encode`] and [`Decode`] produces, and interfaces whose
+//! messages are laid out manually must follow the same convention. The [`read_le`] and
+//! [`write_le`] functions can be used to that end.
+//!
 //! # Interface handling
 //!
 //! A program can register itself as an interface handler. This can be done by sending a message
@@ -78,11 +85,17 @@ extern crate alloc;
 
 pub use block_on::block_on;
 pub use emit::{
-    cancel_message, emit_message_with_response, emit_message_without_response, MessageBuilder,
+    cancel_message, emit_message_with_delivery_report, emit_message_with_response,
+    emit_message_without_response, DeliveryErr, MessageBuilder,
 };
 pub use ffi::DecodedNotificationRef;
-pub use response::{message_response, message_response_sync_raw, MessageResponseFuture};
-pub use traits::{Decode, Encode, EncodedMessage, EncodedMessageRef};
+pub use response::{
+    message_response, message_response_sync_raw, MessageResponseFuture, ResponseError,
+};
+pub use traits::{
+    read_be, read_le, write_be, write_le, Decode, Encode, EncodedMessage, EncodedMessageRef,
+    FixedWidthInteger,
+};
 
 use core::{cmp::PartialEq, convert::TryFrom, fmt, num::NonZeroU64};
 
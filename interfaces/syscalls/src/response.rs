@@ -16,6 +16,7 @@
 use crate::{ffi, Decode, EncodedMessage, MessageId};
 
 use core::{
+    fmt,
     marker::PhantomData,
     pin::Pin,
     task::{Context, Poll},
@@ -26,18 +27,17 @@ use futures::prelude::*;
 ///
 /// Returns the undecoded response.
 // TODO: two futures for the same message will compete with each other; document that?
-pub fn message_response_sync_raw(msg_id: MessageId) -> EncodedMessage {
+pub fn message_response_sync_raw(
+    msg_id: MessageId,
+) -> Result<EncodedMessage, ResponseError<core::convert::Infallible>> {
     let notification = crate::block_on::next_notification(&mut [msg_id.into()], true).unwrap();
-    ffi::decode_notification(&notification)
-        .unwrap()
-        .actual_data
-        .unwrap()
-        .into()
+    decode_response(&notification)
 }
 
 /// Returns a future that is ready when a response to the given message comes back.
 ///
-/// The return value is the type the message decodes to.
+/// The return value is the type the message decodes to, or an error if the interface handler
+/// has rejected the message or sent back a response that can't be decoded.
 pub fn message_response<T: Decode>(msg_id: MessageId) -> MessageResponseFuture<T> {
     MessageResponseFuture {
         finished: false,
@@ -67,16 +67,14 @@ impl<T> Future for MessageResponseFuture<T>
 where
     T: Decode,
 {
-    type Output = T;
+    type Output = Result<T, ResponseError<T::Error>>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
         assert!(!self.finished);
 
         if let Some(response) = crate::block_on::peek_response(self.msg_id) {
             self.finished = true;
-            let decoded = ffi::decode_notification(&response).unwrap();
-            return Poll::Ready(Decode::decode(decoded.actual_data.unwrap().into()).unwrap());
-            // TODO: don't unwrap here?
+            return Poll::Ready(decode_response(&response));
         }
 
         if let Some(r) = &mut self.registration {
@@ -88,13 +86,8 @@ where
         // module. But before doing that, we do a peeking syscall to see if a response has already
         // arrived. This makes it possible for code such as `future.now_or_never()` to work.
         if let Some(notif) = crate::block_on::next_notification(&mut [self.msg_id.into()], false) {
-            let decoded = ffi::decode_notification(&notif).unwrap();
-            debug_assert_eq!(decoded.index_in_list, 0);
-            debug_assert_eq!(decoded.message_id, self.msg_id);
-
             self.finished = true;
-            return Poll::Ready(Decode::decode(decoded.actual_data.unwrap().into()).unwrap());
-            // TODO: don't unwrap here?
+            return Poll::Ready(decode_response(&notif));
         }
 
         self.registration = Some(crate::block_on::register_message_waker(
@@ -106,3 +99,36 @@ where
 }
 
 impl<T> Unpin for MessageResponseFuture<T> {}
+
+/// Error that can happen when receiving the response to a message.
+#[derive(Debug)]
+pub enum ResponseError<T> {
+    /// The notification sent by the kernel is malformed.
+    BadNotification,
+    /// The interface handler has indicated that the message was invalid.
+    InvalidMessage,
+    /// The interface handler has sent back a response that can't be decoded.
+    Decode(T),
+}
+
+impl<T: fmt::Debug> fmt::Display for ResponseError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResponseError::BadNotification => write!(f, "Malformed notification"),
+            ResponseError::InvalidMessage => {
+                write!(f, "Message rejected by the interface handler")
+            }
+            ResponseError::Decode(err) => write!(f, "Failed to decode response: {:?}", err),
+        }
+    }
+}
+
+/// Turns a notification containing a response into the decoded response.
+fn decode_response<T: Decode>(notification: &[u8]) -> Result<T, ResponseError<T::Error>> {
+    let decoded =
+        ffi::decode_notification(notification).map_err(|()| ResponseError::BadNotification)?;
+    let data = decoded
+        .actual_data
+        .map_err(|()| ResponseError::InvalidMessage)?;
+    Decode::decode(data.into()).map_err(ResponseError::Decode)
+}
@@ -14,7 +14,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use alloc::vec::Vec;
-use core::fmt;
+use core::{convert::TryFrom, fmt};
 
 /// Message already encoded.
 ///
@@ -24,6 +24,10 @@ use core::fmt;
 pub struct EncodedMessage(pub Vec<u8>);
 
 /// Objects that represent messages that can be serialized in order to be sent on an interface.
+///
+/// All multi-byte integers found in messages are encoded in little endian, whether the message
+/// is encoded with SCALE or with a hand-written layout. See [`read_le`] and [`write_le`] for
+/// helpers that follow this convention.
 pub trait Encode {
     /// Turn the object into bytes ready to be transmitted.
     fn encode(self) -> EncodedMessage;
@@ -125,3 +129,86 @@ impl<'a> fmt::Debug for EncodedMessageRef<'a> {
         fmt::Debug::fmt(&self.0, f)
     }
 }
+
+/// Fixed-width integer that can be read from or written to a message.
+///
+/// Implemented on all the primitive integer types.
+pub trait FixedWidthInteger: Sized {
+    /// Number of bytes the integer occupies in a message.
+    const SIZE: usize;
+
+    /// Builds the integer from exactly [`FixedWidthInteger::SIZE`] bytes in little endian.
+    /// Returns `None` if `bytes` doesn't have the right length.
+    fn from_le_slice(bytes: &[u8]) -> Option<Self>;
+
+    /// Builds the integer from exactly [`FixedWidthInteger::SIZE`] bytes in big endian.
+    /// Returns `None` if `bytes` doesn't have the right length.
+    fn from_be_slice(bytes: &[u8]) -> Option<Self>;
+
+    /// Appends the little endian representation of the integer to `out`.
+    fn append_le(self, out: &mut Vec<u8>);
+
+    /// Appends the big endian representation of the integer to `out`.
+    fn append_be(self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_fixed_width_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl FixedWidthInteger for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn from_le_slice(bytes: &[u8]) -> Option<Self> {
+                    Some(<$ty>::from_le_bytes(TryFrom::try_from(bytes).ok()?))
+                }
+
+                fn from_be_slice(bytes: &[u8]) -> Option<Self> {
+                    Some(<$ty>::from_be_bytes(TryFrom::try_from(bytes).ok()?))
+                }
+
+                fn append_le(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn append_be(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_width_integer!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Reads a little endian integer located at `offset` within `buffer`.
+///
+/// Returns `None` if `buffer` is too short. This is the encoding that messages must use.
+///
+/// ```
+/// let message = [3, 0x78, 0x56, 0x34, 0x12];
+/// assert_eq!(redshirt_syscalls::read_le::<u32>(&message, 1), Some(0x12345678));
+/// assert_eq!(redshirt_syscalls::read_le::<u32>(&message, 2), None);
+/// ```
+pub fn read_le<T: FixedWidthInteger>(buffer: &[u8], offset: usize) -> Option<T> {
+    T::from_le_slice(buffer.get(offset..offset.checked_add(T::SIZE)?)?)
+}
+
+/// Reads a big endian integer located at `offset` within `buffer`.
+///
+/// Returns `None` if `buffer` is too short. Messages use little endian; this function is meant
+/// for payloads whose format is defined elsewhere, such as network packets.
+pub fn read_be<T: FixedWidthInteger>(buffer: &[u8], offset: usize) -> Option<T> {
+    T::from_be_slice(buffer.get(offset..offset.checked_add(T::SIZE)?)?)
+}
+
+/// Appends `value` to `out` in little endian. This is the encoding that messages must use.
+pub fn write_le<T: FixedWidthInteger>(out: &mut Vec<u8>, value: T) {
+    value.append_le(out)
+}
+
+/// Appends `value` to `out` in big endian.
+///
+/// Messages use little endian; see [`read_be`].
+pub fn write_be<T: FixedWidthInteger>(out: &mut Vec<u8>, value: T) {
+    value.append_be(out)
+}
@@ -0,0 +1,53 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use alloc::vec::Vec;
+use parity_scale_codec::{Decode, Encode};
+use redshirt_syscalls::InterfaceHash;
+
+// TODO: this has been randomly generated; instead should be a hash or something
+pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0x36, 0xc4, 0xcb, 0xfd, 0x5e, 0xf8, 0x92, 0xb7, 0xc3, 0xf9, 0xdd, 0x68, 0xc5, 0xd4, 0xda, 0xf9,
+    0x37, 0x79, 0x3e, 0x8c, 0xf3, 0xd6, 0xf5, 0x21, 0x08, 0x2f, 0x47, 0x77, 0x79, 0x5b, 0x0a, 0x63,
+]);
+
+#[derive(Debug, Encode, Decode)]
+pub enum SysInfoMessage {
+    /// Must respond with a [`HostnameResponse`].
+    GetHostname,
+    /// Must respond with a [`TimezoneResponse`].
+    GetTimezone,
+    /// Must respond with a [`LocaleResponse`].
+    GetLocale,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct HostnameResponse {
+    /// UTF-8 name of the machine. Empty if the machine doesn't have any name.
+    pub hostname: Vec<u8>,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct TimezoneResponse {
+    /// Offset, in seconds, to add to the UTC time in order to obtain the local time.
+    pub utc_offset_secs: i32,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct LocaleResponse {
+    /// Locale of the system, as a UTF-8 POSIX locale string such as `en_US.UTF-8`. Empty if
+    /// unknown.
+    pub locale: Vec<u8>,
+}
@@ -0,0 +1,77 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! System configuration.
+//!
+//! Allows learning the name of the machine, its timezone, and the locale preferred by the user.
+//! This interface is read-only. Modifying the system configuration isn't supported at the
+//! moment.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use futures::prelude::*;
+
+pub mod ffi;
+
+/// Returns the name of the machine.
+///
+/// Returns an error if the handler of the interface answered with an error, a malformed
+/// response, or a name that isn't valid UTF-8.
+pub fn hostname() -> impl Future<Output = Result<String, ()>> {
+    unsafe {
+        let msg = ffi::SysInfoMessage::GetHostname;
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::HostnameResponse = response.map_err(|_| ())?;
+                String::from_utf8(response.hostname).map_err(|_| ())
+            })
+    }
+}
+
+/// Returns the offset, in seconds, to add to the UTC time in order to obtain the local time.
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub fn timezone_offset() -> impl Future<Output = Result<i32, ()>> {
+    unsafe {
+        let msg = ffi::SysInfoMessage::GetTimezone;
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::TimezoneResponse = response.map_err(|_| ())?;
+                Ok(response.utc_offset_secs)
+            })
+    }
+}
+
+/// Returns the locale of the system, such as `en_US.UTF-8`.
+///
+/// Returns an error if the handler of the interface answered with an error, a malformed
+/// response, or a locale that isn't valid UTF-8.
+pub fn locale() -> impl Future<Output = Result<String, ()>> {
+    unsafe {
+        let msg = ffi::SysInfoMessage::GetLocale;
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| {
+                let response: ffi::LocaleResponse = response.map_err(|_| ())?;
+                String::from_utf8(response.locale).map_err(|_| ())
+            })
+    }
+}
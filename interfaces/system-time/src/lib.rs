@@ -24,9 +24,14 @@ use futures::prelude::*;
 pub mod ffi;
 
 /// Returns the number of nanoseconds since the Epoch (January 1st, 1970 at midnight UTC).
-pub fn system_clock() -> impl Future<Output = u128> {
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub fn system_clock() -> impl Future<Output = Result<u128, ()>> {
     unsafe {
         let msg = ffi::TimeMessage::GetSystem;
-        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg).unwrap()
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| response.map_err(|_| ()))
     }
 }
@@ -13,8 +13,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use parity_scale_codec::{Decode, Encode};
-use redshirt_syscalls::InterfaceHash;
+use core::convert::TryFrom as _;
+use parity_scale_codec::{Compact, Decode, Encode, Output};
+use redshirt_syscalls::{InterfaceHash, MessageId};
 
 // TODO: this has been randomly generated; instead should be a hash or something
 pub const INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
@@ -35,6 +36,12 @@ pub enum TcpMessage {
     /// Destroy the given socket. Doesn't expect any response. The given socket ID will no longer
     /// be valid, and any existing message be replied to with `InvalidSocket`.
     Destroy(u32),
+    /// Destroy the socket created by the given [`TcpMessage::Open`] message, which must have been
+    /// emitted by the same process and not answered yet. Typically used to stop a listening
+    /// socket. Doesn't expect any response. The `Open` message is answered with an error.
+    CancelOpen(MessageId),
+    /// Ask to modify the options of a socket. The response is a [`TcpSetOptionResponse`].
+    SetOption(TcpSetOption),
 }
 
 #[derive(Debug, Encode, Decode)]
@@ -49,6 +56,17 @@ pub struct TcpOpen {
     pub ip: [u16; 8],
     /// TCP port.
     pub port: u16,
+    /// Scope identifier of `ip`, as defined in [RFC 4007](https://tools.ietf.org/html/rfc4007).
+    /// Designates the network interface to use for link-local addresses.
+    ///
+    /// `0` if no scope is specified, which is always the case for IPv4 addresses.
+    pub scope_id: u32,
+    /// If `Some` and `listen` is false, IPv6 address and TCP port that the socket must be bound
+    /// to locally before connecting. An unspecified address or a port of `0` lets the handler
+    /// choose the address or the port.
+    ///
+    /// Ignored if `listen` is true.
+    pub bind_addr: Option<([u16; 8], u16)>,
 }
 
 #[derive(Debug, Encode, Decode)]
@@ -114,6 +132,52 @@ pub struct TcpWrite {
     pub data: Vec<u8>,
 }
 
+/// Encoded prefix of a [`TcpMessage::Write`] message, without the data.
+///
+/// Sending this header immediately followed by the data to write is equivalent to sending the
+/// encoded [`TcpMessage::Write`], but doesn't require copying the data to a temporary buffer.
+/// See [`redshirt_syscalls::MessageBuilder::add_data_raw`].
+#[derive(Debug, Clone)]
+pub struct TcpWriteHeader {
+    /// Buffer containing the header. Only the first `len` bytes are valid.
+    buffer: [u8; 10],
+    /// Number of bytes of `buffer` that are used.
+    len: usize,
+}
+
+impl TcpWriteHeader {
+    /// Builds the header of a write of `data_len` bytes on the given socket.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `data_len` doesn't fit in a `u32`.
+    pub fn new(socket_id: u32, data_len: usize) -> Self {
+        let mut header = TcpWriteHeader {
+            buffer: [0; 10],
+            len: 0,
+        };
+
+        // Index of the `Write` variant within `TcpMessage`.
+        header.write(&[3]);
+        socket_id.encode_to(&mut header);
+        Compact(u32::try_from(data_len).unwrap()).encode_to(&mut header);
+        header
+    }
+}
+
+impl AsRef<[u8]> for TcpWriteHeader {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl Output for TcpWriteHeader {
+    fn write(&mut self, bytes: &[u8]) {
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
 #[derive(Debug, Encode, Decode)]
 pub struct TcpWriteResponse {
     pub result: Result<(), TcpWriteError>,
@@ -129,3 +193,45 @@ pub enum TcpWriteError {
     /// The socket ID is invalid.
     InvalidSocket,
 }
+
+#[derive(Debug, Encode, Decode)]
+pub struct TcpSetOption {
+    pub socket_id: u32,
+    /// If `Some`, sets the time-to-live (IPv4) or hop limit (IPv6) of the packets sent by this
+    /// socket. Must be between 1 and 255.
+    pub ttl: Option<u32>,
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct TcpSetOptionResponse {
+    pub result: Result<(), TcpSetOptionError>,
+}
+
+#[derive(Debug, Encode, Decode, derive_more::Display)]
+pub enum TcpSetOptionError {
+    /// One of the values is out of range.
+    InvalidValue,
+    /// The socket ID is invalid.
+    InvalidSocket,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_header_matches_encoded_message() {
+        for data_len in &[0, 1, 63, 64, 16383, 16384, 70000] {
+            let data = vec![0xab; *data_len];
+            let expected = TcpMessage::Write(TcpWrite {
+                socket_id: 0x12345678,
+                data: data.clone(),
+            })
+            .encode();
+
+            let mut obtained = TcpWriteHeader::new(0x12345678, *data_len).as_ref().to_vec();
+            obtained.extend_from_slice(&data);
+            assert_eq!(obtained, expected);
+        }
+    }
+}
@@ -80,9 +80,11 @@
 //!
 
 use futures::{lock::Mutex, prelude::*, ready};
-use redshirt_syscalls::{Encode as _, MessageResponseFuture};
+use redshirt_syscalls::{Encode as _, MessageId, MessageResponseFuture};
 use std::{
-    cmp, io, mem,
+    cmp,
+    convert::TryFrom as _,
+    io,
     net::{IpAddr, Ipv6Addr, SocketAddr},
     pin::Pin,
     task::{Context, Poll},
@@ -95,6 +97,14 @@ pub mod ffi;
 /// This type is similar to [`std::net::TcpStream`].
 pub struct TcpStream {
     handle: u32,
+    /// Local address of the socket, as reported by the handler when opening it.
+    local_addr: SocketAddr,
+    /// Address of the remote, as reported by the handler when opening the socket.
+    remote_addr: SocketAddr,
+    /// Total number of bytes that have been returned to the user by reads.
+    bytes_read: u64,
+    /// Total number of bytes that have been accepted from the user by writes.
+    bytes_written: u64,
     /// Buffer of data that has been read from the socket but not transmitted to the user yet.
     /// Contains `None` after the remote has sent us a FIN, meaning that we will not get any more
     /// data.
@@ -112,19 +122,45 @@ pub struct TcpStream {
 /// This type is similar to [`std::net::TcpListener`].
 pub struct TcpListener {
     local_addr: SocketAddr,
-    next_incoming: Mutex<
-        stream::FuturesUnordered<
-            Pin<Box<dyn Future<Output = Result<(TcpStream, SocketAddr), ()>> + Send>>,
-        >,
-    >,
+    next_incoming: Mutex<Incoming>,
+}
+
+/// Listening sockets of a [`TcpListener`] waiting for an incoming connection.
+struct Incoming {
+    /// One entry for each listening socket.
+    sockets: stream::FuturesUnordered<IncomingSocket>,
+    /// Identifiers of the `Open` messages of the sockets in [`Incoming::sockets`].
+    open_messages: Vec<MessageId>,
+    /// Number of listening sockets to maintain. See [`TcpListener::bind_with_backlog`].
+    backlog: usize,
+    /// True if [`TcpListener::close`] has been called.
+    closed: bool,
 }
 
+/// Future resolving when a listening socket gets a connection. Also yields the identifier of
+/// the `Open` message of the socket.
+type IncomingSocket =
+    Pin<Box<dyn Future<Output = (MessageId, Result<(TcpStream, SocketAddr), ()>)> + Send>>;
+
 impl TcpStream {
     /// Start connecting to the given address. Returns a `TcpStream` if the connection is
     /// successful. The returned `TcpStream` is in the "Established" state (but might quickly
     /// transition to another state).
     pub fn connect(socket_addr: &SocketAddr) -> impl Future<Output = Result<TcpStream, ()>> {
-        let fut = TcpStream::new(socket_addr, false);
+        let fut = TcpStream::new(socket_addr, false, None);
+        async move { Ok(fut.await?.0) }
+    }
+
+    /// Same as [`TcpStream::connect`], but binds the socket to the given local address before
+    /// connecting.
+    ///
+    /// The IP address of `bind_addr` can be unspecified (e.g. `0.0.0.0`) and its port can be `0`,
+    /// in which case they are chosen automatically.
+    pub fn connect_from(
+        socket_addr: &SocketAddr,
+        bind_addr: &SocketAddr,
+    ) -> impl Future<Output = Result<TcpStream, ()>> {
+        let fut = TcpStream::new(socket_addr, false, Some(bind_addr));
         async move { Ok(fut.await?.0) }
     }
 
@@ -133,34 +169,61 @@ impl TcpStream {
     fn new(
         socket_addr: &SocketAddr,
         listen: bool,
+        bind_addr: Option<&SocketAddr>,
     ) -> impl Future<Output = Result<(TcpStream, SocketAddr), ()>> {
+        TcpStream::open(socket_addr, listen, bind_addr).1
+    }
+
+    /// Same as [`TcpStream::new`], but also returns the identifier of the `Open` message.
+    fn open(
+        socket_addr: &SocketAddr,
+        listen: bool,
+        bind_addr: Option<&SocketAddr>,
+    ) -> (
+        MessageId,
+        impl Future<Output = Result<(TcpStream, SocketAddr), ()>>,
+    ) {
+        let bind_addr = bind_addr.map(|addr| match addr {
+            SocketAddr::V4(addr) => (addr.ip().to_ipv6_mapped().segments(), addr.port()),
+            SocketAddr::V6(addr) => (addr.ip().segments(), addr.port()),
+        });
+
         let tcp_open = ffi::TcpMessage::Open(match socket_addr {
             SocketAddr::V4(addr) => ffi::TcpOpen {
                 ip: addr.ip().to_ipv6_mapped().segments(),
                 port: addr.port(),
+                scope_id: 0,
                 listen,
+                bind_addr,
             },
             SocketAddr::V6(addr) => ffi::TcpOpen {
                 ip: addr.ip().segments(),
                 port: addr.port(),
+                scope_id: addr.scope_id(),
                 listen,
+                bind_addr,
             },
         });
 
         // Send the opening message here, so that the socket starts connecting or listening to
         // connections before we start polling the returned `Future`.
-        let open_future = unsafe {
+        let open_message_id = unsafe {
             let msg = tcp_open.encode();
             redshirt_syscalls::MessageBuilder::new()
                 .add_data(&msg)
-                .emit_with_response(&ffi::INTERFACE)
+                .emit_with_response_raw(&ffi::INTERFACE)
                 .unwrap()
         };
+        let open_future = redshirt_syscalls::message_response(open_message_id);
 
-        async move {
-            let message: ffi::TcpOpenResponse = open_future.await;
+        let future = async move {
+            let message: ffi::TcpOpenResponse = open_future.await.map_err(|_| ())?;
 
             let socket_open_info = message.result?;
+            let local_addr = {
+                let ip = Ipv6Addr::from(socket_open_info.local_ip);
+                SocketAddr::new(IpAddr::from(ip), socket_open_info.local_port)
+            };
             let remote_addr = {
                 let ip = Ipv6Addr::from(socket_open_info.remote_ip);
                 SocketAddr::new(IpAddr::from(ip), socket_open_info.remote_port)
@@ -168,6 +231,10 @@ impl TcpStream {
 
             let stream = TcpStream {
                 handle: socket_open_info.socket_id,
+                local_addr,
+                remote_addr,
+                bytes_read: 0,
+                bytes_written: 0,
                 read_buffer: Some(Vec::new()),
                 pending_read: None,
                 pending_write: None,
@@ -175,6 +242,61 @@ impl TcpStream {
             };
 
             Ok((stream, remote_addr))
+        };
+
+        (open_message_id, future)
+    }
+
+    /// Returns the local address of the socket.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the address of the remote the socket is connected to.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// Returns the total number of bytes that have been read from this socket so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the total number of bytes that have been written to this socket so far.
+    ///
+    /// This includes data whose write is still in progress.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Sets the value of the time-to-live (IPv4) or hop limit (IPv6) field of the packets sent
+    /// by this socket.
+    ///
+    /// Returns an error if `ttl` is 0 or superior to 255, or if the handler doesn't know about
+    /// the socket anymore.
+    pub fn set_ttl(&self, ttl: u32) -> impl Future<Output = Result<(), io::Error>> {
+        let set_option = ffi::TcpMessage::SetOption(ffi::TcpSetOption {
+            socket_id: self.handle,
+            ttl: Some(ttl),
+        });
+
+        let response = unsafe {
+            redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, set_option).unwrap()
+        };
+
+        async move {
+            let response: ffi::TcpSetOptionResponse = response.await.map_err(response_error)?;
+            match response.result {
+                Ok(()) => Ok(()),
+                Err(ffi::TcpSetOptionError::InvalidValue) => {
+                    Err(io::ErrorKind::InvalidInput.into())
+                }
+                // The handler might have forgotten about the socket, for example if it has
+                // restarted.
+                Err(ffi::TcpSetOptionError::InvalidSocket) => {
+                    Err(io::ErrorKind::NotConnected.into())
+                }
+            }
         }
     }
 }
@@ -185,9 +307,26 @@ impl AsyncRead for TcpStream {
         cx: &mut Context,
         buf: &mut [u8],
     ) -> Poll<Result<usize, io::Error>> {
+        let available = ready!(AsyncBufRead::poll_fill_buf(self.as_mut(), cx))?;
+        let to_copy = cmp::min(available.len(), buf.len());
+        buf[..to_copy].copy_from_slice(&available[..to_copy]);
+        AsyncBufRead::consume(self, to_copy);
+        Poll::Ready(Ok(to_copy))
+    }
+
+    // TODO: implement poll_read_vectored
+    // TODO: unsafe fn initializer(&self) -> Initializer { ... }
+}
+
+impl AsyncBufRead for TcpStream {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<&[u8], io::Error>> {
+        let this = self.get_mut();
+
         loop {
-            if let Some(pending_read) = self.pending_read.as_mut() {
-                self.read_buffer = match ready!(Future::poll(Pin::new(pending_read), cx)).result {
+            if let Some(pending_read) = this.pending_read.as_mut() {
+                let response = ready!(Future::poll(Pin::new(pending_read), cx));
+                this.pending_read = None;
+                this.read_buffer = match response.map_err(response_error)?.result {
                     Ok(d) if d.is_empty() => None,
                     Ok(d) => Some(d),
                     Err(ffi::TcpReadError::ConnectionFinished) => {
@@ -195,28 +334,20 @@ impl AsyncRead for TcpStream {
                     }
                     Err(ffi::TcpReadError::InvalidSocket) => unreachable!(),
                 };
-                self.pending_read = None;
             }
 
-            debug_assert!(self.pending_read.is_none());
+            debug_assert!(this.pending_read.is_none());
 
-            let read_buffer = match self.read_buffer.as_mut() {
-                Some(b) => b,
+            match this.read_buffer.as_ref() {
+                Some(b) if !b.is_empty() => break,
+                Some(_) => {}
                 // We have received a FIN. Returning EOF.
-                None => return Poll::Ready(Ok(0)),
-            };
-
-            if !read_buffer.is_empty() {
-                let to_copy = cmp::min(read_buffer.len(), buf.len());
-                let mut tmp = mem::replace(read_buffer, Vec::new());
-                *read_buffer = tmp.split_off(to_copy);
-                buf[..to_copy].copy_from_slice(&tmp);
-                return Poll::Ready(Ok(to_copy));
+                None => return Poll::Ready(Ok(&[])),
             }
 
-            self.pending_read = {
+            this.pending_read = {
                 let tcp_read = ffi::TcpMessage::Read(ffi::TcpRead {
-                    socket_id: self.handle,
+                    socket_id: this.handle,
                 });
 
                 let msg_id = unsafe {
@@ -230,10 +361,20 @@ impl AsyncRead for TcpStream {
                 Some(redshirt_syscalls::message_response(msg_id))
             };
         }
+
+        // We only break out of the loop above if `read_buffer` contains data.
+        Poll::Ready(Ok(this.read_buffer.as_ref().unwrap()))
     }
 
-    // TODO: implement poll_read_vectored
-    // TODO: unsafe fn initializer(&self) -> Initializer { ... }
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        if let Some(read_buffer) = self.read_buffer.as_mut() {
+            read_buffer.drain(..amt);
+        } else {
+            assert_eq!(amt, 0);
+        }
+
+        self.bytes_read = self.bytes_read.saturating_add(u64::try_from(amt).unwrap());
+    }
 }
 
 impl AsyncWrite for TcpStream {
@@ -248,15 +389,11 @@ impl AsyncWrite for TcpStream {
         // Perform the write, and store into `self.pending_write` a future to when we can start
         // the next write.
         self.pending_write = {
-            let tcp_write = ffi::TcpMessage::Write(ffi::TcpWrite {
-                socket_id: self.handle,
-                data: buf.to_vec(), // TODO: meh for cloning
-            });
-
+            let header = ffi::TcpWriteHeader::new(self.handle, buf.len());
             let msg_id = unsafe {
-                let msg = tcp_write.encode(); // TODO: meh because we clone data a second time here
                 redshirt_syscalls::MessageBuilder::new()
-                    .add_data(&msg)
+                    .add_data_raw(header.as_ref())
+                    .add_data_raw(buf)
                     .emit_with_response_raw(&ffi::INTERFACE)
                     .unwrap()
             };
@@ -264,6 +401,10 @@ impl AsyncWrite for TcpStream {
             Some(redshirt_syscalls::message_response(msg_id))
         };
 
+        self.bytes_written = self
+            .bytes_written
+            .saturating_add(u64::try_from(buf.len()).unwrap());
+
         Poll::Ready(Ok(buf.len()))
     }
 
@@ -272,9 +413,9 @@ impl AsyncWrite for TcpStream {
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
         // Try to finish the previous write, if any is in progress.
         if let Some(pending_write) = self.pending_write.as_mut() {
-            let result = ready!(Future::poll(Pin::new(pending_write), cx)).result;
+            let response = ready!(Future::poll(Pin::new(pending_write), cx));
             self.pending_write = None;
-            match result {
+            match response.map_err(response_error)?.result {
                 Ok(()) => Poll::Ready(Ok(())),
                 Err(ffi::TcpWriteError::FinAlreaySent) => {
                     Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
@@ -292,9 +433,9 @@ impl AsyncWrite for TcpStream {
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), io::Error>> {
         // Try to finish the previous write, if any is in progress.
         if let Some(pending_write) = self.pending_write.as_mut() {
-            let result = ready!(Future::poll(Pin::new(pending_write), cx)).result;
+            let response = ready!(Future::poll(Pin::new(pending_write), cx));
             self.pending_write = None;
-            match result {
+            match response.map_err(response_error)?.result {
                 Ok(()) => {}
                 Err(ffi::TcpWriteError::FinAlreaySent) => return Poll::Ready(Ok(())),
                 Err(ffi::TcpWriteError::ConnectionFinished) => {
@@ -309,9 +450,9 @@ impl AsyncWrite for TcpStream {
         loop {
             // Try to finish the previous close, if any is in progress.
             if let Some(pending_close) = self.pending_close.as_mut() {
-                let result = ready!(Future::poll(Pin::new(pending_close), cx)).result;
+                let response = ready!(Future::poll(Pin::new(pending_close), cx));
                 self.pending_close = None;
-                match result {
+                match response.map_err(response_error)?.result {
                     Ok(()) | Err(ffi::TcpCloseError::FinAlreaySent) => return Poll::Ready(Ok(())),
                     Err(ffi::TcpCloseError::ConnectionFinished) => {
                         return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
@@ -354,6 +495,16 @@ impl tokio::io::AsyncRead for TcpStream {
     }
 }
 
+impl tokio::io::AsyncBufRead for TcpStream {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<&[u8], io::Error>> {
+        AsyncBufRead::poll_fill_buf(self, cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        AsyncBufRead::consume(self, amt)
+    }
+}
+
 impl tokio::io::AsyncWrite for TcpStream {
     fn poll_write(
         self: Pin<&mut Self>,
@@ -383,18 +534,49 @@ impl Drop for TcpStream {
 
 impl TcpListener {
     /// Create a new [`TcpListener`] listening on the given address and port.
+    ///
+    /// Equivalent to [`TcpListener::bind_with_backlog`] with a backlog of 10.
     pub fn bind(socket_addr: &SocketAddr) -> impl Future<Output = Result<TcpListener, ()>> {
-        let next_incoming = Mutex::new(
-            (0..10)
-                .map(|_| Box::pin(TcpStream::new(socket_addr, true)) as Pin<Box<_>>)
-                .collect(),
-        );
+        TcpListener::bind_with_backlog(socket_addr, 10)
+    }
+
+    /// Create a new [`TcpListener`] listening on the given address and port, and that keeps
+    /// `backlog` listening sockets open.
+    ///
+    /// As explained in [the module-level documentation](crate), the TCP handler has no concept
+    /// of a listener accepting connections. Instead, the [`TcpListener`] opens `backlog` sockets
+    /// listening on the same address and port, each of them capable of receiving one incoming
+    /// connection. Whenever one of them gets connected, a new listening socket is opened in
+    /// order to keep the count at `backlog`.
+    ///
+    /// The backlog is thus the maximum number of incoming connections that can be established
+    /// while nobody is calling [`TcpListener::accept`]. Once it is reached, further connection
+    /// attempts are refused by the TCP handler until a connection is accepted. Each listening
+    /// socket costs some memory in the TCP handler.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `backlog` is 0.
+    ///
+    pub fn bind_with_backlog(
+        socket_addr: &SocketAddr,
+        backlog: usize,
+    ) -> impl Future<Output = Result<TcpListener, ()>> {
+        assert_ne!(backlog, 0);
+
+        let mut incoming = Incoming {
+            sockets: stream::FuturesUnordered::new(),
+            open_messages: Vec::with_capacity(backlog),
+            backlog,
+            closed: false,
+        };
+        incoming.refill(socket_addr);
 
         let socket_addr = socket_addr.clone();
         async move {
             Ok(TcpListener {
                 local_addr: socket_addr,
-                next_incoming,
+                next_incoming: Mutex::new(incoming),
             })
         }
     }
@@ -405,18 +587,110 @@ impl TcpListener {
     }
 
     /// Waits for a new incoming connection and returns it.
+    ///
+    /// If [`TcpListener::close`] has been called, the returned future never finishes. Use
+    /// [`TcpListener::incoming`] in order to be notified of the listener being closed.
     pub async fn accept(&self) -> (TcpStream, SocketAddr) {
+        match self.next_incoming().await {
+            Some(connection) => connection,
+            None => future::pending().await,
+        }
+    }
+
+    /// Returns a stream of the incoming connections. The stream ends after
+    /// [`TcpListener::close`] has been called.
+    pub fn incoming(&self) -> impl Stream<Item = (TcpStream, SocketAddr)> + '_ {
+        stream::unfold(self, |listener| async move {
+            let connection = listener.next_incoming().await?;
+            Some((connection, listener))
+        })
+    }
+
+    /// Stops accepting connections and releases the port.
+    ///
+    /// The connections that have already been returned by [`TcpListener::accept`] or
+    /// [`TcpListener::incoming`] are unaffected.
+    pub async fn close(&self) {
+        self.next_incoming.lock().await.close();
+    }
+
+    async fn next_incoming(&self) -> Option<(TcpStream, SocketAddr)> {
         let mut next_incoming = self.next_incoming.lock().await;
 
-        let (tcp_stream, remote_addr) = loop {
-            match next_incoming.next().await {
-                Some(Ok(v)) => break v,
-                Some(Err(_)) => continue,
+        loop {
+            if next_incoming.closed {
+                return None;
+            }
+
+            let (open_message_id, result) = match next_incoming.sockets.next().await {
+                Some(v) => v,
                 None => unreachable!(),
+            };
+            next_incoming
+                .open_messages
+                .retain(|id| *id != open_message_id);
+            next_incoming.refill(&self.local_addr);
+
+            if let Ok(connection) = result {
+                return Some(connection);
             }
-        };
+        }
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        self.next_incoming.get_mut().close();
+    }
+}
+
+impl Incoming {
+    /// Opens new listening sockets until there are [`Incoming::backlog`] of them.
+    fn refill(&mut self, socket_addr: &SocketAddr) {
+        while self.sockets.len() < self.backlog {
+            let (open_message_id, future) = TcpStream::open(socket_addr, true, None);
+            self.open_messages.push(open_message_id);
+            self.sockets.push(Box::pin(
+                future.map(move |result| (open_message_id, result)),
+            ));
+        }
+    }
+
+    /// Cancels all the `Open` messages and asks the handler to destroy the sockets.
+    fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        self.closed = true;
+
+        // Sockets whose `Open` message has already been answered are connected, and can't be
+        // cancelled anymore. They are closed, then destroyed when the `TcpStream` is dropped.
+        while let Some(Some((open_message_id, result))) = self.sockets.next().now_or_never() {
+            self.open_messages.retain(|id| *id != open_message_id);
+            if let Ok((stream, _)) = result {
+                unsafe {
+                    let close = ffi::TcpMessage::Close(ffi::TcpClose {
+                        socket_id: stream.handle,
+                    });
+                    let _ =
+                        redshirt_syscalls::emit_message_without_response(&ffi::INTERFACE, &close);
+                }
+            }
+        }
 
-        next_incoming.push(Box::pin(TcpStream::new(&self.local_addr, true)));
-        (tcp_stream, remote_addr)
+        self.sockets = stream::FuturesUnordered::new();
+        for open_message_id in self.open_messages.drain(..) {
+            redshirt_syscalls::cancel_message(open_message_id);
+            unsafe {
+                let cancel = ffi::TcpMessage::CancelOpen(open_message_id);
+                let _ = redshirt_syscalls::emit_message_without_response(&ffi::INTERFACE, &cancel);
+            }
+        }
     }
 }
+
+/// Turns a failure to obtain a response from the TCP handler into an I/O error.
+fn response_error(err: redshirt_syscalls::ResponseError<()>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
@@ -16,6 +16,7 @@
 use crate::{monotonic_wait_until, Instant};
 use alloc::boxed::Box;
 use core::{fmt, future::Future, pin::Pin, task::Context, task::Poll, time::Duration};
+use futures::prelude::*;
 
 /// Mimics the API of `futures_timer::Delay`.
 pub struct Delay {
@@ -31,7 +32,9 @@ impl Delay {
     pub fn new_at(at: Instant) -> Delay {
         Delay {
             when: at,
-            inner: Box::pin(monotonic_wait_until(at.inner)),
+            // An error from the handler is treated like the deadline being reached, in order to
+            // not leave the `Delay` pending forever.
+            inner: Box::pin(monotonic_wait_until(at.inner).map(|_| ())),
         }
     }
 
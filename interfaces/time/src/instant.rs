@@ -26,8 +26,15 @@ pub struct Instant {
 }
 
 impl Instant {
+    /// Returns the current value of the monotonic clock.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the clock couldn't be queried. Use [`monotonic_clock`] to handle the error.
+    ///
     pub fn now() -> Instant {
-        let val = redshirt_syscalls::block_on(monotonic_clock());
+        let val = redshirt_syscalls::block_on(monotonic_clock())
+            .expect("failed to query the monotonic clock");
         Instant { inner: val }
     }
 
@@ -31,27 +31,40 @@ mod instant;
 pub mod ffi;
 
 /// Returns the number of nanoseconds since an arbitrary point in time in the past.
-pub fn monotonic_clock() -> impl Future<Output = u128> {
+///
+/// Returns an error if the handler of the interface answered with an error or a malformed
+/// response.
+pub fn monotonic_clock() -> impl Future<Output = Result<u128, ()>> {
     unsafe {
         let msg = ffi::TimeMessage::GetMonotonic;
-        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg).unwrap()
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| response.map_err(|_| ()))
     }
 }
 
 /// Returns a `Future` that yields when the monotonic clock reaches this value.
-pub fn monotonic_wait_until(until: u128) -> impl Future<Output = ()> {
+///
+/// The `Future` yields an error if the handler of the interface answered with an error or a
+/// malformed response.
+pub fn monotonic_wait_until(until: u128) -> impl Future<Output = Result<(), ()>> {
     unsafe {
         let msg = ffi::TimeMessage::WaitMonotonic(until);
-        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg).unwrap()
+        redshirt_syscalls::emit_message_with_response(&ffi::INTERFACE, msg)
+            .unwrap()
+            .map(|response| response.map_err(|_| ()))
     }
 }
 
 /// Returns a `Future` that outputs after `duration` has elapsed.
-pub fn monotonic_wait(duration: Duration) -> impl Future<Output = ()> {
+///
+/// The `Future` yields an error if the handler of the interface answered with an error or a
+/// malformed response.
+pub fn monotonic_wait(duration: Duration) -> impl Future<Output = Result<(), ()>> {
     let dur_nanos = u128::from(duration.as_secs())
         .saturating_mul(1_000_000_000)
         .saturating_add(u128::from(duration.subsec_nanos()));
 
     // TODO: meh for two syscalls
-    monotonic_clock().then(move |now| monotonic_wait_until(now.saturating_add(dur_nanos)))
+    monotonic_clock().and_then(move |now| monotonic_wait_until(now.saturating_add(dur_nanos)))
 }
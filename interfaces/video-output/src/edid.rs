@@ -0,0 +1,163 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parsing of EDIDs (Extended Display Identification Data).
+//!
+//! An EDID is a blob of data provided by a monitor and describing its capabilities. Drivers
+//! that have access to it can pass it when registering a video output through
+//! [`VideoOutputConfig::edid`](crate::video_output::VideoOutputConfig::edid).
+//!
+//! Only the base block of 128 bytes is parsed. Extension blocks are ignored.
+
+use core::convert::TryFrom as _;
+
+/// Information extracted from an EDID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdidInfo {
+    /// Width and height of the visible area of the monitor in millimeters, or `None` if unknown.
+    /// Always `None` for projectors.
+    pub physical_size_mm: Option<(u32, u32)>,
+    /// Preferred video mode of the monitor, or `None` if unknown.
+    pub native_mode: Option<NativeMode>,
+}
+
+/// Preferred video mode of a monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeMode {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Refresh rate, in millihertz.
+    pub refresh_rate_mhz: u32,
+}
+
+/// Parses an EDID. Returns `None` if the header or the checksum is invalid.
+pub fn parse(edid: &[u8]) -> Option<EdidInfo> {
+    let base = edid.get(..128)?;
+    if base[..8] != [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00] {
+        return None;
+    }
+    if base.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+        return None;
+    }
+
+    // The first detailed timing descriptor contains the preferred video mode. A pixel clock of
+    // 0 indicates that the descriptor contains something else.
+    let descriptor = &base[54..72];
+    let pixel_clock_10khz = u16::from_le_bytes([descriptor[0], descriptor[1]]);
+    let (native_mode, descriptor_size_mm) = if pixel_clock_10khz != 0 {
+        let h_active = u32::from(descriptor[2]) | (u32::from(descriptor[4] >> 4) << 8);
+        let h_blanking = u32::from(descriptor[3]) | (u32::from(descriptor[4] & 0xf) << 8);
+        let v_active = u32::from(descriptor[5]) | (u32::from(descriptor[7] >> 4) << 8);
+        let v_blanking = u32::from(descriptor[6]) | (u32::from(descriptor[7] & 0xf) << 8);
+        let h_size_mm = u32::from(descriptor[12]) | (u32::from(descriptor[14] >> 4) << 8);
+        let v_size_mm = u32::from(descriptor[13]) | (u32::from(descriptor[14] & 0xf) << 8);
+
+        let pixels_per_frame = u64::from(h_active + h_blanking) * u64::from(v_active + v_blanking);
+        let refresh_rate_mhz = if pixels_per_frame != 0 {
+            u32::try_from(u64::from(pixel_clock_10khz) * 10_000_000 / pixels_per_frame)
+                .unwrap_or(u32::max_value())
+        } else {
+            0
+        };
+
+        let mode = NativeMode {
+            width: h_active,
+            height: v_active,
+            refresh_rate_mhz,
+        };
+
+        let size = if h_size_mm != 0 && v_size_mm != 0 {
+            Some((h_size_mm, v_size_mm))
+        } else {
+            None
+        };
+
+        (Some(mode), size)
+    } else {
+        (None, None)
+    };
+
+    // The basic display parameters contain a less precise size, in centimeters.
+    let physical_size_mm = descriptor_size_mm.or_else(|| {
+        if base[21] != 0 && base[22] != 0 {
+            Some((u32::from(base[21]) * 10, u32::from(base[22]) * 10))
+        } else {
+            None
+        }
+    });
+
+    Some(EdidInfo {
+        physical_size_mm,
+        native_mode,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, EdidInfo, NativeMode};
+
+    /// Builds an EDID base block with a 1920x1080@60Hz preferred mode.
+    fn sample_edid() -> Vec<u8> {
+        let mut edid = vec![0; 128];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        edid[21] = 53;
+        edid[22] = 30;
+        edid[54..72].copy_from_slice(&[
+            0x02, 0x3a, 0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c, 0x45, 0x00, 0x13, 0x2a,
+            0x21, 0x00, 0x00, 0x1e,
+        ]);
+        let sum = edid.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        edid[127] = 0u8.wrapping_sub(sum);
+        edid
+    }
+
+    #[test]
+    fn parse_preferred_mode() {
+        assert_eq!(
+            parse(&sample_edid()),
+            Some(EdidInfo {
+                physical_size_mm: Some((531, 298)),
+                native_mode: Some(NativeMode {
+                    width: 1920,
+                    height: 1080,
+                    refresh_rate_mhz: 60000,
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn size_falls_back_to_centimeters() {
+        let mut edid = sample_edid();
+        // Turn the detailed timing descriptor into a dummy descriptor.
+        edid[54] = 0;
+        edid[55] = 0;
+        edid[127] = edid[127].wrapping_add(0x02).wrapping_add(0x3a);
+
+        let info = parse(&edid).unwrap();
+        assert_eq!(info.physical_size_mm, Some((530, 300)));
+        assert_eq!(info.native_mode, None);
+    }
+
+    #[test]
+    fn invalid_checksum() {
+        let mut edid = sample_edid();
+        edid[127] = edid[127].wrapping_add(1);
+        assert!(parse(&edid).is_none());
+        assert!(parse(&edid[..100]).is_none());
+    }
+}
@@ -33,8 +33,16 @@ pub enum VideoOutputMessage {
         width: u32,
         /// Height in pixels of the output.
         height: u32,
+        /// Refresh rate of the output in millihertz, or 0 if unknown.
+        refresh_rate_mhz: u32,
+        /// Video modes that the output can be switched to, including the current one. Empty if
+        /// the mode of the output can't be changed.
+        modes: Vec<Mode>,
         /// Expected format of the output.
         format: Format,
+        /// Raw EDID of the monitor connected to the output, if known. See the [`edid`](crate::edid)
+        /// module.
+        edid: Option<Vec<u8>>,
     },
 
     /// Removes a previously-registered video output.
@@ -42,10 +50,25 @@ pub enum VideoOutputMessage {
 
     /// Asks for the next image to present on this output. Must answer with a `NextImage`.
     NextImage(u64),
+
+    /// Reports an input event that happened on this output, for example on a remote client
+    /// displaying it. No answer is expected.
+    Input {
+        /// Identifier passed at registration.
+        id: u64,
+        /// Event that happened.
+        event: InputEvent,
+    },
 }
 
 #[derive(Debug, Encode, Decode, Clone)]
 pub struct NextImage {
+    /// If `Some`, the output must switch to this mode before applying `changes`. The mode is
+    /// always one of the modes passed at registration.
+    ///
+    /// When the mode changes, the content of the output is entirely invalidated, and `changes`
+    /// are relative to the new mode and cover the whole output.
+    pub mode: Option<Mode>,
     pub changes: Vec<NextImageChange>,
 }
 
@@ -63,3 +86,65 @@ pub struct NextImageChange {
 pub enum Format {
     R8G8B8X8,
 }
+
+/// Input event reported by a video output.
+#[derive(Debug, Encode, Decode, Clone)]
+pub enum InputEvent {
+    /// A keyboard key has been pressed or released.
+    KeyboardChange {
+        /// Scancode as defined in the USB HID Usage tables.
+        scancode: u16,
+        /// True if the key is now pressed.
+        pressed: bool,
+    },
+
+    /// A character has been typed, taking the keyboard layout into account.
+    Character {
+        /// Unicode scalar value of the character.
+        character: u32,
+    },
+
+    /// The cursor has moved over the output.
+    CursorMoved {
+        /// New position of the cursor in millipixels relative to the top-left hand corner of the
+        /// output. `None` if the cursor has left the output.
+        position: Option<(u64, u64)>,
+    },
+
+    /// A mouse button has been pressed or released.
+    MouseButtonChange {
+        /// Which mouse button is concerned.
+        button: MouseButton,
+        /// True if the button is now pressed.
+        pressed: bool,
+    },
+
+    /// The mouse has physically moved. Contrary to [`InputEvent::CursorMoved`], this is reported
+    /// even if the cursor can't move any further, for example because it is at the edge of the
+    /// output.
+    RawMouseMotion {
+        /// Horizontal movement, in device-specific units. Positive means towards the right.
+        dx: i32,
+        /// Vertical movement, in device-specific units. Positive means towards the bottom.
+        dy: i32,
+    },
+}
+
+#[derive(Debug, Encode, Decode, Copy, Clone, PartialEq, Eq)]
+pub enum MouseButton {
+    /// Typically but not necessarily the left mouse button.
+    Main,
+    /// Typically but not necessarily the right mouse button.
+    Secondary,
+}
+
+/// Video mode of an output.
+#[derive(Debug, Encode, Decode, Copy, Clone, PartialEq, Eq)]
+pub struct Mode {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Refresh rate in millihertz, or 0 if unknown.
+    pub refresh_rate_mhz: u32,
+}
@@ -18,9 +18,12 @@
 //! This interface serves to register devices capable of presenting an image to the user. Usually
 //! a monitor.
 //!
+//! A video output can give the list of its supported video modes at registration, and is then
+//! informed of mode changes alongside the images to present. It can also report input events,
+//! which the handler forwards to the framebuffers displayed on it.
+//!
 //! This interface is extremely naive at the moment. In the future, it should include:
 //!
-//! - Giving the list of supported video modes, and allowing changing the video mode of the output.
 //! - Generic graphics rendering. One would register graphics accelerators, connected to 0 or more
 //!   monitors.
 //! - Still registering devices in "linear framebuffer mode", for compatibility with VGA/VBE on PC,
@@ -29,5 +32,6 @@
 //! The main inspiration for designing "graphics commands" should be Vulkan and WebGPU.
 //!
 
+pub mod edid;
 pub mod ffi;
 pub mod video_output;
@@ -29,29 +29,43 @@ pub struct VideoOutputConfig {
     pub width: u32,
     /// Height in pixels of the output.
     pub height: u32,
+    /// Refresh rate of the output in millihertz, or 0 if unknown.
+    pub refresh_rate_mhz: u32,
+    /// Video modes that the output can be switched to, including the current one. Leave empty
+    /// if the mode of the output can't be changed.
+    ///
+    /// The new mode is reported through [`ffi::NextImage::mode`].
+    pub modes: Vec<ffi::Mode>,
     /// Format of the output.
     pub format: ffi::Format,
+    /// Raw EDID of the monitor connected to the output, or `None` if it isn't known.
+    pub edid: Option<Vec<u8>>,
 }
 
 /// Registers a new video output.
-pub async fn register(config: VideoOutputConfig) -> VideoOutputRegistration {
+///
+/// Returns an error if no identifier could be generated for the video output.
+pub async fn register(config: VideoOutputConfig) -> Result<VideoOutputRegistration, ()> {
     unsafe {
-        let id = redshirt_random_interface::generate_u64().await;
+        let id = redshirt_random_interface::generate_u64().await?;
 
         redshirt_syscalls::emit_message_without_response(&ffi::INTERFACE, &{
             ffi::VideoOutputMessage::Register {
                 id,
                 width: config.width,
                 height: config.height,
+                refresh_rate_mhz: config.refresh_rate_mhz,
+                modes: config.modes,
                 format: config.format,
+                edid: config.edid,
             }
         })
         .unwrap();
 
-        VideoOutputRegistration {
+        Ok(VideoOutputRegistration {
             id,
             frames: Mutex::new((0..10).map(|_| build_frame_future(id)).collect()),
-        }
+        })
     }
 }
 
@@ -81,7 +95,9 @@ impl VideoOutputRegistration {
     /// Returns the next packet to send to the network.
     ///
     /// This function will pull and merge all the pending frames into one. Even if the code calling
-    /// this method lags behind, only one frame will be returned.
+    /// this method lags behind, only one frame will be returned. If one of the merged frames
+    /// switches to a different mode, the changes of the frames that precede it are discarded,
+    /// as they apply to the previous mode.
     ///
     /// > **Note**: It is possible to call this method multiple times on the same
     /// >           [`VideoOutputRegistration`]. If that is done, no guarantee exists as to which
@@ -89,8 +105,28 @@ impl VideoOutputRegistration {
     pub async fn next_frame(&self) -> ffi::NextImage {
         let mut frames = self.frames.lock().await;
 
-        let mut out = frames.next().await.unwrap();
+        // Frames that can't be decoded are ignored.
+        let mut out = loop {
+            let frame = frames.next().await.unwrap();
+            frames.push(build_frame_future(self.id));
+            match frame {
+                Ok(frame) => break frame,
+                Err(err) => log::warn!("Ignoring bad frame: {}", err),
+            }
+        };
+
         while let Some(next_frame) = frames.select_next_some().now_or_never() {
+            let next_frame = match next_frame {
+                Ok(f) => f,
+                Err(err) => {
+                    log::warn!("Ignoring bad frame: {}", err);
+                    continue;
+                }
+            };
+            if next_frame.mode.is_some() {
+                out.mode = next_frame.mode;
+                out.changes.clear();
+            }
             out.changes.extend(next_frame.changes);
         }
 
@@ -100,6 +136,14 @@ impl VideoOutputRegistration {
 
         out
     }
+
+    /// Reports an input event that happened on this video output.
+    pub fn report_input(&self, event: ffi::InputEvent) {
+        unsafe {
+            let message = ffi::VideoOutputMessage::Input { id: self.id, event };
+            redshirt_syscalls::emit_message_without_response(&ffi::INTERFACE, &message).unwrap();
+        }
+    }
 }
 
 impl fmt::Debug for VideoOutputRegistration {
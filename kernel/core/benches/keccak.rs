@@ -61,7 +61,7 @@ fn bench(c: &mut Criterion) {
             .build()
             .unwrap();
         b.iter(|| {
-            system.execute(&module).unwrap();
+            system.execute(&module, None).unwrap();
             futures::executor::block_on(async {
                 loop {
                     match system.run().await {
@@ -0,0 +1,207 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Measures the overhead of the `System` when exchanging messages and switching between threads.
+//!
+//! The messages are emitted on an interface handled natively by the benchmark itself, which
+//! answers each message with its own body. This isolates the cost of the `System` from the cost
+//! of any actual interface handler.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use redshirt_core::{
+    extrinsics::{wasi::WasiExtrinsics, Extrinsics, NoExtrinsics},
+    system::StepOutcome,
+    InterfaceHash, System, SystemBuilder, SystemRunOutcome,
+};
+
+/// Interface handled by the benchmark. Must match the data at offset 0 in the modules below.
+const ECHO_INTERFACE: InterfaceHash = InterfaceHash::from_raw_hash([
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+    0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37,
+]);
+
+/// Number of messages simultaneously waiting for an answer in the concurrent benchmark. Must
+/// match the constants in the corresponding module.
+const NUM_CONCURRENT: usize = 32;
+
+fn bench(c: &mut Criterion) {
+    // Endlessly emits an 8 bytes message on the echo interface, then waits for its answer.
+    let round_trip_module = redshirt_core::from_wat!(
+        r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\00\01\02\03\04\05\06\07\10\11\12\13\14\15\16\17\20\21\22\23\24\25\26\27\30\31\32\33\34\35\36\37")
+    (data (i32.const 32) "\28\00\00\00\08\00\00\00")
+    (data (i32.const 40) "\01\02\03\04\05\06\07\08")
+    (func $_start (result i32)
+        (loop $loop
+            i32.const 0
+            i32.const 32
+            i32.const 1
+            i64.const 1
+            i32.const 48
+            call $emit_message
+            drop
+            i32.const 56
+            i32.const 48
+            i64.load
+            i64.store
+            i32.const 56
+            i32.const 1
+            i32.const 64
+            i32.const 256
+            i64.const 1
+            call $next_notification
+            drop
+            br $loop)
+        i32.const 0))"#
+    );
+
+    // Endlessly emits 32 messages on the echo interface, then waits for all their answers.
+    let concurrent_module = redshirt_core::from_wat!(
+        r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\00\01\02\03\04\05\06\07\10\11\12\13\14\15\16\17\20\21\22\23\24\25\26\27\30\31\32\33\34\35\36\37")
+    (data (i32.const 32) "\28\00\00\00\08\00\00\00")
+    (data (i32.const 40) "\01\02\03\04\05\06\07\08")
+    (func $_start (result i32)
+        (local $i i32)
+        (loop $batch
+            i32.const 0
+            set_local $i
+            (loop $emit
+                i32.const 0
+                i32.const 32
+                i32.const 1
+                i64.const 1
+                get_local $i
+                i32.const 8
+                i32.mul
+                i32.const 512
+                i32.add
+                call $emit_message
+                drop
+                get_local $i
+                i32.const 1
+                i32.add
+                tee_local $i
+                i32.const 32
+                i32.lt_u
+                br_if $emit)
+            i32.const 0
+            set_local $i
+            (loop $wait
+                i32.const 512
+                i32.const 32
+                i32.const 1024
+                i32.const 256
+                i64.const 1
+                call $next_notification
+                drop
+                get_local $i
+                i32.const 1
+                i32.add
+                tee_local $i
+                i32.const 32
+                i32.lt_u
+                br_if $wait)
+            br $batch)
+        i32.const 0))"#
+    );
+
+    // Endlessly yields its thread to the scheduler.
+    let yield_module = redshirt_core::from_wat!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "sched_yield" (func $sched_yield (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (func $_start (result i32)
+        (loop $loop
+            call $sched_yield
+            drop
+            br $loop)
+        i32.const 0))"#
+    );
+
+    c.bench_function("message-round-trip", |b| {
+        let system = SystemBuilder::<NoExtrinsics>::new([0; 64])
+            .with_native_interface_handler(ECHO_INTERFACE)
+            .build()
+            .unwrap();
+        system.execute(&round_trip_module, None).unwrap();
+        b.iter(|| echo_messages(&system, 1))
+    });
+
+    let mut group = c.benchmark_group("messages-concurrent");
+    group.throughput(Throughput::Elements(NUM_CONCURRENT as u64));
+    group.bench_function("32", |b| {
+        let system = SystemBuilder::<NoExtrinsics>::new([0; 64])
+            .with_native_interface_handler(ECHO_INTERFACE)
+            .build()
+            .unwrap();
+        system.execute(&concurrent_module, None).unwrap();
+        b.iter(|| echo_messages(&system, NUM_CONCURRENT))
+    });
+    group.finish();
+
+    c.bench_function("thread-switch", |b| {
+        let system = SystemBuilder::<WasiExtrinsics>::new([0; 64])
+            .build()
+            .unwrap();
+        // Two processes, so that every step switches to a different thread.
+        system.execute(&yield_module, None).unwrap();
+        system.execute(&yield_module, None).unwrap();
+        b.iter(|| match system.step() {
+            StepOutcome::Progress => {}
+            _ => panic!(),
+        })
+    });
+}
+
+/// Runs the system until `num_messages` messages have been emitted on [`ECHO_INTERFACE`], then
+/// answers each of them with its own body.
+fn echo_messages<TExt: Extrinsics>(system: &System<TExt>, num_messages: usize) {
+    let mut received = Vec::with_capacity(num_messages);
+
+    while received.len() < num_messages {
+        match system.step() {
+            StepOutcome::Progress => {}
+            StepOutcome::Event(SystemRunOutcome::NativeInterfaceMessage {
+                message_id: Some(message_id),
+                message,
+                ..
+            }) => received.push((message_id, message.extract())),
+            _ => panic!(),
+        }
+    }
+
+    for (message_id, body) in received {
+        system.answer_message(message_id, Ok(body));
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -33,8 +33,8 @@ pub mod wasi;
 
 /// Trait implemented on types that can handle extrinsics.
 ///
-/// The `Default` trait is used to instantiate structs that implement this trait. One instance is
-/// created for each WASM process.
+/// One instance is created for each WASM process, using [`Extrinsics::with_process_args`] if
+/// the process has been started with a [`ProcessArgs`], or the `Default` trait otherwise.
 // TODO: in this API one can only emit one message at the time; this is fine in terms of logic, but
 // is sub-optimal
 pub trait Extrinsics: Default {
@@ -54,12 +54,21 @@ pub trait Extrinsics: Default {
     /// Returns an iterator to the list of extrinsics that this struct supports.
     fn supported_extrinsics() -> Self::Iterator;
 
+    /// Instantiates the extrinsics of a process that has been started with the given arguments
+    /// and environment variables.
+    ///
+    /// The default implementation ignores `args` and calls `Default::default()`.
+    fn with_process_args(args: ProcessArgs) -> Self {
+        let _ = args;
+        Default::default()
+    }
+
     /// Called when a WASM module calls an extrinsic.
     ///
     /// Returns what to do next on this context.
     ///
-    /// Returning [`ExtrinsicsAction::Resume`] or [`ExtrinsicsAction::ProgramCrash`] finishes the
-    /// extrinsic call and destroys the context.
+    /// Returning [`ExtrinsicsAction::Resume`], [`ExtrinsicsAction::ProgramCrash`] or
+    /// [`ExtrinsicsAction::ProgramExit`] finishes the extrinsic call and destroys the context.
     fn new_context(
         &self,
         tid: ThreadId,
@@ -75,8 +84,8 @@ pub trait Extrinsics: Default {
     ///
     /// Returns what to do next on this context.
     ///
-    /// Returning [`ExtrinsicsAction::Resume`] or [`ExtrinsicsAction::ProgramCrash`] finishes the
-    /// extrinsic call and destroys the context.
+    /// Returning [`ExtrinsicsAction::Resume`], [`ExtrinsicsAction::ProgramCrash`] or
+    /// [`ExtrinsicsAction::ProgramExit`] finishes the extrinsic call and destroys the context.
     fn inject_message_response(
         &self,
         ctxt: &mut Self::Context,
@@ -85,6 +94,16 @@ pub trait Extrinsics: Default {
     ) -> ExtrinsicsAction;
 }
 
+/// Command-line arguments and environment variables passed to a process when it starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessArgs {
+    /// Arguments of the process. By convention, the first argument is the name of the program.
+    pub args: Vec<Vec<u8>>,
+
+    /// Environment variables, each in the `KEY=value` format.
+    pub env_vars: Vec<Vec<u8>>,
+}
+
 /// Access to a process's memory.
 pub trait ExtrinsicsMemoryAccess {
     /// Reads the process' memory in the given range and returns a copy of it.
@@ -146,6 +165,9 @@ pub enum ExtrinsicsAction {
     /// Crash the program that called the extrinsic.
     ProgramCrash,
 
+    /// Gracefully terminate the program that called the extrinsic, with the given exit code.
+    ProgramExit(i32),
+
     /// Successfully finish the call and return with the given value.
     Resume(Option<WasmValue>),
 
@@ -16,7 +16,9 @@
 //! Implementation of the [`Extrinsics`] trait that wraps around another implementation and sends
 //! all the calls to the `log` interface for debugging.
 
-use crate::extrinsics::{Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, SupportedExtrinsic};
+use crate::extrinsics::{
+    Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, ProcessArgs, SupportedExtrinsic,
+};
 use crate::{EncodedMessage, EncodedMessageRef, ThreadId, WasmValue};
 
 use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
@@ -95,6 +97,10 @@ where
         LogIterator(TInner::supported_extrinsics())
     }
 
+    fn with_process_args(args: ProcessArgs) -> Self {
+        Self::new(TInner::with_process_args(args))
+    }
+
     fn new_context(
         &self,
         thread_id: ThreadId,
@@ -136,6 +142,10 @@ where
                 ctxt.waiting_for_log_message = Some(a);
                 Cow::Borrowed(&b"<crash>"[..])
             }
+            ExtrinsicsAction::ProgramExit(code) => {
+                ctxt.waiting_for_log_message = Some(ExtrinsicsAction::ProgramExit(code));
+                Cow::Owned(format!("<exit {}>", code).into_bytes())
+            }
             a @ ExtrinsicsAction::EmitMessage { .. } => return (ctxt, a),
         };
 
@@ -177,6 +187,10 @@ where
                     ctxt.waiting_for_log_message = Some(a);
                     Cow::Borrowed(&b"<crash>"[..])
                 }
+                ExtrinsicsAction::ProgramExit(code) => {
+                    ctxt.waiting_for_log_message = Some(ExtrinsicsAction::ProgramExit(code));
+                    Cow::Owned(format!("<exit {}>", code).into_bytes())
+                }
                 a @ ExtrinsicsAction::EmitMessage { .. } => return a,
             };
 
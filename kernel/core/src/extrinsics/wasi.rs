@@ -18,7 +18,9 @@
 // Reference for function signatures:
 // https://github.com/WebAssembly/wasi-libc/blob/e1149ab0677317c6c981bcbb5e4c159e4d2b9669/libc-bottom-half/headers/public/wasi/api.h
 
-use crate::extrinsics::{Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, SupportedExtrinsic};
+use crate::extrinsics::{
+    Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, ProcessArgs, SupportedExtrinsic,
+};
 use crate::{sig, Encode as _, EncodedMessage, EncodedMessageRef, ThreadId, WasmValue};
 
 use alloc::{
@@ -35,10 +37,10 @@ use spinning_top::Spinlock;
 /// Implementation of the [`Extrinsics`] trait for WASI.
 #[derive(Debug)]
 pub struct WasiExtrinsics {
-    /// Arguments passed to the program.
+    /// Arguments passed to the program. Returned by `args_get`.
     args: Vec<Vec<u8>>,
 
-    /// Environment variables passed to the program.
+    /// Environment variables passed to the program. Returned by `environ_get`.
     env_vars: Vec<Vec<u8>>,
 
     /// List of open file descriptors.
@@ -304,6 +306,14 @@ impl Extrinsics for WasiExtrinsics {
         .into_iter()
     }
 
+    fn with_process_args(args: ProcessArgs) -> Self {
+        WasiExtrinsics {
+            args: args.args,
+            env_vars: args.env_vars,
+            ..Default::default()
+        }
+    }
+
     fn new_context(
         &self,
         _: ThreadId,
@@ -356,7 +366,10 @@ impl Extrinsics for WasiExtrinsics {
     ) -> ExtrinsicsAction {
         match ctxt.0 {
             ContextInner::WaitClockVal { out_ptr } => {
-                let response = response.unwrap();
+                let response = match response {
+                    Some(r) => r,
+                    None => return ExtrinsicsAction::ProgramCrash,
+                };
                 // TODO: extra copy
                 let value: u128 = match EncodedMessage::from(response).decode() {
                     Ok(v) => v,
@@ -377,7 +390,10 @@ impl Extrinsics for WasiExtrinsics {
                 mut out_ptr,
                 mut remaining_len,
             } => {
-                let response = response.unwrap();
+                let response = match response {
+                    Some(r) => r,
+                    None => return ExtrinsicsAction::ProgramCrash,
+                };
                 // TODO: extra copy
                 let value: redshirt_random_interface::ffi::GenerateResponse =
                     match EncodedMessage::from(response).decode() {
@@ -385,12 +401,16 @@ impl Extrinsics for WasiExtrinsics {
                         Err(_) => return ExtrinsicsAction::ProgramCrash,
                     };
 
-                assert!(
-                    u32::try_from(value.result.len()).unwrap_or(u32::max_value()) <= remaining_len
-                );
+                // The random interface handler isn't trusted to send back the number of bytes
+                // that were requested.
+                if value.result.is_empty()
+                    || u32::try_from(value.result.len()).unwrap_or(u32::max_value()) > remaining_len
+                {
+                    return ExtrinsicsAction::ProgramCrash;
+                }
+
                 mem_access.write_memory(out_ptr, &value.result).unwrap(); // TODO: don't unwrap
 
-                assert_ne!(value.result.len(), 0); // TODO: don't unwrap
                 out_ptr += u32::try_from(value.result.len()).unwrap();
                 remaining_len -= u32::try_from(value.result.len()).unwrap();
 
@@ -873,7 +893,7 @@ fn fd_read(
         // TODO: don't panic if allocation size is too large
         let mut list_out = Vec::with_capacity(usize::try_from(num)?);
         for elem in list_buf.chunks(4) {
-            list_out.push(u32::from_le_bytes(<[u8; 4]>::try_from(elem).unwrap()));
+            list_out.push(redshirt_syscalls::read_le::<u32>(elem, 0).unwrap());
         }
         list_out
     };
@@ -1035,7 +1055,7 @@ fn fd_write(
         // TODO: don't panic if allocation size is too large
         let mut list_out = Vec::with_capacity(usize::try_from(num)?);
         for elem in list_buf.chunks(4) {
-            list_out.push(u32::from_le_bytes(<[u8; 4]>::try_from(elem).unwrap()));
+            list_out.push(redshirt_syscalls::read_le::<u32>(elem, 0).unwrap());
         }
         list_out
     };
@@ -1288,17 +1308,15 @@ fn proc_exit(
     mut params: impl ExactSizeIterator<Item = WasmValue>,
     _: &mut impl ExtrinsicsMemoryAccess,
 ) -> Result<(ContextInner, ExtrinsicsAction), WasiCallErr> {
-    let _ret_val = params.next().unwrap().into_i32().unwrap();
+    let ret_val = params.next().unwrap().into_i32().unwrap();
     assert!(params.next().is_none());
 
-    // TODO: returning `ProgramCrash` leads to `unimplemented!()`, so we panic
-    // beforehand for a more explicit message
     // If the exit code is weird, it's probably one of these values:
     // https://github.com/WebAssembly/wasi-libc/blob/320054e84f8f2440def3b1c8700cedb8fd697bf8/libc-top-half/musl/include/sysexits.h
-    panic!("proc_exit called with {:?}", _ret_val);
-
-    // TODO: implement in a better way than crashing?
-    Ok((ContextInner::Finished, ExtrinsicsAction::ProgramCrash))
+    Ok((
+        ContextInner::Finished,
+        ExtrinsicsAction::ProgramExit(ret_val),
+    ))
 }
 
 fn random_get(
@@ -13,6 +13,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use alloc::{
+    string::{String, ToString as _},
+    vec::Vec,
+};
 use core::fmt;
 
 /// Represents a successfully-parsed binary.
@@ -28,9 +32,34 @@ pub struct Module {
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct ModuleHash([u8; 32]);
 
-/// Error that can happen when calling [`ModuleHash::from_bytes`].
+/// Maximum number of memory pages that a module can request, either as the initial size of a
+/// memory or as its maximum size.
+///
+/// This is the limit of the 32 bits address space of Wasm.
+const MAX_MEMORY_PAGES: u32 = 65536;
+
+/// Error that can happen when calling [`Module::from_bytes`].
 #[derive(Debug)]
-pub struct FromBytesError {}
+pub enum FromBytesError {
+    /// The bytes can't be decoded as a Wasm module.
+    InvalidWasm(String),
+    /// The module uses a Wasm feature or extension that isn't supported, such as SIMD or
+    /// atomics instructions.
+    UnsupportedFeature(String),
+    /// The module imports an object that can never be provided. Only functions and a single
+    /// memory can be imported.
+    UnsatisfiableImport {
+        /// Name of the module the object is imported from.
+        module_name: String,
+        /// Name of the imported object.
+        field_name: String,
+    },
+    /// The module declares or imports a memory whose size is above the supported limits.
+    MemoryLimitsExceeded {
+        /// Number of pages requested by the module.
+        requested_pages: u32,
+    },
+}
 
 /// Error that can happen when calling [`ModuleHash::from_base58`].
 #[derive(Debug)]
@@ -39,7 +68,22 @@ pub struct FromBase58Error {}
 impl Module {
     /// Parses a module from WASM bytes.
     pub fn from_bytes(buffer: impl AsRef<[u8]>) -> Result<Self, FromBytesError> {
-        let inner = wasmi::Module::from_buffer(buffer.as_ref()).map_err(|_| FromBytesError {})?;
+        let module =
+            parity_wasm::elements::deserialize_buffer(buffer.as_ref()).map_err(
+                |err| match err {
+                    // Instructions of unsupported extensions are reported as unknown opcodes.
+                    parity_wasm::elements::Error::UnknownOpcode(_)
+                    | parity_wasm::elements::Error::UnsupportedVersion(_) => {
+                        FromBytesError::UnsupportedFeature(err.to_string())
+                    }
+                    err => FromBytesError::InvalidWasm(err.to_string()),
+                },
+            )?;
+
+        check_imports_and_memory(&module)?;
+
+        let inner = wasmi::Module::from_parity_wasm_module(module)
+            .map_err(|err| FromBytesError::InvalidWasm(err.to_string()))?;
         let hash = ModuleHash::from_bytes(buffer);
 
         Ok(Module { inner, hash })
@@ -58,6 +102,40 @@ impl Module {
     }
 }
 
+/// Checks that the imports of the module can be satisfied, and that the memory of the module
+/// fits within the limits.
+fn check_imports_and_memory(module: &parity_wasm::elements::Module) -> Result<(), FromBytesError> {
+    use parity_wasm::elements::External;
+
+    let mut memories = Vec::new();
+
+    for entry in module.import_section().map(|s| s.entries()).unwrap_or(&[]) {
+        match entry.external() {
+            External::Function(_) => {}
+            External::Memory(ty) if memories.is_empty() => memories.push(*ty.limits()),
+            External::Memory(_) | External::Global(_) | External::Table(_) => {
+                return Err(FromBytesError::UnsatisfiableImport {
+                    module_name: entry.module().to_string(),
+                    field_name: entry.field().to_string(),
+                })
+            }
+        }
+    }
+
+    if let Some(section) = module.memory_section() {
+        memories.extend(section.entries().iter().map(|ty| *ty.limits()));
+    }
+
+    for limits in memories {
+        let requested_pages = limits.maximum().unwrap_or(0).max(limits.initial());
+        if requested_pages > MAX_MEMORY_PAGES {
+            return Err(FromBytesError::MemoryLimitsExceeded { requested_pages });
+        }
+    }
+
+    Ok(())
+}
+
 impl From<[u8; 32]> for ModuleHash {
     fn from(hash: [u8; 32]) -> ModuleHash {
         ModuleHash(hash)
@@ -111,12 +189,26 @@ impl fmt::Display for FromBase58Error {
 
 impl fmt::Display for FromBytesError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "FromBytesError")
+        match self {
+            FromBytesError::InvalidWasm(err) => write!(f, "Invalid Wasm module: {}", err),
+            FromBytesError::UnsupportedFeature(err) => write!(f, "Unsupported feature: {}", err),
+            FromBytesError::UnsatisfiableImport {
+                module_name,
+                field_name,
+            } => write!(f, "Unsatisfiable import: {}:{}", module_name, field_name),
+            FromBytesError::MemoryLimitsExceeded { requested_pages } => write!(
+                f,
+                "Memory of {} pages exceeds the limit of {} pages",
+                requested_pages, MAX_MEMORY_PAGES
+            ),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{FromBytesError, Module};
+
     #[test]
     fn empty_wat_works() {
         let _ = from_wat!(local, "(module)");
@@ -136,4 +228,39 @@ mod tests {
             "#
         );
     }
+
+    #[test]
+    fn invalid_bytes_rejected() {
+        match Module::from_bytes(&b"not a wasm module"[..]) {
+            Err(FromBytesError::InvalidWasm(_)) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn imported_global_rejected() {
+        let bytes =
+            redshirt_core_proc_macros::wat_to_bin!(r#"(module (import "foo" "bar" (global i32)))"#);
+        match Module::from_bytes(bytes) {
+            Err(FromBytesError::UnsatisfiableImport {
+                module_name,
+                field_name,
+            }) => {
+                assert_eq!(module_name, "foo");
+                assert_eq!(field_name, "bar");
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn memory_too_large_rejected() {
+        let bytes = redshirt_core_proc_macros::wat_to_bin!(r#"(module (memory 1 65537))"#);
+        match Module::from_bytes(bytes) {
+            Err(FromBytesError::MemoryLimitsExceeded { requested_pages }) => {
+                assert_eq!(requested_pages, 65537)
+            }
+            _ => panic!(),
+        }
+    }
 }
@@ -125,13 +125,13 @@ pub enum WasmValue {
     I64(i64),
     /// A 32-bits floating point number.
     ///
-    /// Contains the bits representation of the float.
-    // TODO: which format is this? IEEE 754?
+    /// Contains the IEEE 754 bits representation of the float, as returned by
+    /// [`f32::to_bits`]. Using the bits rather than an `f32` preserves NaN payloads.
     F32(u32),
     /// A 64-bits floating point number.
     ///
-    /// Contains the bits representation of the float.
-    // TODO: which format is this? IEEE 754?
+    /// Contains the IEEE 754 bits representation of the float, as returned by
+    /// [`f64::to_bits`]. Using the bits rather than an `f64` preserves NaN payloads.
     F64(u64),
 }
 
@@ -176,6 +176,24 @@ impl WasmValue {
             None
         }
     }
+
+    /// Unwraps [`WasmValue::F32`] into its value.
+    pub fn into_f32(self) -> Option<f32> {
+        if let WasmValue::F32(v) = self {
+            Some(f32::from_bits(v))
+        } else {
+            None
+        }
+    }
+
+    /// Unwraps [`WasmValue::F64`] into its value.
+    pub fn into_f64(self) -> Option<f64> {
+        if let WasmValue::F64(v) = self {
+            Some(f64::from_bits(v))
+        } else {
+            None
+        }
+    }
 }
 
 impl From<wasmi::RuntimeValue> for WasmValue {
@@ -183,7 +201,8 @@ impl From<wasmi::RuntimeValue> for WasmValue {
         match val {
             wasmi::RuntimeValue::I32(v) => WasmValue::I32(v),
             wasmi::RuntimeValue::I64(v) => WasmValue::I64(v),
-            _ => unimplemented!(),
+            wasmi::RuntimeValue::F32(v) => WasmValue::F32(v.to_bits()),
+            wasmi::RuntimeValue::F64(v) => WasmValue::F64(v.to_bits()),
         }
     }
 }
@@ -193,7 +212,12 @@ impl From<WasmValue> for wasmi::RuntimeValue {
         match val {
             WasmValue::I32(v) => wasmi::RuntimeValue::I32(v),
             WasmValue::I64(v) => wasmi::RuntimeValue::I64(v),
-            _ => unimplemented!(),
+            WasmValue::F32(v) => {
+                wasmi::RuntimeValue::F32(wasmi::nan_preserving_float::F32::from_bits(v))
+            }
+            WasmValue::F64(v) => {
+                wasmi::RuntimeValue::F64(wasmi::nan_preserving_float::F64::from_bits(v))
+            }
         }
     }
 }
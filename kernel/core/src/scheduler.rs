@@ -24,4 +24,4 @@ mod tests;
 mod vm;
 
 pub use self::ipc::{Core, CoreBuilder, CoreProcess, CoreRunOutcome, ExecuteOut, ReadyToRun};
-pub use self::vm::NewErr;
+pub use self::vm::{NewErr, UnresolvedImport};
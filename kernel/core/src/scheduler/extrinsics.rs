@@ -33,7 +33,7 @@
 //! they are waiting for a notification.
 
 use crate::extrinsics::{
-    Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, ExtrinsicsMemoryAccessErr,
+    Extrinsics, ExtrinsicsAction, ExtrinsicsMemoryAccess, ExtrinsicsMemoryAccessErr, ProcessArgs,
 };
 use crate::module::Module;
 use crate::scheduler::{processes, vm};
@@ -344,11 +344,15 @@ where
     pub fn execute(
         &self,
         module: &Module,
+        args: Option<ProcessArgs>,
         proc_user_data: TPud,
         main_thread_user_data: TTud,
     ) -> Result<(ProcAccess<TPud, TTud, TExt>, ThreadId), vm::NewErr> {
         let proc_user_data = LocalProcessUserData {
-            extrinsics: Default::default(),
+            extrinsics: match args {
+                Some(args) => TExt::with_process_args(args),
+                None => Default::default(),
+            },
             external_user_data: proc_user_data,
         };
         let main_thread_user_data = LocalThreadUserData {
@@ -399,7 +403,18 @@ where
                     }
                     LocalThreadState::OtherExtrinsicApplyAction { context, action } => match action
                     {
-                        ExtrinsicsAction::ProgramCrash => unimplemented!(),
+                        ExtrinsicsAction::ProgramCrash => {
+                            // The thread must be put back in the collection before aborting,
+                            // so that it is part of the dead threads of the process.
+                            let process = thread.process();
+                            drop(thread);
+                            process.abort();
+                        }
+                        ExtrinsicsAction::ProgramExit(exit_code) => {
+                            let process = thread.process();
+                            drop(thread);
+                            process.exit(exit_code);
+                        }
                         ExtrinsicsAction::Resume(value) => {
                             thread.user_data_mut().state = LocalThreadState::ReadyToRun;
                             thread.resume(value)
@@ -592,6 +607,14 @@ where
         }
     }
 
+    /// Returns an iterator to all the processes that exist in the collection.
+    pub fn processes(&self) -> impl ExactSizeIterator<Item = ProcAccess<TPud, TTud, TExt>> + '_ {
+        self.inner.processes().map(move |inner| ProcAccess {
+            parent: self,
+            inner,
+        })
+    }
+
     /// Returns a process by its [`Pid`], if it exists.
     ///
     /// This function returns a "lock".
@@ -729,6 +752,21 @@ where
         &self.inner.user_data().external_user_data
     }
 
+    /// Returns the number of threads of the process.
+    pub fn num_threads(&self) -> usize {
+        self.inner.num_threads()
+    }
+
+    /// Returns the size, in bytes, of the memory of the process.
+    pub fn memory_size(&self) -> usize {
+        self.inner.memory_size()
+    }
+
+    /// Returns true if the process is in the process of terminating.
+    pub fn is_dying(&self) -> bool {
+        self.inner.is_dying()
+    }
+
     /// Adds a new thread to the process, starting the function with the given index and passing
     /// the given parameters.
     ///
@@ -839,7 +877,9 @@ impl<'a, TPud, TTud, TExt: Extrinsics> ThreadEmitMessage<'a, TPud, TTud, TExt> {
     /// Returns true if the caller wants an answer to the message.
     pub fn needs_answer(&mut self) -> bool {
         match self.inner.user_data().state {
-            LocalThreadState::EmitMessage(ref emit) => emit.message_id_write.is_some(),
+            LocalThreadState::EmitMessage(ref emit) => {
+                emit.message_id_write.is_some() && !emit.report_delivery
+            }
             LocalThreadState::OtherExtrinsicEmit {
                 response_expected, ..
             } => response_expected,
@@ -847,6 +887,16 @@ impl<'a, TPud, TTud, TExt: Extrinsics> ThreadEmitMessage<'a, TPud, TTud, TExt> {
         }
     }
 
+    /// Returns true if the caller doesn't want an answer, but wants to be notified when the
+    /// message is delivered or discarded.
+    pub fn report_delivery(&mut self) -> bool {
+        match self.inner.user_data().state {
+            LocalThreadState::EmitMessage(ref emit) => emit.report_delivery,
+            LocalThreadState::OtherExtrinsicEmit { .. } => false,
+            _ => unreachable!(),
+        }
+    }
+
     /// Returns the interface to emit the message on.
     pub fn emit_interface(&mut self) -> &InterfaceHash {
         match self.inner.user_data().state {
@@ -869,8 +919,9 @@ impl<'a, TPud, TTud, TExt: Extrinsics> ThreadEmitMessage<'a, TPud, TTud, TExt> {
     ///
     /// # Panic
     ///
-    /// - Panics if `message_id.is_some() != thread.needs_answer()`. In other words, if
-    /// `needs_answer` is true, then you **must** provide a `MessageId`.
+    /// - Panics if `message_id.is_some() != (thread.needs_answer() || thread.report_delivery())`.
+    /// In other words, if `needs_answer` or `report_delivery` is true, then you **must** provide
+    /// a `MessageId`.
     ///
     pub fn accept_emit(mut self, message_id: Option<MessageId>) -> EncodedMessage {
         match mem::replace(
@@ -214,8 +214,10 @@ pub fn parse_extrinsic_emit_message<TExtr, TPud, TTud>(
         .into_i64()
         .ok_or(ExtrinsicEmitMessageErr::BadParameter)?;
     let needs_answer = (flags & 0x1) != 0;
+    // A delivery report is redundant with the answer, if any.
+    let report_delivery = !needs_answer && (flags & 0x4) != 0;
 
-    let message_id_write = if needs_answer {
+    let message_id_write = if needs_answer || report_delivery {
         Some(
             u32::try_from(
                 params[4]
@@ -233,6 +235,7 @@ pub fn parse_extrinsic_emit_message<TExtr, TPud, TTud>(
         message_id_write,
         message,
         allow_delay: (flags & 0x2) != 0,
+        report_delivery,
     })
 }
 
@@ -241,14 +244,17 @@ pub fn parse_extrinsic_emit_message<TExtr, TPud, TTud>(
 pub struct EmitMessage {
     /// Interface the process wants to emit the message on.
     pub interface: InterfaceHash,
-    /// Location in the process' memory where to write the generated message ID, or `None` if no
-    /// answer is expected.
+    /// Location in the process' memory where to write the generated message ID, or `None` if
+    /// neither an answer nor a delivery report is expected.
     pub message_id_write: Option<u32>,
     /// Message itself. Needs to be delivered to the interface handler.
     pub message: EncodedMessage,
     /// True if we're allowed to block the thread to wait for an interface handler to be
     /// available.
     pub allow_delay: bool,
+    /// True if the process doesn't expect an answer but wants to receive a notification when the
+    /// message is delivered or discarded. If true, `message_id_write` is always `Some`.
+    pub report_delivery: bool,
 }
 
 /// Error that [`parse_extrinsic_emit_message`] can return.
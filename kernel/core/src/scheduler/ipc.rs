@@ -14,9 +14,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    extrinsics::Extrinsics,
+    extrinsics::{Extrinsics, ProcessArgs},
     id_pool::IdPool,
-    module::Module,
+    module::{Module, ModuleHash},
     scheduler::{
         extrinsics::{self, ThreadAccessAccess as _},
         vm,
@@ -187,6 +187,9 @@ pub enum CoreRunOutcome {
 /// Additional information about a process.
 #[derive(Debug)]
 struct Process {
+    /// Hash of the module the process has been started from.
+    module_hash: ModuleHash,
+
     /// Notifications available for retrieval by the process by calling `next_notification`.
     notifications_queue: notifications_queue::NotificationsQueue,
 
@@ -326,28 +329,68 @@ impl<TExt: Extrinsics> Core<TExt> {
         Some(CoreProcess { process: p })
     }
 
+    /// Returns an iterator to all the processes that currently exist.
+    pub fn processes(&self) -> impl ExactSizeIterator<Item = CoreProcess<TExt>> + '_ {
+        self.processes
+            .processes()
+            .map(|process| CoreProcess { process })
+    }
+
     /// After [`CoreRunOutcome::InterfaceMessage`] is generated, use this method to accept the
     /// message and resume the thread that is emitting the message.
     ///
     /// If the message [expects an answer](`CoreRunOutcome::InterfaceMessage::needs_answer`), it
     /// must later be answered with [`Core::answer_message`].
     ///
+    /// If the emitter has asked for a delivery report, accepting the message is considered as
+    /// delivering it, and the emitter is notified.
+    ///
     /// Returns `None` if the message doesn't exist or no longer exists, which can typically
     /// happen if the program has been aborted in parallel.
     pub fn accept_interface_message(&self, message_id: MessageId) -> Option<(Pid, EncodedMessage)> {
+        let (pid, message, _, report_delivery) = self.accept_interface_message_inner(message_id)?;
+        if report_delivery {
+            self.answer_message(message_id, Ok(EncodedMessage(Vec::new())));
+        }
+        Some((pid, message))
+    }
+
+    /// After [`CoreRunOutcome::InterfaceMessage`] is generated, use this method to resume the
+    /// thread that is emitting the message and throw away the message.
+    ///
+    /// Contrary to [`Core::reject_interface_message`], the call to `emit_message` succeeds. If the
+    /// message expects an answer or a delivery report, it is answered with an error.
+    pub fn discard_interface_message(&self, message_id: MessageId) {
+        if let Some((_, _, needs_answer, report_delivery)) =
+            self.accept_interface_message_inner(message_id)
+        {
+            if needs_answer || report_delivery {
+                self.answer_message(message_id, Err(()));
+            }
+        }
+    }
+
+    /// Resumes the thread that is emitting the given message, and returns the emitter, the
+    /// message, and whether it needs an answer or a delivery report.
+    fn accept_interface_message_inner(
+        &self,
+        message_id: MessageId,
+    ) -> Option<(Pid, EncodedMessage, bool, bool)> {
         let (pid, tid) = self.pending_accept_messages.lock().remove(&message_id)?;
 
         self.pending_answer_messages.lock().insert(message_id, pid);
 
         match self.processes.interrupted_thread_by_id(tid).unwrap() {
             extrinsics::ThreadAccess::EmitMessage(mut thread) => {
-                let message = if thread.needs_answer() {
+                let needs_answer = thread.needs_answer();
+                let report_delivery = thread.report_delivery();
+                let message = if needs_answer || report_delivery {
                     thread.accept_emit(Some(message_id))
                 } else {
                     thread.accept_emit(None)
                 };
 
-                Some((pid, message))
+                Some((pid, message, needs_answer, report_delivery))
             }
             _ => unreachable!(),
         }
@@ -362,6 +405,19 @@ impl<TExt: Extrinsics> Core<TExt> {
     /// Might panic if the message is in the wrong state.
     ///
     pub fn reject_immediate_interface_message(&self, message_id: MessageId) {
+        self.reject_interface_message_inner(message_id, true)
+    }
+
+    /// After [`CoreRunOutcome::InterfaceMessage`] is generated, use this method to refuse the
+    /// message. The emitter is resumed and its call to `emit_message` returns an error.
+    ///
+    /// Contrary to [`Core::reject_immediate_interface_message`], this works no matter the value
+    /// of [`CoreRunOutcome::InterfaceMessage::immediate`].
+    pub fn reject_interface_message(&self, message_id: MessageId) {
+        self.reject_interface_message_inner(message_id, false)
+    }
+
+    fn reject_interface_message_inner(&self, message_id: MessageId, must_be_immediate: bool) {
         let (_, tid) = match self.pending_accept_messages.lock().remove(&message_id) {
             Some(v) => v,
             None => return, // Process might have been killed in-between.
@@ -369,7 +425,9 @@ impl<TExt: Extrinsics> Core<TExt> {
 
         match self.processes.interrupted_thread_by_id(tid) {
             Ok(extrinsics::ThreadAccess::EmitMessage(mut thread)) => {
-                assert!(!thread.allow_delay());
+                if must_be_immediate {
+                    assert!(!thread.allow_delay());
+                }
                 thread.refuse_emit();
             }
             Err(extrinsics::ThreadByIdErr::RunningOrDead) => {}
@@ -406,13 +464,21 @@ impl<TExt: Extrinsics> Core<TExt> {
     /// Start executing the module passed as parameter.
     ///
     /// Each import of the [`Module`](crate::module::Module) is resolved.
-    pub fn execute(&self, module: &Module) -> Result<(CoreProcess<TExt>, ThreadId), vm::NewErr> {
+    ///
+    /// If `args` is `Some`, the extrinsics of the process are built with
+    /// [`Extrinsics::with_process_args`](crate::extrinsics::Extrinsics::with_process_args).
+    pub fn execute(
+        &self,
+        module: &Module,
+        args: Option<ProcessArgs>,
+    ) -> Result<(CoreProcess<TExt>, ThreadId), vm::NewErr> {
         let proc_metadata = Process {
+            module_hash: module.hash().clone(),
             notifications_queue: notifications_queue::NotificationsQueue::new(),
             wait_notifications_threads: waiting_threads::WaitingThreads::new(),
         };
 
-        let (process, main_tid) = self.processes.execute(module, proc_metadata, ())?;
+        let (process, main_tid) = self.processes.execute(module, args, proc_metadata, ())?;
 
         Ok((CoreProcess { process }, main_tid))
     }
@@ -443,6 +509,27 @@ impl<'a, TExt: Extrinsics> CoreProcess<'a, TExt> {
         self.process.pid()
     }
 
+    /// Returns the hash of the module the process has been started from.
+    pub fn module_hash(&self) -> &ModuleHash {
+        &self.process.user_data().module_hash
+    }
+
+    /// Returns the number of threads of the process.
+    pub fn num_threads(&self) -> usize {
+        self.process.num_threads()
+    }
+
+    /// Returns the size, in bytes, of the memory of the process.
+    pub fn memory_size(&self) -> usize {
+        self.process.memory_size()
+    }
+
+    /// Returns true if the process has been aborted or has exited, but its termination hasn't
+    /// been reported yet.
+    pub fn is_dying(&self) -> bool {
+        self.process.is_dying()
+    }
+
     /// Adds a new thread to the process, starting the function with the given index and passing
     /// the given parameters.
     pub fn start_thread(
@@ -282,11 +282,11 @@ impl<TExtr, TPud, TTud> ProcessesCollection<TExtr, TPud, TTud> {
                         if expected_signature == obtained_signature {
                             return Ok(*index);
                         } else {
-                            // TODO: way to report the signature mismatch?
+                            return Err(Some(expected_signature.clone()));
                         }
                     }
 
-                    Err(())
+                    Err(None)
                 },
             )?
         };
@@ -904,6 +904,32 @@ impl<'a, TExtr, TPud, TTud> ProcAccess<'a, TExtr, TPud, TTud> {
         &self.process.as_ref().unwrap().user_data
     }
 
+    /// Returns the number of threads of the process, or 0 if the process has been marked as
+    /// dead.
+    pub fn num_threads(&self) -> usize {
+        let process_state = self.process.as_ref().unwrap().lock.lock();
+        if process_state.dead.is_some() {
+            return 0;
+        }
+        process_state.vm.num_threads()
+    }
+
+    /// Returns the size, in bytes, of the memory of the process, or 0 if the process has been
+    /// marked as dead.
+    pub fn memory_size(&self) -> usize {
+        let process_state = self.process.as_ref().unwrap().lock.lock();
+        if process_state.dead.is_some() {
+            return 0;
+        }
+        process_state.vm.memory_size()
+    }
+
+    /// Returns true if the process has been marked as aborting or exiting, but its termination
+    /// hasn't been reported yet.
+    pub fn is_dying(&self) -> bool {
+        self.process.as_ref().unwrap().lock.lock().dead.is_some()
+    }
+
     /// Adds a new thread to the process, starting the function with the given index and passing
     /// the given parameters.
     ///
@@ -943,6 +969,31 @@ impl<'a, TExtr, TPud, TTud> ProcAccess<'a, TExtr, TPud, TTud> {
     ///
     /// Calling [`abort`](ProcAccess::abort) a second time or more has no effect.
     pub fn abort(&self) {
+        // TODO: replace with better error once we no longer expose wasmi::Trap in the API
+        #[derive(Debug)]
+        struct HostErr;
+        impl wasmi::HostError for HostErr {}
+        impl fmt::Display for HostErr {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "Aborted")
+            }
+        }
+
+        self.mark_dead(Err(From::from(wasmi::TrapKind::Host(Box::new(HostErr)))));
+    }
+
+    /// Marks the process as terminating gracefully, as if its main thread had returned
+    /// `exit_code`.
+    ///
+    /// The termination will happen after all locks to this process have been released.
+    ///
+    /// Has no effect if the process is already aborting or exiting.
+    pub fn exit(&self, exit_code: i32) {
+        self.mark_dead(Ok(Some(crate::WasmValue::I32(exit_code))));
+    }
+
+    /// Marks the process as dead with the given outcome, unless it is already dead.
+    fn mark_dead(&self, outcome: Result<Option<crate::WasmValue>, wasmi::Trap>) {
         let mut process_state = self.process.as_ref().unwrap().lock.lock();
 
         if process_state.dead.is_some() {
@@ -962,19 +1013,9 @@ impl<'a, TExtr, TPud, TTud> ProcAccess<'a, TExtr, TPud, TTud> {
             dead_threads.push((tid, user_data));
         }
 
-        // TODO: replace with better error once we no longer expose wasmi::Trap in the API
-        #[derive(Debug)]
-        struct HostErr;
-        impl wasmi::HostError for HostErr {}
-        impl fmt::Display for HostErr {
-            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                write!(f, "Aborted")
-            }
-        }
-
         process_state.dead = Some(ProcessDeadState {
             dead_threads,
-            outcome: Err(From::from(wasmi::TrapKind::Host(Box::new(HostErr)))),
+            outcome,
         });
     }
 }
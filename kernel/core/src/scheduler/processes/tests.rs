@@ -83,6 +83,32 @@ fn aborting_works() {
     };
 }
 
+#[test]
+fn process_stats() {
+    let module = from_wat!(
+        local,
+        r#"(module
+        (memory $mem 2)
+        (export "memory" (memory $mem))
+        (func $_start (result i32)
+            i32.const 5)
+        (export "_start" (func $_start)))
+    "#
+    );
+    let processes = ProcessesCollectionBuilder::<()>::with_seed([0; 32]).build();
+    let pid = processes.execute(&module, (), ()).unwrap().0.pid();
+
+    let process = processes.processes().next().unwrap();
+    assert_eq!(process.pid(), pid);
+    assert_eq!(process.num_threads(), 1);
+    assert_eq!(process.memory_size(), 2 * 65536);
+    assert!(!process.is_dying());
+
+    process.abort();
+    assert!(process.is_dying());
+    assert_eq!(process.num_threads(), 0);
+}
+
 #[test]
 fn many_processes() {
     let module = from_wat!(
@@ -29,7 +29,7 @@ fn trapping_module() {
     );
 
     let core = CoreBuilder::<NoExtrinsics>::with_seed([0; 64]).build();
-    let expected_pid = core.execute(&module).unwrap().0.pid();
+    let expected_pid = core.execute(&module, None).unwrap().0.pid();
 
     let event = loop {
         if let Some(ev) = core.run().now_or_never().unwrap().or_run() {
@@ -18,7 +18,7 @@ use crate::{module::Module, primitives::Signature, ValueType, WasmValue};
 use alloc::{
     borrow::{Cow, ToOwned as _},
     boxed::Box,
-    format,
+    string::String,
     vec::Vec,
 };
 use core::{
@@ -199,6 +199,22 @@ pub enum NewErr {
     MultipleMemoriesNotSupported,
     /// If a "__indirect_function_table" symbol is provided, it must be a table.
     IndirectTableIsntTable,
+    /// Some of the functions imported by the module couldn't be resolved.
+    UnresolvedFunctionImports(Vec<UnresolvedImport>),
+}
+
+/// Function imported by a module that couldn't be resolved.
+#[derive(Debug, Clone)]
+pub struct UnresolvedImport {
+    /// Name of the module the function is imported from.
+    pub module_name: String,
+    /// Name of the function.
+    pub field_name: String,
+    /// Signature of the function, as declared by the module.
+    pub obtained_signature: Signature,
+    /// If a function with that name is available but has a different signature, contains the
+    /// signature of that function.
+    pub expected_signature: Option<Signature>,
 }
 
 /// Error that can happen when starting a new thread.
@@ -232,18 +248,25 @@ impl<T> ProcessStateMachine<T> {
     /// The closure is called for each import that the module has. It must assign a number to each
     /// import, or return an error if the import can't be resolved. When the VM calls one of these
     /// functions, this number will be returned back in order for the user to know how to handle
-    /// the call.
+    /// the call. In case of error, the closure can return the signature that the function was
+    /// expected to have, if a function with that name exists.
+    ///
+    /// All the imports are resolved before an error is returned, so that the returned
+    /// [`NewErr::UnresolvedFunctionImports`] lists all the imports that couldn't be resolved.
     ///
     /// A single main thread (whose user data is passed by parameter) is automatically created and
     /// is paused at the start of the "_start" function of the module.
     pub fn new(
         module: &Module,
         main_thread_user_data: T,
-        mut symbols: impl FnMut(&str, &str, &Signature) -> Result<usize, ()>,
+        mut symbols: impl FnMut(&str, &str, &Signature) -> Result<usize, Option<Signature>>,
     ) -> Result<Self, NewErr> {
         struct ImportResolve<'a> {
-            func: RefCell<&'a mut dyn FnMut(&str, &str, &Signature) -> Result<usize, ()>>,
+            func: RefCell<
+                &'a mut dyn FnMut(&str, &str, &Signature) -> Result<usize, Option<Signature>>,
+            >,
             memory: RefCell<&'a mut Option<wasmi::MemoryRef>>,
+            unresolved: RefCell<Vec<UnresolvedImport>>,
         }
 
         impl<'a> wasmi::ImportResolver for ImportResolve<'a> {
@@ -254,13 +277,20 @@ impl<T> ProcessStateMachine<T> {
                 signature: &wasmi::Signature,
             ) -> Result<wasmi::FuncRef, wasmi::Error> {
                 let closure = &mut **self.func.borrow_mut();
-                let index = match closure(module_name, field_name, &From::from(signature)) {
+                let obtained_signature = Signature::from(signature);
+                let index = match closure(module_name, field_name, &obtained_signature) {
                     Ok(i) => i,
-                    Err(_) => {
-                        return Err(wasmi::Error::Instantiation(format!(
-                            "Couldn't resolve `{}`:`{}`",
-                            module_name, field_name
-                        )))
+                    Err(expected_signature) => {
+                        // Rather than failing immediately, we keep resolving the other imports
+                        // in order to report all the problems at once. The function returned
+                        // here is never called, as instantiation fails afterwards.
+                        self.unresolved.borrow_mut().push(UnresolvedImport {
+                            module_name: module_name.to_owned(),
+                            field_name: field_name.to_owned(),
+                            obtained_signature,
+                            expected_signature,
+                        });
+                        usize::max_value()
                     }
                 };
 
@@ -319,9 +349,14 @@ impl<T> ProcessStateMachine<T> {
             let resolve = ImportResolve {
                 func: RefCell::new(&mut symbols),
                 memory: RefCell::new(&mut imported_memory),
+                unresolved: RefCell::new(Vec::new()),
             };
             let not_started = wasmi::ModuleInstance::new(module.as_ref(), &resolve)
                 .map_err(NewErr::Interpreter)?;
+            let unresolved = resolve.unresolved.into_inner();
+            if !unresolved.is_empty() {
+                return Err(NewErr::UnresolvedFunctionImports(unresolved));
+            }
             (not_started, imported_memory)
         };
 
@@ -474,6 +509,13 @@ impl<T> ProcessStateMachine<T> {
         self.threads.len()
     }
 
+    /// Returns the size, in bytes, of the memory of the process.
+    pub fn memory_size(&self) -> usize {
+        self.memory.as_ref().map_or(0, |mem| {
+            wasmi::memory_units::Bytes::from(mem.current_size()).0
+        })
+    }
+
     /// Returns the thread with the given index. The index is between `0` and
     /// [`num_threads`](ProcessStateMachine::num_threads).
     ///
@@ -700,6 +742,30 @@ impl fmt::Display for NewErr {
                 f,
                 "If a \"__indirect_function_table\" symbol is provided, it must be a table"
             ),
+            NewErr::UnresolvedFunctionImports(list) => {
+                write!(f, "Unresolved function imports:")?;
+                for (n, import) in list.iter().enumerate() {
+                    if n != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}", import)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for UnresolvedImport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}`:`{}` with signature {:?}",
+            self.module_name, self.field_name, self.obtained_signature
+        )?;
+        match &self.expected_signature {
+            Some(expected) => write!(f, " (expected {:?})", expected),
+            None => write!(f, " (not implemented)"),
         }
     }
 }
@@ -764,6 +830,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn all_unresolved_imports_reported() {
+        let module = from_wat!(
+            local,
+            r#"(module
+            (import "foo" "a" (func $a (result i32)))
+            (import "foo" "b" (func $b (param i32)))
+            (import "foo" "c" (func $c))
+            (func $_start (result i32)
+                i32.const 5)
+            (export "_start" (func $_start)))
+        "#
+        );
+
+        let res = ProcessStateMachine::new(&module, (), |_, name, _| match name {
+            "a" => Err(None),
+            "b" => Ok(0),
+            "c" => Err(Some(crate::sig!((I32)))),
+            _ => unreachable!(),
+        });
+
+        match res {
+            Err(NewErr::UnresolvedFunctionImports(list)) => {
+                assert_eq!(list.len(), 2);
+                assert_eq!(list[0].field_name, "a");
+                assert_eq!(list[0].obtained_signature, crate::sig!(() -> I32));
+                assert!(list[0].expected_signature.is_none());
+                assert_eq!(list[1].field_name, "c");
+                assert_eq!(list[1].expected_signature, Some(crate::sig!((I32))));
+            }
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn main_executes() {
         let module = from_wat!(
@@ -823,6 +923,36 @@ mod tests {
         assert!(state_machine.thread(0).is_none());
     }
 
+    #[test]
+    fn external_call_returning_f64() {
+        let module = from_wat!(
+            local,
+            r#"(module
+            (import "" "test" (func $test (result f64)))
+            (func $_start (result f64)
+                call $test
+                f64.const 0.25
+                f64.add)
+            (export "_start" (func $_start)))
+        "#
+        );
+
+        let mut state_machine = ProcessStateMachine::new(&module, (), |_, _, _| Ok(1)).unwrap();
+        match state_machine.thread(0).unwrap().run(None) {
+            Ok(ExecOutcome::Interrupted { id: 1, .. }) => {}
+            _ => panic!(),
+        }
+
+        let value = WasmValue::F64(1.5f64.to_bits());
+        match state_machine.thread(0).unwrap().run(Some(value)) {
+            Ok(ExecOutcome::ThreadFinished {
+                return_value: Some(ret),
+                ..
+            }) => assert_eq!(ret.into_f64(), Some(1.75)),
+            _ => panic!(),
+        }
+    }
+
     #[test]
     fn poisoning_works() {
         let module = from_wat!(
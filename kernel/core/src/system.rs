@@ -21,6 +21,8 @@
 //! TODO: more details
 //!
 //! - `interface`.
+//! - `power`, which is always privileged. See [`SystemBuilder::with_privileged_interface`].
+//! - `process`, which lets programs enumerate the running processes.
 //!
 
 use crate::extrinsics;
@@ -29,11 +31,23 @@ use crate::scheduler::{self, Core, CoreBuilder, CoreRunOutcome, NewErr};
 use crate::InterfaceHash;
 
 mod interfaces;
+mod message_hooks;
+#[cfg(feature = "tracing")]
+mod message_spans;
 mod pending_answers;
+#[cfg(feature = "message-recording")]
+mod recording;
+
+pub use interfaces::StalledMessage;
+#[cfg(feature = "message-recording")]
+pub use recording::{replay_responses, RecordedEvent};
 
 use alloc::{collections::VecDeque, format, vec::Vec};
-use core::{convert::TryFrom as _, fmt, iter, num::NonZeroU64, sync::atomic::Ordering};
+use core::{
+    convert::TryFrom as _, fmt, iter, num::NonZeroU64, sync::atomic::Ordering, time::Duration,
+};
 use crossbeam_queue::SegQueue;
+use futures::prelude::*;
 use hashbrown::{HashMap, HashSet};
 use nohash_hasher::BuildNoHashHasher;
 use redshirt_syscalls::{Decode, Encode, EncodedMessage, MessageId, Pid};
@@ -53,6 +67,9 @@ pub struct System<TExtr: extrinsics::Extrinsics> {
     /// Collection of messages that have been delivered but are waiting to be answered.
     pending_answers: pending_answers::PendingAnswers,
 
+    /// Observes the messages being processed, for the optional features that need it.
+    hooks: message_hooks::MessageHooks,
+
     /// Total number of processes that have been spawned since initialization.
     num_processes_started: atomic::Atomic<u64>,
 
@@ -65,6 +82,13 @@ pub struct System<TExtr: extrinsics::Extrinsics> {
     /// Interfaces handled natively.
     native_interfaces: HashSet<InterfaceHash, fnv::FnvBuildHasher>,
 
+    /// Interfaces that can only be used by processes that have the corresponding capability.
+    privileged_interfaces: HashSet<InterfaceHash, fnv::FnvBuildHasher>,
+
+    /// For each process, the list of privileged interfaces it is allowed to emit messages on
+    /// and register. Processes that aren't in this map can't use any privileged interface.
+    capabilities: Spinlock<HashMap<Pid, Vec<InterfaceHash>, fnv::FnvBuildHasher>>,
+
     /// Registration ID (i.e. index in [`Interfaces::registrations`]) that handles the `loader`
     /// interface, or `None` is no such program exists yet.
     // TODO: add timeout for loader interface availability?
@@ -131,11 +155,34 @@ pub struct SystemBuilder<TExtr: extrinsics::Extrinsics> {
     /// Interfaces handled natively.
     native_interfaces: HashSet<InterfaceHash, fnv::FnvBuildHasher>,
 
-    /// List of programs to start executing immediately after construction.
-    startup_processes: Vec<Module>,
+    /// Same field as [`System::privileged_interfaces`].
+    privileged_interfaces: HashSet<InterfaceHash, fnv::FnvBuildHasher>,
+
+    /// List of programs to start executing immediately after construction, the privileged
+    /// interfaces they are allowed to use (`None` means all of them), and their arguments.
+    startup_processes: Vec<(
+        Module,
+        Option<Vec<InterfaceHash>>,
+        Option<extrinsics::ProcessArgs>,
+    )>,
 
     /// Same field as [`System::programs_to_load`].
     programs_to_load: SegQueue<ModuleHash>,
+
+    /// See [`SystemBuilder::with_max_queued_messages`].
+    default_max_queued_messages: usize,
+
+    /// See [`SystemBuilder::with_interface_max_queued_messages`].
+    max_queued_messages: HashMap<InterfaceHash, usize, fnv::FnvBuildHasher>,
+
+    /// See [`SystemBuilder::with_message_recording`].
+    #[cfg(feature = "message-recording")]
+    record_messages: bool,
+
+    /// See [`SystemBuilder::with_replayed_interface`].
+    #[cfg(feature = "message-recording")]
+    replayed_interfaces:
+        HashMap<InterfaceHash, Vec<Result<EncodedMessage, ()>>, fnv::FnvBuildHasher>,
 }
 
 /// Event returned by [`System::run`].
@@ -172,16 +219,22 @@ pub enum SystemRunOutcome<'a, TExtr: extrinsics::Extrinsics> {
     ProgramFinished {
         /// Identifier of the process that has stopped.
         pid: Pid,
-        /// Either `Ok(())` if the main thread has ended, or the error that happened in the
-        /// process.
-        // TODO: change error type
-        outcome: Result<(), wasmi::Error>,
+        /// Either `Ok` with the exit code of the program, or the reason why the program has
+        /// crashed.
+        ///
+        /// The exit code is the value passed to `proc_exit`, or the value returned by the main
+        /// function if it returns an `i32`. It is `0` if the main function returns nothing.
+        outcome: Result<i32, TrapReason>,
     },
 
     /// A program has requested metrics from the kernel. Use the [`KernelDebugMetricsRequest`] to
     /// report them.
     KernelDebugMetricsRequest(KernelDebugMetricsRequest<'a, TExtr>),
 
+    /// A program has asked to turn off, restart or suspend the machine. Only generated for
+    /// processes that are allowed to do so.
+    PowerRequest(PowerRequest<'a, TExtr>),
+
     /// A program has emitted a message on a native interface.
     NativeInterfaceMessage {
         /// Hash of the interface. Guaranteed to be one of the interfaces that were passed to
@@ -200,10 +253,23 @@ pub enum SystemRunOutcome<'a, TExtr: extrinsics::Extrinsics> {
     },
 }
 
+/// Outcome of calling [`System::step`].
+#[derive(Debug)]
+pub enum StepOutcome<'a, TExtr: extrinsics::Extrinsics> {
+    /// Nothing can make progress without an external action, such as answering a message
+    /// emitted on a native interface.
+    Idle,
+    /// A scheduling decision has been made and has been entirely handled by the [`System`].
+    Progress,
+    /// A scheduling decision has been made and has led to an event.
+    Event(SystemRunOutcome<'a, TExtr>),
+}
+
 /// See [`SystemRunOutcome::NativeInterfaceMessage::message`].
 pub struct NativeInterfaceMessage<'a, TExtr: extrinsics::Extrinsics> {
     system: &'a System<TExtr>,
     message_id: MessageId,
+    needs_answer: bool,
 }
 
 impl<'a, TExtr: extrinsics::Extrinsics> NativeInterfaceMessage<'a, TExtr> {
@@ -215,11 +281,16 @@ impl<'a, TExtr: extrinsics::Extrinsics> NativeInterfaceMessage<'a, TExtr> {
     /// >           sure to lock some mutex prior to calling this method to ensure that a
     /// >           follow-up message isn't processed earlier than the one returned here.
     pub fn extract(self) -> EncodedMessage {
-        self.system
+        let message = self
+            .system
             .core
             .accept_interface_message(self.message_id)
             .unwrap()
-            .1
+            .1;
+        self.system
+            .hooks
+            .delivered(self.message_id, None, self.needs_answer, &message);
+        message
     }
 }
 
@@ -234,9 +305,46 @@ where
     TExtr: extrinsics::Extrinsics,
 {
     /// Start executing a program.
-    pub fn execute(&self, program: &Module) -> Result<Pid, NewErr> {
+    ///
+    /// If `args` is `Some`, the program can retrieve these arguments and environment variables
+    /// through the extrinsics, for example with WASI's `args_get` and `environ_get`. See
+    /// [`Extrinsics::with_process_args`](extrinsics::Extrinsics::with_process_args).
+    ///
+    /// The program isn't allowed to use any of the interfaces passed to
+    /// [`SystemBuilder::with_privileged_interface`].
+    pub fn execute(
+        &self,
+        program: &Module,
+        args: Option<extrinsics::ProcessArgs>,
+    ) -> Result<Pid, NewErr> {
         self.num_processes_started.fetch_add(1, Ordering::Relaxed);
-        Ok(self.core.execute(program)?.0.pid())
+        Ok(self.core.execute(program, args)?.0.pid())
+    }
+
+    /// Start executing a program that is allowed to emit messages on and register the given
+    /// privileged interfaces.
+    ///
+    /// Interfaces that haven't been passed to [`SystemBuilder::with_privileged_interface`] can
+    /// be used by all programs, and are ignored if passed here.
+    pub fn execute_with_capabilities(
+        &self,
+        program: &Module,
+        args: Option<extrinsics::ProcessArgs>,
+        capabilities: impl IntoIterator<Item = InterfaceHash>,
+    ) -> Result<Pid, NewErr> {
+        let capabilities = capabilities
+            .into_iter()
+            .filter(|interface| self.privileged_interfaces.contains(interface))
+            .collect::<Vec<_>>();
+
+        // The lock is held while the process starts, so that it can't emit a message before
+        // its capabilities are known.
+        let mut capabilities_lock = self.capabilities.lock();
+        let pid = self.execute(program, args)?;
+        if !capabilities.is_empty() {
+            capabilities_lock.insert(pid, capabilities);
+        }
+        Ok(pid)
     }
 
     /// Runs the [`System`] once and returns the outcome.
@@ -264,19 +372,66 @@ where
         }
     }
 
+    /// Advances the [`System`] by exactly one scheduling decision, without waiting.
+    ///
+    /// Contrary to [`System::run`], this function never blocks and never runs more than one
+    /// thread. This is intended for tests that need fine control over the order in which
+    /// events happen. Interfaces passed to [`SystemBuilder::with_native_interface_handler`] can
+    /// be used in order for the test to act as the handler of an interface and answer messages
+    /// with [`System::answer_message`].
+    pub fn step(&self) -> StepOutcome<TExtr> {
+        let event = match self.core.run().now_or_never() {
+            Some(scheduler::ExecuteOut::Direct(event)) => self.inner_event(event),
+            Some(scheduler::ExecuteOut::ReadyToRun(ready_to_run)) => ReadyToRun {
+                system: self,
+                inner: ready_to_run,
+            }
+            .run(),
+            None => return StepOutcome::Idle,
+        };
+
+        match event {
+            Some(event) => StepOutcome::Event(event),
+            None => StepOutcome::Progress,
+        }
+    }
+
     fn inner_event<'a>(
         &'a self,
         event: scheduler::CoreRunOutcome,
     ) -> Option<SystemRunOutcome<'a, TExtr>> {
         match event {
             CoreRunOutcome::ProgramFinished { pid, outcome, .. } => {
-                // TODO: cancel interface registrations ; update loader_registration_id
                 // TODO: notify interface registrations of process destruction
 
-                for _message_id in self.pending_answers.drain_by_answerer(&pid) {
+                // Cancel the interface registrations of the process. Processes that were waiting
+                // to register one of these interfaces take over.
+                for granted in self.interfaces.process_destroyed(pid) {
+                    let response = redshirt_interface_interface::ffi::InterfaceRegisterResponse {
+                        result: Ok(granted.registration_id),
+                    };
+                    self.core
+                        .answer_message(granted.message_id, Ok(response.encode()));
+                    self.interface_handler_set(&granted.interface, granted.registration_id);
+                }
+
+                // Registration IDs are reused. If the process was handling the loader and nobody
+                // took over, the ID must be forgotten before it is given to another registration.
+                if self
+                    .interfaces
+                    .handler(&redshirt_loader_interface::ffi::INTERFACE)
+                    .is_none()
+                {
+                    self.loader_registration_id.store(None, Ordering::Release);
+                }
+
+                for message_id in self.pending_answers.drain_by_answerer(&pid) {
                     // TODO: notify emitter of cancellation
+                    self.hooks.answered(message_id, &Err(()));
                 }
 
+                self.capabilities.lock().remove(&pid);
+
                 if outcome.is_ok() {
                     self.num_processes_finished.fetch_add(1, Ordering::Relaxed);
                 } else {
@@ -285,10 +440,34 @@ where
 
                 return Some(SystemRunOutcome::ProgramFinished {
                     pid,
-                    outcome: outcome.map(|_| ()).map_err(|err| err.into()),
+                    outcome: match outcome {
+                        Ok(Some(crate::WasmValue::I32(exit_code))) => Ok(exit_code),
+                        Ok(_) => Ok(0),
+                        Err(trap) => Err(TrapReason(trap)),
+                    },
                 });
             }
 
+            CoreRunOutcome::InterfaceMessage {
+                pid,
+                needs_answer: _,
+                immediate,
+                message_id,
+                interface,
+            } if interface != redshirt_power_interface::ffi::INTERFACE
+                && !self.is_allowed(pid, &interface) =>
+            {
+                // The emitter doesn't have the capability to use this privileged interface.
+                // Messages on the `power` interface are instead answered below with an error.
+                if immediate {
+                    self.core.reject_immediate_interface_message(message_id);
+                } else {
+                    self.core.discard_interface_message(message_id);
+                }
+
+                None
+            }
+
             CoreRunOutcome::InterfaceMessage {
                 pid,
                 needs_answer,
@@ -305,9 +484,10 @@ where
                 match redshirt_interface_interface::ffi::InterfaceMessage::decode(message) {
                     Ok(redshirt_interface_interface::ffi::InterfaceMessage::Register(
                         interface_hash,
+                        metadata,
                     )) => {
                         // Set the process as interface handler, if possible.
-                        let result = self.set_interface_handler(&interface_hash, pid);
+                        let result = self.set_interface_handler(&interface_hash, pid, metadata);
 
                         let response =
                             redshirt_interface_interface::ffi::InterfaceRegisterResponse {
@@ -319,6 +499,68 @@ where
 
                         None
                     }
+                    Ok(redshirt_interface_interface::ffi::InterfaceMessage::RegisterOrWait(
+                        interface_hash,
+                        metadata,
+                    )) if needs_answer => {
+                        if let Err(err) = self.check_registration(&interface_hash, pid, &metadata) {
+                            let response =
+                                redshirt_interface_interface::ffi::InterfaceRegisterResponse {
+                                    result: Err(err),
+                                };
+                            self.core.answer_message(message_id, Ok(response.encode()));
+                            return None;
+                        }
+
+                        // Set the process as interface handler, or queue the registration. If
+                        // queued, the message is answered when the registration is granted.
+                        if let Some(registration_id) =
+                            self.interfaces.set_interface_handler_or_wait(
+                                interface_hash.clone(),
+                                pid,
+                                message_id,
+                                metadata,
+                            )
+                        {
+                            self.interface_handler_set(&interface_hash, registration_id);
+                            let response =
+                                redshirt_interface_interface::ffi::InterfaceRegisterResponse {
+                                    result: Ok(registration_id),
+                                };
+                            self.core.answer_message(message_id, Ok(response.encode()));
+                        }
+
+                        None
+                    }
+                    Ok(redshirt_interface_interface::ffi::InterfaceMessage::RegisterOrWait(
+                        interface_hash,
+                        metadata,
+                    )) => {
+                        // Without an answer, there is no way to report a registration that is
+                        // granted later. Behave like `Register`.
+                        let _ = self.set_interface_handler(&interface_hash, pid, metadata);
+                        None
+                    }
+                    Ok(redshirt_interface_interface::ffi::InterfaceMessage::QueryHandler(
+                        interface_hash,
+                    )) => {
+                        if needs_answer {
+                            let handler =
+                                self.interfaces
+                                    .handler(&interface_hash)
+                                    .map(|(pid, metadata)| {
+                                        redshirt_interface_interface::ffi::HandlerInfo {
+                                            pid,
+                                            metadata,
+                                        }
+                                    });
+                            let response =
+                                redshirt_interface_interface::ffi::QueryHandlerResponse { handler };
+                            self.core.answer_message(message_id, Ok(response.encode()));
+                        }
+
+                        None
+                    }
                     Ok(redshirt_interface_interface::ffi::InterfaceMessage::NextMessage(
                         registration_id,
                     )) => {
@@ -331,11 +573,20 @@ where
                                     message_id,
                                     pid,
                                 ) {
-                                    Ok(Some(delivery)) => {
+                                    Ok(Some(interfaces::MessageQuery::Deliver(delivery))) => {
                                         if self.deliver(delivery).is_err() {
                                             continue;
                                         }
                                     }
+                                    Ok(Some(interfaces::MessageQuery::InterfaceRegistered {
+                                        query_message_id,
+                                        interface,
+                                    })) => {
+                                        self.notify_interface_registered(
+                                            query_message_id,
+                                            &interface,
+                                        );
+                                    }
                                     Ok(None) => {}
                                     Err(()) => {
                                         self.core.answer_message(message_id, Err(()));
@@ -350,6 +601,23 @@ where
 
                         None
                     }
+                    Ok(
+                        redshirt_interface_interface::ffi::InterfaceMessage::WatchRegistrations(
+                            registration_id,
+                        ),
+                    ) => {
+                        let result = self
+                            .interfaces
+                            .watch_registrations(registration_id.into(), pid);
+                        if needs_answer {
+                            self.core.answer_message(
+                                message_id,
+                                result.map(|()| EncodedMessage(Vec::new())),
+                            );
+                        }
+
+                        None
+                    }
                     Ok(redshirt_interface_interface::ffi::InterfaceMessage::Answer(
                         answered_message_id,
                         answer_bytes,
@@ -361,11 +629,10 @@ where
                             .remove(&answered_message_id, &pid)
                             .is_ok()
                         {
+                            let response = answer_bytes.map(EncodedMessage);
+                            self.hooks.answered(answered_message_id, &response);
                             // TODO: must handle emitter is native
-                            self.core.answer_message(
-                                answered_message_id,
-                                answer_bytes.map(EncodedMessage),
-                            );
+                            self.core.answer_message(answered_message_id, response);
                         }
 
                         None
@@ -422,6 +689,119 @@ where
                 None
             }
 
+            CoreRunOutcome::InterfaceMessage {
+                pid,
+                needs_answer,
+                immediate: _,
+                message_id,
+                interface,
+            } if interface == redshirt_power_interface::ffi::INTERFACE => {
+                // Handling messages on the `power` interface.
+                let (_, message) = match self.core.accept_interface_message(message_id) {
+                    Some(v) => v,
+                    None => return None,
+                };
+
+                let message_id = if needs_answer { Some(message_id) } else { None };
+
+                let action = match redshirt_power_interface::ffi::PowerMessage::decode(message) {
+                    Ok(action) => action,
+                    Err(_) => {
+                        if let Some(message_id) = message_id {
+                            self.core.answer_message(message_id, Err(()));
+                        }
+                        return None;
+                    }
+                };
+
+                if !self.is_allowed(pid, &interface) {
+                    if let Some(message_id) = message_id {
+                        let response = redshirt_power_interface::ffi::PowerResponse {
+                            result: Err(redshirt_power_interface::ffi::PowerError::Unauthorized),
+                        };
+                        self.core.answer_message(message_id, Ok(response.encode()));
+                    }
+                    return None;
+                }
+
+                Some(SystemRunOutcome::PowerRequest(PowerRequest {
+                    system: self,
+                    message_id,
+                    action,
+                }))
+            }
+
+            CoreRunOutcome::InterfaceMessage {
+                pid: _,
+                needs_answer,
+                immediate: _,
+                message_id,
+                interface,
+            } if interface == redshirt_process_interface::ffi::INTERFACE => {
+                // Handling messages on the `process` interface.
+                let (_, message) = match self.core.accept_interface_message(message_id) {
+                    Some(v) => v,
+                    None => return None,
+                };
+
+                if !needs_answer {
+                    return None;
+                }
+
+                let response =
+                    match redshirt_process_interface::ffi::ProcessMessage::decode(message) {
+                        Ok(redshirt_process_interface::ffi::ProcessMessage::List) => {
+                            let processes = self
+                                .core
+                                .processes()
+                                .map(|process| self.process_info(&process))
+                                .collect();
+                            redshirt_process_interface::ffi::ListResponse { processes }.encode()
+                        }
+                        Ok(redshirt_process_interface::ffi::ProcessMessage::Info(target)) => {
+                            let process = self
+                                .core
+                                .process_by_id(target)
+                                .map(|process| self.process_info(&process));
+                            redshirt_process_interface::ffi::InfoResponse { process }.encode()
+                        }
+                        Err(_) => {
+                            self.core.answer_message(message_id, Err(()));
+                            return None;
+                        }
+                    };
+
+                self.core.answer_message(message_id, Ok(response));
+                None
+            }
+
+            #[cfg(feature = "message-recording")]
+            CoreRunOutcome::InterfaceMessage {
+                pid,
+                needs_answer,
+                message_id,
+                interface,
+                ..
+            } if self.hooks.recorder.is_replayed(&interface) => {
+                // Answers to messages on this interface are taken from a recording.
+                self.hooks
+                    .emitted(message_id, pid, &interface, needs_answer);
+                let (_, message) = match self.core.accept_interface_message(message_id) {
+                    Some(v) => v,
+                    None => return None,
+                };
+                self.hooks
+                    .delivered(message_id, None, needs_answer, &message);
+
+                if needs_answer {
+                    let response = self.hooks.recorder.next_replayed_response(&interface);
+                    self.hooks.answered(message_id, &response);
+                    self.core.answer_message(message_id, response);
+                }
+
+                None
+            }
+
             CoreRunOutcome::InterfaceMessage {
                 pid: emitter_pid,
                 needs_answer,
@@ -429,6 +809,9 @@ where
                 interface,
                 ..
             } if self.native_interfaces.contains(&interface) => {
+                self.hooks
+                    .emitted(message_id, emitter_pid, &interface, needs_answer);
+
                 return Some(SystemRunOutcome::NativeInterfaceMessage {
                     interface,
                     emitter_pid,
@@ -436,6 +819,7 @@ where
                     message: NativeInterfaceMessage {
                         system: self,
                         message_id,
+                        needs_answer,
                     },
                 });
             }
@@ -447,6 +831,9 @@ where
                 message_id,
                 interface,
             } => {
+                self.hooks
+                    .emitted(message_id, pid, &interface, needs_answer);
+
                 match self.interfaces.emit_interface_message(
                     &interface,
                     message_id,
@@ -461,10 +848,13 @@ where
                         }
                     }
                     interfaces::EmitInterfaceMessage::Reject => {
-                        debug_assert!(immediate);
-                        self.core.reject_immediate_interface_message(message_id);
+                        // Either the message is immediate and can't be delivered, or the queue
+                        // of the interface is full.
+                        self.core.reject_interface_message(message_id);
+                        self.hooks.answered(message_id, &Err(()));
                     }
-                    interfaces::EmitInterfaceMessage::Queued => {
+                    interfaces::EmitInterfaceMessage::Queued
+                    | interfaces::EmitInterfaceMessage::Blocked => {
                         debug_assert!(!immediate);
                     }
                 }
@@ -479,33 +869,149 @@ where
     /// > **Note**: The validity of the [`MessageId`] is not checked, for performance reasons.
     /// >           Passing a wrong value can lead to logic errors.
     pub fn answer_message(&self, message_id: MessageId, response: Result<EncodedMessage, ()>) {
+        self.hooks.answered(message_id, &response);
         self.core.answer_message(message_id, response);
     }
 
+    /// Returns the list of messages that have been waiting for at least `min_duration` for the
+    /// interface they are emitted on to have a handler. Each of these messages corresponds to a
+    /// paused thread.
+    ///
+    /// This is meant to be called periodically in order to detect programs that are stuck.
+    ///
+    /// `now` must be the current time according to a monotonic clock. The time a message starts
+    /// waiting is approximated as the value of `now` during the first call to this method that
+    /// finds this message waiting. Consequently, the precision of the returned durations depends
+    /// on how often this method is called.
+    pub fn stalled_messages(&self, now: Duration, min_duration: Duration) -> Vec<StalledMessage> {
+        self.interfaces.stalled_messages(now, min_duration)
+    }
+
+    /// Returns the list of messages emitted by the given process that expect an answer and
+    /// haven't been answered yet, alongside with the interface they have been emitted on.
+    ///
+    /// This includes both messages that are waiting to be delivered to the interface handler and
+    /// messages that have been delivered but not answered yet. Messages emitted on interfaces
+    /// handled natively aren't included.
+    pub fn pending_messages(&self, pid: Pid) -> Vec<(MessageId, InterfaceHash)> {
+        let mut list = self.interfaces.queued_messages_by_emitter(pid);
+        list.extend(self.pending_answers.by_emitter(&pid));
+        list
+    }
+
+    /// Returns the list of messages-related events that have happened since the previous call,
+    /// in order.
+    ///
+    /// Always returns an empty list if [`SystemBuilder::with_message_recording`] hasn't been
+    /// called.
+    #[cfg(feature = "message-recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "message-recording")))]
+    pub fn take_recorded_events(&self) -> Vec<RecordedEvent> {
+        self.hooks.recorder.take_events()
+    }
+
     fn set_interface_handler(
         &self,
         interface_hash: &InterfaceHash,
         pid: Pid,
+        metadata: Vec<u8>,
     ) -> Result<NonZeroU64, redshirt_interface_interface::ffi::InterfaceRegisterError> {
+        self.check_registration(interface_hash, pid, &metadata)?;
+
         let result = self
             .interfaces
-            .set_interface_handler(interface_hash.clone(), pid);
+            .set_interface_handler(interface_hash.clone(), pid, metadata);
+
+        if let Ok(registration_id) = result {
+            self.interface_handler_set(interface_hash, registration_id);
+        }
+
+        result
+    }
+
+    /// Checks whether the given process is allowed to register the given interface with the
+    /// given metadata.
+    fn check_registration(
+        &self,
+        interface_hash: &InterfaceHash,
+        pid: Pid,
+        metadata: &[u8],
+    ) -> Result<(), redshirt_interface_interface::ffi::InterfaceRegisterError> {
+        if !self.is_allowed(pid, interface_hash) {
+            return Err(redshirt_interface_interface::ffi::InterfaceRegisterError::Unauthorized);
+        }
+
+        if metadata.len() > redshirt_interface_interface::ffi::MAX_METADATA_LEN {
+            return Err(
+                redshirt_interface_interface::ffi::InterfaceRegisterError::MetadataTooLarge,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Builds the information about a process reported on the `process` interface.
+    fn process_info(
+        &self,
+        process: &scheduler::CoreProcess<TExtr>,
+    ) -> redshirt_process_interface::ffi::ProcessInfo {
+        let pid = process.pid();
+        redshirt_process_interface::ffi::ProcessInfo {
+            pid,
+            module_hash: process.module_hash().clone().into(),
+            memory_size: u64::try_from(process.memory_size()).unwrap(),
+            num_threads: u32::try_from(process.num_threads()).unwrap_or(u32::max_value()),
+            pending_messages: u32::try_from(self.pending_messages(pid).len())
+                .unwrap_or(u32::max_value()),
+            state: if process.is_dying() {
+                redshirt_process_interface::ffi::ProcessState::Terminating
+            } else {
+                redshirt_process_interface::ffi::ProcessState::Running
+            },
+        }
+    }
+
+    /// Returns true if the given process is allowed to emit messages on and register the given
+    /// interface.
+    fn is_allowed(&self, pid: Pid, interface: &InterfaceHash) -> bool {
+        if !self.privileged_interfaces.contains(interface) {
+            return true;
+        }
+
+        self.capabilities
+            .lock()
+            .get(&pid)
+            .map_or(false, |capabilities| capabilities.contains(interface))
+    }
+
+    /// Must be called after a handler has been set for the given interface.
+    fn interface_handler_set(&self, interface_hash: &InterfaceHash, registration_id: NonZeroU64) {
+        for query_message_id in self.interfaces.interface_registered(interface_hash) {
+            self.notify_interface_registered(query_message_id, interface_hash);
+        }
 
         // Special handling if the registered interface is the loader.
         if *interface_hash == redshirt_loader_interface::ffi::INTERFACE {
-            if let Ok(registration_id) = result {
-                self.loader_registration_id.store(
-                    Some(usize::try_from(registration_id.get()).unwrap()),
-                    Ordering::Release,
-                );
-
-                while let Some(h) = self.programs_to_load.pop() {
-                    todo!() // TODO:
-                }
+            self.loader_registration_id.store(
+                Some(usize::try_from(registration_id.get()).unwrap()),
+                Ordering::Release,
+            );
+
+            while let Some(h) = self.programs_to_load.pop() {
+                todo!() // TODO:
             }
         }
+    }
 
-        result
+    /// Answers a `NextMessage` query with a notification that the given interface has been
+    /// registered.
+    fn notify_interface_registered(&self, query_message_id: MessageId, interface: &InterfaceHash) {
+        let notification =
+            redshirt_interface_interface::ffi::build_interface_registered_notification(interface);
+        self.core.answer_message(
+            query_message_id,
+            Ok(EncodedMessage(notification.into_bytes())),
+        );
     }
 
     /// Applies an [`interfaces::MessageDelivery`].
@@ -535,8 +1041,21 @@ where
         // The message is added to `pending_answers` before being actually delivered, in order to
         // avoid a situation where the recipient manages to answer the message before it is added
         // to `pending_answers`.
-        self.pending_answers
-            .add(delivery.to_deliver_message_id, delivery.recipient_pid);
+        self.hooks.delivered(
+            delivery.to_deliver_message_id,
+            Some(delivery.recipient_pid),
+            delivery.needs_answer,
+            &message,
+        );
+
+        if delivery.needs_answer {
+            self.pending_answers.add(
+                delivery.to_deliver_message_id,
+                delivery.recipient_pid,
+                emitter_pid,
+                delivery.interface.clone(),
+            );
+        }
 
         self.core.answer_message(
             delivery.query_message_id,
@@ -615,6 +1134,84 @@ impl<'a, TExtr: extrinsics::Extrinsics> fmt::Debug for KernelDebugMetricsRequest
     }
 }
 
+/// Request from a process to change the power state of the machine.
+#[must_use]
+pub struct PowerRequest<'a, TExtr: extrinsics::Extrinsics> {
+    system: &'a System<TExtr>,
+    message_id: Option<MessageId>,
+    action: redshirt_power_interface::ffi::PowerMessage,
+}
+
+impl<'a, TExtr: extrinsics::Extrinsics> PowerRequest<'a, TExtr> {
+    /// Returns the action that has been requested.
+    pub fn action(&self) -> redshirt_power_interface::ffi::PowerMessage {
+        self.action
+    }
+
+    /// Prepares the [`System`] for the machine to be turned off or restarted.
+    ///
+    /// All the messages that have been delivered to an interface handler but not answered yet
+    /// are answered with an error, then all the interface handlers are stopped. The machine
+    /// can then be powered off.
+    pub fn teardown(self) {
+        for message_id in self.system.pending_answers.drain() {
+            self.system.hooks.answered(message_id, &Err(()));
+            self.system.core.answer_message(message_id, Err(()));
+        }
+
+        for pid in self.system.interfaces.handlers() {
+            if let Some(process) = self.system.core.process_by_id(pid) {
+                process.abort();
+            }
+        }
+    }
+
+    /// Notifies the emitter that the request has been successfully performed. Meant to be
+    /// called after the machine wakes up from suspension.
+    pub fn resumed(self) {
+        self.answer(Ok(()));
+    }
+
+    /// Notifies the emitter that the request isn't supported.
+    pub fn reject(self) {
+        self.answer(Err(redshirt_power_interface::ffi::PowerError::Unsupported));
+    }
+
+    fn answer(self, result: Result<(), redshirt_power_interface::ffi::PowerError>) {
+        if let Some(message_id) = self.message_id {
+            let response = redshirt_power_interface::ffi::PowerResponse { result };
+            self.system
+                .core
+                .answer_message(message_id, Ok(response.encode()));
+        }
+    }
+}
+
+impl<'a, TExtr: extrinsics::Extrinsics> fmt::Debug for PowerRequest<'a, TExtr> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("PowerRequest").field(&self.action).finish()
+    }
+}
+
+/// Reason why a program has crashed. See [`SystemRunOutcome::ProgramFinished`].
+///
+/// This includes problems in the WebAssembly code, such as reaching an `unreachable`
+/// instruction, as well as programs being forcefully aborted.
+// TODO: expose the actual reason as an enum instead of an opaque error
+pub struct TrapReason(wasmi::Trap);
+
+impl fmt::Debug for TrapReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for TrapReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
 impl<TExtr> SystemBuilder<TExtr>
 where
     TExtr: extrinsics::Extrinsics,
@@ -631,8 +1228,15 @@ where
             core,
             startup_processes: Vec::new(),
             native_interfaces: Default::default(),
+            privileged_interfaces: iter::once(redshirt_power_interface::ffi::INTERFACE).collect(),
             load_source_virtual_pid,
             programs_to_load: SegQueue::new(),
+            default_max_queued_messages: 1024,
+            max_queued_messages: Default::default(),
+            #[cfg(feature = "message-recording")]
+            record_messages: false,
+            #[cfg(feature = "message-recording")]
+            replayed_interfaces: Default::default(),
         }
     }
 
@@ -644,6 +1248,81 @@ where
         self
     }
 
+    /// Marks the given interface as privileged.
+    ///
+    /// Only processes that have been given the capability to do so can emit messages on or
+    /// register a privileged interface. See [`SystemBuilder::with_startup_process`],
+    /// [`SystemBuilder::with_restricted_startup_process`] and
+    /// [`System::execute_with_capabilities`]. Other processes have their messages rejected.
+    ///
+    /// The `power` interface is always privileged, and messages on it from processes without
+    /// the capability are answered with [`PowerError::Unauthorized`].
+    ///
+    /// Duplicates are ignored.
+    ///
+    /// [`PowerError::Unauthorized`]: redshirt_power_interface::ffi::PowerError::Unauthorized
+    pub fn with_privileged_interface(mut self, hash: InterfaceHash) -> Self {
+        self.privileged_interfaces.insert(hash);
+        self
+    }
+
+    /// Sets the maximum number of messages that can be waiting for an interface handler to pull
+    /// them. Applies to all interfaces, except the ones passed to
+    /// [`SystemBuilder::with_interface_max_queued_messages`].
+    ///
+    /// When this limit is reached, messages emitted on the interface that don't expect an answer
+    /// are rejected, and `emit_message` returns an error to their emitter. Messages that expect
+    /// an answer are instead held back, and their emitter stays blocked until enough queued
+    /// messages have been delivered for them to be queued.
+    ///
+    /// The default value is 1024.
+    pub fn with_max_queued_messages(mut self, max: usize) -> Self {
+        self.default_max_queued_messages = max;
+        self
+    }
+
+    /// Same as [`SystemBuilder::with_max_queued_messages`], but only for the given interface.
+    pub fn with_interface_max_queued_messages(
+        mut self,
+        interface: InterfaceHash,
+        max: usize,
+    ) -> Self {
+        self.max_queued_messages.insert(interface, max);
+        self
+    }
+
+    /// Enables recording all the messages that are emitted, delivered, and answered, including
+    /// their content. The recorded events can be retrieved with
+    /// [`System::take_recorded_events`].
+    ///
+    /// > **Note**: The events are kept in memory until they are retrieved. Make sure to call
+    /// >           [`System::take_recorded_events`] regularly.
+    #[cfg(feature = "message-recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "message-recording")))]
+    pub fn with_message_recording(mut self) -> Self {
+        self.record_messages = true;
+        self
+    }
+
+    /// Answers the messages emitted on the given interface with the given responses, in order,
+    /// instead of delivering them to a handler. Once all the responses have been used, messages
+    /// are answered with an error. Messages that don't need an answer are discarded.
+    ///
+    /// The responses can be obtained from a previous recording with [`replay_responses`]. This
+    /// makes it possible to reproduce an interaction with an interface handler without running
+    /// the handler.
+    #[cfg(feature = "message-recording")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "message-recording")))]
+    pub fn with_replayed_interface(
+        mut self,
+        interface: InterfaceHash,
+        responses: impl IntoIterator<Item = Result<EncodedMessage, ()>>,
+    ) -> Self {
+        self.replayed_interfaces
+            .insert(interface, responses.into_iter().collect());
+        self
+    }
+
     /// Adds a process to the list of processes that the [`System`] must start as part of the
     /// startup process.
     ///
@@ -652,9 +1331,43 @@ where
     ///
     /// By default, the list is empty. Should at least contain a process that handles the `loader`
     /// interface.
+    ///
+    /// Processes added through this method are allowed to use all the privileged interfaces.
+    /// See [`SystemBuilder::with_privileged_interface`].
     pub fn with_startup_process(mut self, process: impl Into<Module>) -> Self {
         let process = process.into();
-        self.startup_processes.push(process);
+        self.startup_processes.push((process, None, None));
+        self
+    }
+
+    /// Same as [`SystemBuilder::with_startup_process`], except that the process is only allowed
+    /// to use the given privileged interfaces instead of all of them.
+    pub fn with_restricted_startup_process(
+        mut self,
+        process: impl Into<Module>,
+        capabilities: impl IntoIterator<Item = InterfaceHash>,
+    ) -> Self {
+        let process = process.into();
+        self.startup_processes
+            .push((process, Some(capabilities.into_iter().collect()), None));
+        self
+    }
+
+    /// Same as [`SystemBuilder::with_restricted_startup_process`], except that the process is
+    /// started with the given command-line arguments and environment variables. See
+    /// [`System::execute`].
+    pub fn with_startup_process_args(
+        mut self,
+        process: impl Into<Module>,
+        capabilities: impl IntoIterator<Item = InterfaceHash>,
+        args: extrinsics::ProcessArgs,
+    ) -> Self {
+        let process = process.into();
+        self.startup_processes.push((
+            process,
+            Some(capabilities.into_iter().collect()),
+            Some(args),
+        ));
         self
     }
 
@@ -689,21 +1402,47 @@ where
         let core = self.core.build();
 
         let num_processes_started = u64::try_from(self.startup_processes.len()).unwrap();
-        for program in self.startup_processes {
-            core.execute(&program)?;
+        let mut capabilities =
+            HashMap::with_capacity_and_hasher(self.startup_processes.len(), Default::default());
+        let privileged_interfaces = &self.privileged_interfaces;
+        for (program, process_capabilities, args) in self.startup_processes {
+            let pid = core.execute(&program, args)?.0.pid();
+
+            let process_capabilities = match process_capabilities {
+                Some(list) => list
+                    .into_iter()
+                    .filter(|interface| privileged_interfaces.contains(interface))
+                    .collect::<Vec<_>>(),
+                None => privileged_interfaces.iter().cloned().collect(),
+            };
+            if !process_capabilities.is_empty() {
+                capabilities.insert(pid, process_capabilities);
+            }
         }
 
         self.native_interfaces.shrink_to_fit();
+        self.privileged_interfaces.shrink_to_fit();
 
         Ok(System {
             core,
             load_source_virtual_pid: self.load_source_virtual_pid,
-            interfaces: Default::default(),
+            interfaces: interfaces::Interfaces::with_max_queued_messages(
+                self.default_max_queued_messages,
+                self.max_queued_messages,
+            ),
             pending_answers: Default::default(),
+            hooks: message_hooks::MessageHooks {
+                #[cfg(feature = "tracing")]
+                spans: Default::default(),
+                #[cfg(feature = "message-recording")]
+                recorder: recording::Recorder::new(self.record_messages, self.replayed_interfaces),
+            },
             num_processes_started: atomic::Atomic::new(num_processes_started),
             num_processes_finished: atomic::Atomic::new(0),
             num_processes_trap: atomic::Atomic::new(0),
             native_interfaces: self.native_interfaces,
+            privileged_interfaces: self.privileged_interfaces,
+            capabilities: Spinlock::new(capabilities),
             loader_registration_id: atomic::Atomic::new(None),
             loading_programs: Spinlock::new(Default::default()),
             programs_to_load: self.programs_to_load,
@@ -712,11 +1451,686 @@ where
 }
 #[cfg(test)]
 mod tests {
-    use crate::extrinsics;
+    use super::{StepOutcome, SystemBuilder, SystemRunOutcome};
+    use crate::{extrinsics, module::Module, InterfaceHash};
+    use core::iter;
+    use redshirt_power_interface::ffi::PowerMessage;
 
     #[test]
     fn send_sync() {
         fn is_send_sync<T: Send + Sync>() {}
         is_send_sync::<super::System<extrinsics::NoExtrinsics>>()
     }
+
+    #[test]
+    fn step_native_message_then_finish() {
+        // Emits the message `[1, 2, 3, 4, 5, 6, 7, 8]` without expecting an answer on the
+        // interface below, then returns.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (type $t0 (func (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "emit_message" (func $emit_message (type $t0)))
+    (func $_start (result i32)
+        (local $l0 i32)
+        get_global $g0
+        i32.const 64
+        i32.sub
+        tee_local $l0
+        set_global $g0
+        get_local $l0
+        i64.const 3978425819141910832
+        i64.store offset=32
+        get_local $l0
+        i64.const 2820983053732684064
+        i64.store offset=24
+        get_local $l0
+        i64.const 1663540288323457296
+        i64.store offset=16
+        get_local $l0
+        i64.const 506097522914230528
+        i64.store offset=8
+        get_local $l0
+        i32.const 1048576
+        i64.extend_u/i32
+        i64.const 34359738368
+        i64.or
+        i64.store offset=41 align=1
+        get_local $l0
+        i32.const 1
+        i32.store8 offset=40
+        get_local $l0
+        i32.const 8
+        i32.add
+        get_local $l0
+        i32.const 40
+        i32.add
+        i32.const 1
+        i32.or
+        i32.const 1
+        i64.const 2
+        get_local $l0
+        i32.const 56
+        i32.add
+        call $emit_message
+        drop
+        get_local $l0
+        i32.const 64
+        i32.add
+        set_global $g0
+        i32.const 0)
+    (table $T0 1 1 anyfunc)
+    (memory $memory 17)
+    (global $g0 (mut i32) (i32.const 1048576))
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 1048576) "\01\02\03\04\05\06\07\08"))"#
+        );
+
+        let interface = InterfaceHash::from_raw_hash([
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15,
+            0x16, 0x17, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x30, 0x31, 0x32, 0x33,
+            0x34, 0x35, 0x36, 0x37,
+        ]);
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_native_interface_handler(interface.clone())
+            .build()
+            .unwrap();
+        let pid = system.execute(&module, None).unwrap();
+
+        let mut events = Vec::new();
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::NativeInterfaceMessage {
+                    interface: msg_interface,
+                    emitter_pid,
+                    message_id,
+                    message,
+                }) => {
+                    assert_eq!(msg_interface, interface);
+                    assert_eq!(emitter_pid, pid);
+                    assert!(message_id.is_none());
+                    assert_eq!(message.extract().0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+                    events.push("message");
+                }
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished {
+                    pid: finished_pid,
+                    outcome,
+                }) => {
+                    assert_eq!(finished_pid, pid);
+                    assert!(outcome.is_ok());
+                    events.push("finished");
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert_eq!(events, &["message", "finished"]);
+    }
+
+    #[test]
+    fn proc_exit_reports_exit_code() {
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (func $_start
+        i32.const 42
+        call $proc_exit
+        unreachable))"#
+        );
+
+        let system = SystemBuilder::<extrinsics::wasi::WasiExtrinsics>::new([0; 64])
+            .build()
+            .unwrap();
+        let pid = system.execute(&module, None).unwrap();
+
+        loop {
+            match system.step() {
+                StepOutcome::Idle => panic!(),
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished {
+                    pid: finished_pid,
+                    outcome,
+                }) => {
+                    assert_eq!(finished_pid, pid);
+                    assert_eq!(outcome.unwrap(), 42);
+                    break;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn trap_reported() {
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (func $_start
+        unreachable)
+    (export "_start" (func $_start)))"#
+        );
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .build()
+            .unwrap();
+        system.execute(&module, None).unwrap();
+
+        loop {
+            match system.step() {
+                StepOutcome::Idle => panic!(),
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_err());
+                    break;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn startup_process_args_passed_to_wasi() {
+        // Traps unless its arguments are `prog` and `arg`, and its environment is `A=b`.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (func $_start (result i32)
+        (if (i32.ne (call $args_sizes_get (i32.const 0) (i32.const 4)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 0)) (i32.const 2))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 4)) (i32.const 9))
+            (then unreachable))
+        (if (i32.ne (call $args_get (i32.const 16) (i32.const 64)) (i32.const 0))
+            (then unreachable))
+        ;; "prog\00arg\00"
+        (if (i64.ne (i64.load (i32.const 64)) (i64.const 0x67726100676f7270))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 72)) (i32.const 0))
+            (then unreachable))
+
+        (if (i32.ne (call $environ_sizes_get (i32.const 0) (i32.const 4)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 0)) (i32.const 1))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 4)) (i32.const 4))
+            (then unreachable))
+        (if (i32.ne (call $environ_get (i32.const 16) (i32.const 128)) (i32.const 0))
+            (then unreachable))
+        ;; "A=b\00"
+        (if (i32.ne (i32.load (i32.const 128)) (i32.const 0x00623d41))
+            (then unreachable))
+        i32.const 0))"#
+        );
+
+        let system = SystemBuilder::<extrinsics::wasi::WasiExtrinsics>::new([0; 64])
+            .with_startup_process_args(
+                module,
+                iter::empty(),
+                extrinsics::ProcessArgs {
+                    args: vec![b"prog".to_vec(), b"arg".to_vec()],
+                    env_vars: vec![b"A=b".to_vec()],
+                },
+            )
+            .build()
+            .unwrap();
+
+        loop {
+            match system.step() {
+                StepOutcome::Idle => panic!(),
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    break;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+    }
+
+    /// Builds a module that emits a `PowerMessage::Shutdown` on the `power` interface without
+    /// expecting an answer, then returns.
+    fn power_shutdown_module() -> Module {
+        from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\27\6d\5e\79\68\dd\ec\63\67\5b\a4\1f\c9\0b\1e\30\34\01\bb\99\f5\d1\5e\ce\ed\61\1b\ad\e1\07\ce\63")
+    (data (i32.const 32) "\28\00\00\00\01\00\00\00")
+    (data (i32.const 40) "\00")
+    (func $_start (result i32)
+        i32.const 0
+        i32.const 32
+        i32.const 1
+        i64.const 0
+        i32.const 48
+        call $emit_message
+        drop
+        i32.const 0))"#
+        )
+    }
+
+    /// Builds a module that emits a `PowerMessage::Shutdown` on the `power` interface, waits for
+    /// the answer, then returns. Traps if the answer isn't `PowerError::Unauthorized`.
+    fn power_shutdown_unauthorized_module() -> Module {
+        from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\27\6d\5e\79\68\dd\ec\63\67\5b\a4\1f\c9\0b\1e\30\34\01\bb\99\f5\d1\5e\ce\ed\61\1b\ad\e1\07\ce\63")
+    (data (i32.const 32) "\28\00\00\00\01\00\00\00")
+    (data (i32.const 40) "\00")
+    (func $_start (result i32)
+        ;; Emit the message with the `needs_answer` flag. The message id is written at 48.
+        (if (i32.ne (call $emit_message (i32.const 0) (i32.const 32) (i32.const 1) (i64.const 1) (i32.const 48)) (i32.const 0))
+            (then unreachable))
+        ;; Wait for the answer, written at 64. It must be a successful response whose body is
+        ;; `Err(PowerError::Unauthorized)`.
+        (if (i32.ne (call $next_notification (i32.const 48) (i32.const 1) (i32.const 64) (i32.const 64) (i64.const 1)) (i32.const 16))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 77)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 78)) (i32.const 1))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 79)) (i32.const 0))
+            (then unreachable))
+        i32.const 0))"#
+        )
+    }
+
+    #[test]
+    fn power_request_from_startup_process() {
+        let module = power_shutdown_module();
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_startup_process(module)
+            .build()
+            .unwrap();
+
+        let mut power_requests = 0;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::PowerRequest(request)) => {
+                    assert_eq!(request.action(), PowerMessage::Shutdown);
+                    request.teardown();
+                    power_requests += 1;
+                }
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert_eq!(power_requests, 1);
+    }
+
+    #[test]
+    fn power_request_from_other_process_unauthorized() {
+        let module = power_shutdown_unauthorized_module();
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .build()
+            .unwrap();
+        let pid = system.execute(&module, None).unwrap();
+
+        let mut finished = false;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished {
+                    pid: finished_pid,
+                    outcome,
+                }) => {
+                    assert_eq!(finished_pid, pid);
+                    assert!(outcome.is_ok());
+                    finished = true;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert!(finished);
+    }
+
+    #[test]
+    fn privileged_interface_rejected_without_capability() {
+        let module = power_shutdown_unauthorized_module();
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_privileged_interface(redshirt_power_interface::ffi::INTERFACE)
+            .with_restricted_startup_process(module, iter::empty())
+            .build()
+            .unwrap();
+
+        let mut finished = false;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    finished = true;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert!(finished);
+    }
+
+    #[test]
+    fn privileged_interface_accepted_with_capability() {
+        let module = power_shutdown_module();
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_privileged_interface(redshirt_power_interface::ffi::INTERFACE)
+            .with_restricted_startup_process(
+                module,
+                iter::once(redshirt_power_interface::ffi::INTERFACE),
+            )
+            .build()
+            .unwrap();
+
+        let mut power_requests = 0;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::PowerRequest(request)) => {
+                    request.reject();
+                    power_requests += 1;
+                }
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert_eq!(power_requests, 1);
+    }
+
+    #[test]
+    fn delivery_reported_to_emitter() {
+        // Emits the message `[5]` on the interface below with the `report_delivery` flag, and
+        // traps unless the notification that follows is an empty successful answer.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22\22")
+    (data (i32.const 32) "\28\00\00\00\01\00\00\00")
+    (data (i32.const 40) "\05")
+    (func $_start (result i32)
+        (if (i32.ne (call $emit_message (i32.const 0) (i32.const 32) (i32.const 1) (i64.const 4) (i32.const 48)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (call $next_notification (i32.const 48) (i32.const 1) (i32.const 64) (i32.const 64) (i64.const 1)) (i32.const 14))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 77)) (i32.const 0))
+            (then unreachable))
+        i32.const 0))"#
+        );
+
+        let interface = InterfaceHash::from_raw_hash([0x22; 32]);
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_native_interface_handler(interface.clone())
+            .build()
+            .unwrap();
+        let pid = system.execute(&module, None).unwrap();
+
+        let mut events = Vec::new();
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::NativeInterfaceMessage {
+                    interface: msg_interface,
+                    emitter_pid,
+                    message_id,
+                    message,
+                }) => {
+                    assert_eq!(msg_interface, interface);
+                    assert_eq!(emitter_pid, pid);
+                    // The delivery report is sent by the system and not by the handler.
+                    assert!(message_id.is_none());
+                    assert_eq!(message.extract().0, &[5]);
+                    events.push("message");
+                }
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    events.push("finished");
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert_eq!(events, &["message", "finished"]);
+    }
+
+    #[test]
+    fn discarded_message_not_delivered() {
+        // Emits the message `[5]` on the interface below with the `allow_delay` and
+        // `report_delivery` flags, and traps unless the notification that follows is an error.
+        // Without `allow_delay`, the message would be rejected rather than discarded.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33\33")
+    (data (i32.const 32) "\28\00\00\00\01\00\00\00")
+    (data (i32.const 40) "\05")
+    (func $_start (result i32)
+        (if (i32.ne (call $emit_message (i32.const 0) (i32.const 32) (i32.const 1) (i64.const 6) (i32.const 48)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (call $next_notification (i32.const 48) (i32.const 1) (i32.const 64) (i32.const 64) (i64.const 1)) (i32.const 14))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 77)) (i32.const 1))
+            (then unreachable))
+        i32.const 0))"#
+        );
+
+        // The interface is handled natively, but the process isn't allowed to use it. The
+        // message is thus discarded by the system instead of being delivered.
+        let interface = InterfaceHash::from_raw_hash([0x33; 32]);
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_native_interface_handler(interface.clone())
+            .with_privileged_interface(interface)
+            .with_restricted_startup_process(module, iter::empty())
+            .build()
+            .unwrap();
+
+        let mut finished = false;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    finished = true;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert!(finished);
+    }
+
+    #[cfg(feature = "message-recording")]
+    #[test]
+    fn replayed_interface_answered_and_recorded() {
+        use super::RecordedEvent;
+        use redshirt_syscalls::EncodedMessage;
+
+        // Emits the message `[5]` expecting an answer on the interface below, and traps unless
+        // the answer is `[42]`.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11")
+    (data (i32.const 32) "\28\00\00\00\01\00\00\00")
+    (data (i32.const 40) "\05")
+    (func $_start (result i32)
+        (if (i32.ne (call $emit_message (i32.const 0) (i32.const 32) (i32.const 1) (i64.const 1) (i32.const 48)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (call $next_notification (i32.const 48) (i32.const 1) (i32.const 64) (i32.const 64) (i64.const 1)) (i32.const 15))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 77)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 78)) (i32.const 42))
+            (then unreachable))
+        i32.const 0))"#
+        );
+
+        let interface = InterfaceHash::from_raw_hash([0x11; 32]);
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .with_message_recording()
+            .with_replayed_interface(interface.clone(), iter::once(Ok(EncodedMessage(vec![42]))))
+            .build()
+            .unwrap();
+        let pid = system.execute(&module, None).unwrap();
+
+        let mut finished = false;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    finished = true;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+        assert!(finished);
+
+        let events = system.take_recorded_events();
+        assert_eq!(events.len(), 3);
+        let message_id = match &events[0] {
+            RecordedEvent::Emitted {
+                message_id,
+                emitter_pid,
+                interface: i,
+                needs_answer: true,
+            } if *emitter_pid == pid && *i == interface => *message_id,
+            _ => panic!(),
+        };
+        assert_eq!(
+            events[1..],
+            [
+                RecordedEvent::Delivered {
+                    message_id,
+                    handler_pid: None,
+                    body: EncodedMessage(vec![5]),
+                },
+                RecordedEvent::Answered {
+                    message_id,
+                    response: Ok(EncodedMessage(vec![42])),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn register_with_too_large_metadata_rejected() {
+        // Sends a `Register` message on the interface interface with a metadata of 1025 bytes,
+        // and traps unless the answer is `Err(InterfaceRegisterError::MetadataTooLarge)`.
+        let module = from_wat!(
+            local,
+            r#"
+(module
+    (import "redshirt" "emit_message" (func $emit_message (param i32 i32 i32 i64 i32) (result i32)))
+    (import "redshirt" "next_notification" (func $next_notification (param i32 i32 i32 i32 i64) (result i32)))
+    (memory $memory 1)
+    (export "memory" (memory 0))
+    (export "_start" (func $_start))
+    (data (i32.const 0) "\49\6e\56\14\8c\d4\2b\c3\9b\4e\bf\5e\b6\2c\60\4d\7d\d5\70\92\4d\4f\70\df\b3\da\f6\fe\dc\65\93\8a")
+    (data (i32.const 32) "\00\01\00\00\23\00\00\00\00\04\00\00\01\04\00\00")
+    ;; `Register` variant, the interface to register, and the SCALE-compact length of the
+    ;; metadata. The metadata itself is 1025 zero bytes found at 1024.
+    (data (i32.const 256) "\00\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\05\10")
+    (func $_start (result i32)
+        (if (i32.ne (call $emit_message (i32.const 0) (i32.const 32) (i32.const 2) (i64.const 1) (i32.const 48)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (call $next_notification (i32.const 48) (i32.const 1) (i32.const 64) (i32.const 64) (i64.const 1)) (i32.const 16))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 77)) (i32.const 0))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 78)) (i32.const 1))
+            (then unreachable))
+        (if (i32.ne (i32.load8_u (i32.const 79)) (i32.const 2))
+            (then unreachable))
+        i32.const 0))"#
+        );
+
+        let system = SystemBuilder::<extrinsics::NoExtrinsics>::new([0; 64])
+            .build()
+            .unwrap();
+        system.execute(&module, None).unwrap();
+
+        let mut finished = false;
+        loop {
+            match system.step() {
+                StepOutcome::Idle => break,
+                StepOutcome::Progress => {}
+                StepOutcome::Event(SystemRunOutcome::ProgramFinished { outcome, .. }) => {
+                    assert!(outcome.is_ok());
+                    finished = true;
+                }
+                StepOutcome::Event(_) => panic!(),
+            }
+        }
+
+        assert!(finished);
+    }
 }
@@ -15,9 +15,10 @@
 
 // TODO: doc
 
-use alloc::collections::VecDeque;
-use core::{convert::TryFrom as _, mem, num::NonZeroU64};
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{convert::TryFrom as _, mem, num::NonZeroU64, time::Duration};
 use hashbrown::{hash_map::Entry, HashMap};
+use nohash_hasher::BuildNoHashHasher;
 use redshirt_syscalls::{InterfaceHash, MessageId, Pid};
 
 pub struct Interfaces {
@@ -29,6 +30,31 @@ pub struct Interfaces {
 struct Inner {
     interfaces: HashMap<InterfaceHash, Interface, fnv::FnvBuildHasher>,
     registrations: slab::Slab<InterfaceRegistration>,
+    /// For each interface, registration requests waiting for the current handler to go away.
+    /// Contains the message to answer once the registration is granted, the [`Pid`] of the
+    /// process to register, and the metadata of the registration.
+    waiting_registrations:
+        HashMap<InterfaceHash, VecDeque<(MessageId, Pid, Vec<u8>)>, fnv::FnvBuildHasher>,
+    /// Information about each message found in one of the `pending_accept` fields or in
+    /// [`Inner::blocked_messages`].
+    queued_messages: HashMap<MessageId, QueuedMessage, BuildNoHashHasher<u64>>,
+    /// For each interface whose `pending_accept` field is full, messages that need an answer and
+    /// that are waiting for room to be made in that field. Their emitter stays paused meanwhile.
+    blocked_messages: HashMap<InterfaceHash, VecDeque<MessageId>, fnv::FnvBuildHasher>,
+    /// Maximum number of messages in the `pending_accept` field of an interface. Overrides
+    /// [`Inner::default_max_queued_messages`].
+    max_queued_messages: HashMap<InterfaceHash, usize, fnv::FnvBuildHasher>,
+    /// Same as [`Inner::max_queued_messages`], for interfaces not in this list.
+    default_max_queued_messages: usize,
+}
+
+#[derive(Debug)]
+struct QueuedMessage {
+    /// Process that has emitted the message.
+    emitter_pid: Pid,
+    /// If the message is waiting for the interface to have a handler, the value passed to
+    /// [`Interfaces::stalled_messages`] the first time it has been found waiting.
+    blocked_since: Option<Duration>,
 }
 
 #[derive(Debug)]
@@ -41,12 +67,7 @@ enum Interface {
     /// Interface has no registered handler yet.
     NotRegistered {
         /// Messages emitted by programs and that haven't been accepted yet are pushed to this
-        /// field.
-        ///
-        /// No limit is enforced on the size of this container. However, since each entry
-        /// corresponds to a thread currently being paused, the total number of entries across
-        /// all `pending_accept` fields is bounded by the total number of threads across all
-        /// processes.
+        /// field, up to the maximum number of queued messages of the interface.
         pending_accept: VecDeque<(MessageId, bool)>,
     },
 }
@@ -55,16 +76,37 @@ enum Interface {
 struct InterfaceRegistration {
     interface: InterfaceHash,
     pid: Pid,
+    /// Opaque metadata provided by the handler when registering.
+    metadata: Vec<u8>,
     /// Messages of type `NextMessage` sent on the interface interface and that must be answered
     /// with the next interface message.
     queries: VecDeque<MessageId>,
     /// If [`InterfaceRegistration::queries`] is empty, messages emitted by programs and that
     /// haven't been accepted yet are pushed to this field.
     pending_accept: VecDeque<(MessageId, bool)>,
+    /// If true, the registration must be notified whenever an interface gets registered.
+    watches_registrations: bool,
+    /// If [`InterfaceRegistration::queries`] is empty, interfaces that got registered and that
+    /// haven't been reported yet are pushed to this field.
+    pending_registered: VecDeque<InterfaceHash>,
 }
 
 impl Interfaces {
+    /// Initializes a new [`Interfaces`] where the number of queued messages is unbounded.
     pub fn new() -> Self {
+        Interfaces::with_max_queued_messages(usize::max_value(), Default::default())
+    }
+
+    /// Initializes a new [`Interfaces`].
+    ///
+    /// Once an interface has `default_max_queued_messages` messages, or the value in
+    /// `max_queued_messages` if the interface is in this list, waiting to be delivered, messages
+    /// that don't need an answer are rejected. Messages that need an answer are blocked until
+    /// room has been made in the queue.
+    pub fn with_max_queued_messages(
+        default_max_queued_messages: usize,
+        max_queued_messages: HashMap<InterfaceHash, usize, fnv::FnvBuildHasher>,
+    ) -> Self {
         Interfaces {
             inner: spinning_top::Spinlock::new(Inner {
                 interfaces: Default::default(),
@@ -76,17 +118,29 @@ impl Interfaces {
                     let _id = registrations.insert(InterfaceRegistration {
                         interface: InterfaceHash::from_raw_hash(Default::default()),
                         pid: 0xdeadbeef.into(), // TODO: ?!
+                        metadata: Vec::new(),
                         queries: VecDeque::new(),
                         pending_accept: VecDeque::new(),
+                        watches_registrations: false,
+                        pending_registered: VecDeque::new(),
                     });
                     assert_eq!(_id, 0);
                     registrations
                 },
+                waiting_registrations: Default::default(),
+                queued_messages: Default::default(),
+                blocked_messages: Default::default(),
+                max_queued_messages,
+                default_max_queued_messages,
             }),
         }
     }
 
     /// Called when a process requests to deliver a message to an interface handler.
+    ///
+    /// If too many messages are already waiting to be delivered, messages that don't need an
+    /// answer are rejected and messages that need an answer are blocked. See
+    /// [`Interfaces::with_max_queued_messages`].
     pub fn emit_interface_message(
         &self,
         interface_hash: &InterfaceHash,
@@ -98,6 +152,12 @@ impl Interfaces {
         let mut interfaces = self.inner.lock();
         let interfaces = &mut *interfaces; // Avoids borrow errors.
 
+        let max_queued_messages = interfaces
+            .max_queued_messages
+            .get(interface_hash)
+            .copied()
+            .unwrap_or(interfaces.default_max_queued_messages);
+
         let entry = match interfaces.interfaces.entry(interface_hash.clone()) {
             Entry::Occupied(e) => e.into_mut(),
             Entry::Vacant(_) if immediate => {
@@ -122,21 +182,40 @@ impl Interfaces {
                         query_message_id,
                         recipient_pid: registration.pid,
                     })
-                } else if immediate {
+                } else if immediate
+                    || (!needs_answer && registration.pending_accept.len() >= max_queued_messages)
+                {
                     EmitInterfaceMessage::Reject
+                } else if registration.pending_accept.len() >= max_queued_messages {
+                    interfaces.block_message(interface_hash, message_id, emitter_pid)
                 } else {
                     registration
                         .pending_accept
                         .push_back((message_id, needs_answer));
+                    interfaces.queued_messages.insert(
+                        message_id,
+                        QueuedMessage {
+                            emitter_pid,
+                            blocked_since: None,
+                        },
+                    );
                     EmitInterfaceMessage::Queued
                 }
             }
             Interface::NotRegistered { pending_accept } => {
-                if immediate {
+                if immediate || (!needs_answer && pending_accept.len() >= max_queued_messages) {
                     EmitInterfaceMessage::Reject
+                } else if pending_accept.len() >= max_queued_messages {
+                    interfaces.block_message(interface_hash, message_id, emitter_pid)
                 } else {
-                    // TODO: is this unbounded queue attackable?
                     pending_accept.push_back((message_id, needs_answer));
+                    interfaces.queued_messages.insert(
+                        message_id,
+                        QueuedMessage {
+                            emitter_pid,
+                            blocked_since: None,
+                        },
+                    );
                     EmitInterfaceMessage::Queued
                 }
             }
@@ -149,33 +228,46 @@ impl Interfaces {
     /// Must be passed a [`RegistrationId`] and the [`Pid`] that the registration is expected to
     /// belong to. The method verifies that the ownership matches.
     ///
-    /// On success, can return a [`MessageDelivery`] representing a delivery of a certain
-    /// message earlier pushed using [`Interfaces::emit_interface_message`] to
-    /// `expected_registrer_pid` by answering `query_message_id`.
+    /// On success, can return a [`MessageQuery`] indicating how to answer `query_message_id`.
+    /// This is either a delivery of a certain message earlier pushed using
+    /// [`Interfaces::emit_interface_message`] to `expected_registrer_pid`, or a report of an
+    /// interface earlier passed to [`Interfaces::interface_registered`].
     pub fn emit_message_query(
         &self,
         registration_id: RegistrationId,
         query_message_id: MessageId,
         expected_registerer_pid: Pid,
-    ) -> Result<Option<MessageDelivery>, ()> {
+    ) -> Result<Option<MessageQuery>, ()> {
         let registration_id = match usize::try_from(registration_id.0.get()) {
             Ok(v) => v,
             Err(_) => return Err(()),
         };
 
         let mut inner = self.inner.lock();
+        let inner = &mut *inner;
 
         if let Some(registration) = inner.registrations.get_mut(registration_id) {
             if registration.pid == expected_registerer_pid {
-                if let Some((msg, needs_answer)) = registration.pending_accept.pop_front() {
+                if let Some(interface) = registration.pending_registered.pop_front() {
+                    debug_assert!(registration.queries.is_empty());
+                    Ok(Some(MessageQuery::InterfaceRegistered {
+                        query_message_id,
+                        interface,
+                    }))
+                } else if let Some((msg, needs_answer)) = pop_pending_accept(
+                    &mut registration.pending_accept,
+                    &mut inner.blocked_messages,
+                    &registration.interface,
+                ) {
                     debug_assert!(registration.queries.is_empty());
-                    Ok(Some(MessageDelivery {
+                    inner.queued_messages.remove(&msg);
+                    Ok(Some(MessageQuery::Deliver(MessageDelivery {
                         to_deliver_message_id: msg,
                         interface: registration.interface.clone(),
                         needs_answer,
                         query_message_id,
                         recipient_pid: registration.pid,
-                    }))
+                    })))
                 } else {
                     registration.queries.push_back(query_message_id);
                     Ok(None)
@@ -188,6 +280,57 @@ impl Interfaces {
         }
     }
 
+    /// Called when an interface handler asks to be notified of interface registrations.
+    ///
+    /// Must be passed a [`RegistrationId`] and the [`Pid`] that the registration is expected to
+    /// belong to. The method verifies that the ownership matches.
+    pub fn watch_registrations(
+        &self,
+        registration_id: RegistrationId,
+        expected_registerer_pid: Pid,
+    ) -> Result<(), ()> {
+        let registration_id = match usize::try_from(registration_id.0.get()) {
+            Ok(v) => v,
+            Err(_) => return Err(()),
+        };
+
+        let mut inner = self.inner.lock();
+        match inner.registrations.get_mut(registration_id) {
+            Some(registration) if registration.pid == expected_registerer_pid => {
+                registration.watches_registrations = true;
+                Ok(())
+            }
+            _ => Err(()),
+        }
+    }
+
+    /// Must be called after [`Interfaces::set_interface_handler`] has succeeded, in order to
+    /// notify the registrations that watch registrations.
+    ///
+    /// Returns the list of `NextMessage` queries that must be answered with a notification
+    /// about `interface_hash`. Registrations that have no query available get the notification
+    /// queued, and it will later be returned by [`Interfaces::emit_message_query`].
+    pub fn interface_registered(&self, interface_hash: &InterfaceHash) -> Vec<MessageId> {
+        let mut inner = self.inner.lock();
+        let mut to_answer = Vec::new();
+
+        for (_, registration) in inner.registrations.iter_mut() {
+            if !registration.watches_registrations || registration.interface == *interface_hash {
+                continue;
+            }
+
+            if let Some(query_message_id) = registration.queries.pop_front() {
+                to_answer.push(query_message_id);
+            } else {
+                registration
+                    .pending_registered
+                    .push_back(interface_hash.clone());
+            }
+        }
+
+        to_answer
+    }
+
     /// Sets the handler of the given interface hash.
     ///
     /// On success, returns a [`RegistrationId`] to pass later to refer to that registration.
@@ -197,22 +340,297 @@ impl Interfaces {
         &self,
         interface_hash: InterfaceHash,
         pid: Pid,
+        metadata: Vec<u8>,
     ) -> Result<NonZeroU64, redshirt_interface_interface::ffi::InterfaceRegisterError> {
-        let mut interfaces = self.inner.lock();
-        let interfaces = &mut *interfaces;
+        self.inner
+            .lock()
+            .set_interface_handler(interface_hash, pid, metadata)
+    }
+
+    /// Same as [`Interfaces::set_interface_handler`], except that if there already exists a
+    /// handler for this interface, the registration is queued and `None` is returned.
+    ///
+    /// Queued registrations are later granted by [`Interfaces::process_destroyed`], and must then
+    /// be answered by answering `message_id`.
+    pub fn set_interface_handler_or_wait(
+        &self,
+        interface_hash: InterfaceHash,
+        pid: Pid,
+        message_id: MessageId,
+        metadata: Vec<u8>,
+    ) -> Option<NonZeroU64> {
+        // The lock is held between the check and the queuing. Otherwise, the current handler
+        // could go away in-between, and the registration would wait for a process that no
+        // longer exists.
+        let mut inner = self.inner.lock();
+
+        if let Some(Interface::Registered(_)) = inner.interfaces.get(&interface_hash) {
+            inner
+                .waiting_registrations
+                .entry(interface_hash)
+                .or_insert_with(VecDeque::new)
+                .push_back((message_id, pid, metadata));
+            return None;
+        }
+
+        let result = inner.set_interface_handler(interface_hash, pid, metadata);
+        debug_assert!(result.is_ok());
+        result.ok()
+    }
+
+    /// Returns the list of messages that have been waiting for the interface they are emitted
+    /// on to have a handler for at least `min_duration`.
+    ///
+    /// `now` must be the current time according to a monotonic clock. Since no clock is
+    /// available here, the time a message starts waiting is approximated as the value of `now`
+    /// during the first call to this method that finds this message waiting. The precision of
+    /// the returned durations thus depends on how often this method is called.
+    pub fn stalled_messages(&self, now: Duration, min_duration: Duration) -> Vec<StalledMessage> {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+
+        let mut out = Vec::new();
+
+        for (interface, state) in inner.interfaces.iter() {
+            let pending_accept = match state {
+                Interface::NotRegistered { pending_accept } => pending_accept,
+                Interface::Registered(_) => continue,
+            };
+
+            let blocked = inner.blocked_messages.get(interface);
+            let message_ids = pending_accept
+                .iter()
+                .map(|(id, _)| id)
+                .chain(blocked.into_iter().flatten());
+            for message_id in message_ids {
+                let queued = match inner.queued_messages.get_mut(message_id) {
+                    Some(q) => q,
+                    None => continue,
+                };
+
+                let blocked_since = *queued.blocked_since.get_or_insert(now);
+                let duration = now.checked_sub(blocked_since).unwrap_or_default();
+                if duration >= min_duration {
+                    out.push(StalledMessage {
+                        emitter_pid: queued.emitter_pid,
+                        message_id: *message_id,
+                        interface: interface.clone(),
+                        duration,
+                    });
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Returns the list of messages emitted by the given process that expect an answer and are
+    /// still waiting to be delivered, alongside with the interface they have been emitted on.
+    pub fn queued_messages_by_emitter(&self, emitter_pid: Pid) -> Vec<(MessageId, InterfaceHash)> {
+        let inner = self.inner.lock();
+
+        let from_registrations = inner
+            .registrations
+            .iter()
+            .filter(|(id, _)| *id != 0)
+            .map(|(_, r)| (&r.interface, &r.pending_accept));
+        let from_not_registered = inner.interfaces.iter().filter_map(|(hash, i)| match i {
+            Interface::NotRegistered { pending_accept } => Some((hash, pending_accept)),
+            Interface::Registered(_) => None,
+        });
+
+        let mut out = Vec::new();
+        for (interface, pending_accept) in from_registrations.chain(from_not_registered) {
+            for (message_id, needs_answer) in pending_accept {
+                if !needs_answer {
+                    continue;
+                }
+                if inner
+                    .queued_messages
+                    .get(message_id)
+                    .map_or(false, |q| q.emitter_pid == emitter_pid)
+                {
+                    out.push((*message_id, interface.clone()));
+                }
+            }
+        }
+        for (interface, blocked) in &inner.blocked_messages {
+            for message_id in blocked {
+                if inner
+                    .queued_messages
+                    .get(message_id)
+                    .map_or(false, |q| q.emitter_pid == emitter_pid)
+                {
+                    out.push((*message_id, interface.clone()));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the [`Pid`] of the handler of the given interface, and the metadata it has
+    /// provided when registering. Returns `None` if the interface has no handler.
+    pub fn handler(&self, interface_hash: &InterfaceHash) -> Option<(Pid, Vec<u8>)> {
+        let inner = self.inner.lock();
+        match inner.interfaces.get(interface_hash)? {
+            Interface::Registered(id) => {
+                let registration = &inner.registrations[*id];
+                Some((registration.pid, registration.metadata.clone()))
+            }
+            Interface::NotRegistered { .. } => None,
+        }
+    }
+
+    /// Returns the list of processes that have registered at least one interface.
+    pub fn handlers(&self) -> Vec<Pid> {
+        let inner = self.inner.lock();
+        let mut out = inner
+            .registrations
+            .iter()
+            .filter(|(id, _)| *id != 0)
+            .map(|(_, r)| r.pid)
+            .collect::<Vec<_>>();
+        out.sort_unstable_by_key(|pid| u64::from(*pid));
+        out.dedup();
+        out
+    }
+
+    /// Removes all the registrations of the given process, all the registrations it was
+    /// waiting for, and all the messages it has emitted that haven't been delivered yet.
+    ///
+    /// If another process was waiting, through [`Interfaces::set_interface_handler_or_wait`],
+    /// for one of the freed interfaces, it becomes the new handler and inherits the messages that
+    /// haven't been delivered yet. Returns the list of these new registrations.
+    pub fn process_destroyed(&self, pid: Pid) -> Vec<GrantedRegistration> {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+
+        for waiting in inner.waiting_registrations.values_mut() {
+            waiting.retain(|(_, p, _)| *p != pid);
+        }
+
+        // Discard the messages emitted by the process. Blocked messages take the room that this
+        // makes in the queues.
+        {
+            let queued_messages = &inner.queued_messages;
+            let blocked_messages = &mut inner.blocked_messages;
+            let emitted_by_pid = |message_id: &MessageId| {
+                queued_messages
+                    .get(message_id)
+                    .map_or(false, |q| q.emitter_pid == pid)
+            };
+
+            let from_registrations = inner
+                .registrations
+                .iter_mut()
+                .filter(|(id, _)| *id != 0)
+                .map(|(_, r)| (&r.interface, &mut r.pending_accept));
+            let from_not_registered = inner.interfaces.iter_mut().filter_map(|(hash, i)| match i {
+                Interface::NotRegistered { pending_accept } => Some((&*hash, pending_accept)),
+                Interface::Registered(_) => None,
+            });
+
+            for (interface, pending_accept) in from_registrations.chain(from_not_registered) {
+                let len_before = pending_accept.len();
+                pending_accept.retain(|(message_id, _)| !emitted_by_pid(message_id));
+
+                if let Some(blocked) = blocked_messages.get_mut(interface) {
+                    blocked.retain(|message_id| !emitted_by_pid(message_id));
+                    for _ in pending_accept.len()..len_before {
+                        match blocked.pop_front() {
+                            Some(unblocked) => pending_accept.push_back((unblocked, true)),
+                            None => break,
+                        }
+                    }
+                    if blocked.is_empty() {
+                        blocked_messages.remove(interface);
+                    }
+                }
+            }
+        }
+        inner.queued_messages.retain(|_, q| q.emitter_pid != pid);
+
+        let to_remove = inner
+            .registrations
+            .iter()
+            .filter(|(id, registration)| *id != 0 && registration.pid == pid)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        let mut granted = Vec::new();
+
+        for registration_id in to_remove {
+            let registration = inner.registrations.remove(registration_id);
+
+            let next_waiting = inner
+                .waiting_registrations
+                .get_mut(&registration.interface)
+                .and_then(|w| w.pop_front());
+
+            let new_state = if let Some((message_id, new_pid, metadata)) = next_waiting {
+                let id = inner.registrations.insert(InterfaceRegistration {
+                    pid: new_pid,
+                    interface: registration.interface.clone(),
+                    metadata,
+                    queries: VecDeque::with_capacity(16), // TODO: be less magic with capacity
+                    pending_accept: registration.pending_accept,
+                    watches_registrations: false,
+                    pending_registered: VecDeque::new(),
+                });
+                granted.push(GrantedRegistration {
+                    message_id,
+                    interface: registration.interface.clone(),
+                    registration_id: NonZeroU64::new(u64::try_from(id).unwrap()).unwrap(),
+                });
+                Interface::Registered(id)
+            } else {
+                Interface::NotRegistered {
+                    pending_accept: registration.pending_accept,
+                }
+            };
+
+            inner.interfaces.insert(registration.interface, new_state);
+        }
+
+        inner.waiting_registrations.retain(|_, w| !w.is_empty());
+        granted
+    }
+}
 
-        match interfaces.interfaces.entry(interface_hash) {
+impl Inner {
+    /// See [`Interfaces::set_interface_handler`].
+    fn set_interface_handler(
+        &mut self,
+        interface_hash: InterfaceHash,
+        pid: Pid,
+        metadata: Vec<u8>,
+    ) -> Result<NonZeroU64, redshirt_interface_interface::ffi::InterfaceRegisterError> {
+        match self.interfaces.entry(interface_hash) {
             Entry::Occupied(mut entry) => {
                 let interface = entry.key().clone();
                 match entry.get_mut() {
                     Interface::Registered(_) =>
                         Err(redshirt_interface_interface::ffi::InterfaceRegisterError::AlreadyRegistered),
                     Interface::NotRegistered { pending_accept } => {
-                        let id = interfaces.registrations.insert(InterfaceRegistration {
+                        let blocked = self.blocked_messages.get(&interface);
+                        let message_ids = pending_accept
+                            .iter()
+                            .map(|(id, _)| id)
+                            .chain(blocked.into_iter().flatten());
+                        for message_id in message_ids {
+                            if let Some(queued) = self.queued_messages.get_mut(message_id) {
+                                queued.blocked_since = None;
+                            }
+                        }
+
+                        let id = self.registrations.insert(InterfaceRegistration {
                             pid,
                             interface,
+                            metadata,
                             queries: VecDeque::with_capacity(16),  // TODO: be less magic with capacity
                             pending_accept: mem::take(pending_accept),
+                            watches_registrations: false,
+                            pending_registered: VecDeque::new(),
                         });
                         entry.insert(Interface::Registered(id));
                         Ok(NonZeroU64::new(u64::try_from(id).unwrap()).unwrap())
@@ -220,17 +638,61 @@ impl Interfaces {
                 }
             }
             Entry::Vacant(entry) => {
-                let id = interfaces.registrations.insert(InterfaceRegistration {
+                let id = self.registrations.insert(InterfaceRegistration {
                     pid,
                     interface: entry.key().clone(),
+                    metadata,
                     queries: VecDeque::with_capacity(16), // TODO: be less magic with capacity
                     pending_accept: VecDeque::with_capacity(16), // TODO: be less magic with capacity
+                    watches_registrations: false,
+                    pending_registered: VecDeque::new(),
                 });
                 entry.insert(Interface::Registered(id));
                 Ok(NonZeroU64::new(u64::try_from(id).unwrap()).unwrap())
             }
         }
     }
+
+    /// Pushes a message that needs an answer to [`Inner::blocked_messages`].
+    fn block_message(
+        &mut self,
+        interface_hash: &InterfaceHash,
+        message_id: MessageId,
+        emitter_pid: Pid,
+    ) -> EmitInterfaceMessage {
+        self.blocked_messages
+            .entry(interface_hash.clone())
+            .or_insert_with(VecDeque::new)
+            .push_back(message_id);
+        self.queued_messages.insert(
+            message_id,
+            QueuedMessage {
+                emitter_pid,
+                blocked_since: None,
+            },
+        );
+        EmitInterfaceMessage::Blocked
+    }
+}
+
+/// Pops the next message to deliver from the `pending_accept` field of the given interface.
+/// Since this makes room in the queue, the oldest message in [`Inner::blocked_messages`], if
+/// any, is moved to `pending_accept`.
+fn pop_pending_accept(
+    pending_accept: &mut VecDeque<(MessageId, bool)>,
+    blocked_messages: &mut HashMap<InterfaceHash, VecDeque<MessageId>, fnv::FnvBuildHasher>,
+    interface: &InterfaceHash,
+) -> Option<(MessageId, bool)> {
+    if let Some(blocked) = blocked_messages.get_mut(interface) {
+        if let Some(unblocked) = blocked.pop_front() {
+            pending_accept.push_back((unblocked, true));
+        }
+        if blocked.is_empty() {
+            blocked_messages.remove(interface);
+        }
+    }
+
+    pending_accept.pop_front()
 }
 
 impl Default for Interfaces {
@@ -252,16 +714,57 @@ pub struct MessageDelivery {
     pub recipient_pid: Pid,
 }
 
+/// Registration granted to a process that was waiting for it. See
+/// [`Interfaces::process_destroyed`].
+pub struct GrantedRegistration {
+    /// Message to answer with the new registration ID.
+    pub message_id: MessageId,
+    /// Interface that is now registered.
+    pub interface: InterfaceHash,
+    /// Identifier of the new registration.
+    pub registration_id: NonZeroU64,
+}
+
+/// Message waiting for its interface to have a handler. See [`Interfaces::stalled_messages`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalledMessage {
+    /// Process that has emitted the message.
+    pub emitter_pid: Pid,
+    /// Identifier of the message.
+    pub message_id: MessageId,
+    /// Interface the message has been emitted on.
+    pub interface: InterfaceHash,
+    /// How long the message has been waiting.
+    pub duration: Duration,
+}
+
+/// Outcome of [`Interfaces::emit_message_query`].
+pub enum MessageQuery {
+    /// Query must be answered by delivering a message.
+    Deliver(MessageDelivery),
+    /// Query must be answered with a notification that the given interface now has a handler.
+    InterfaceRegistered {
+        query_message_id: MessageId,
+        interface: InterfaceHash,
+    },
+}
+
 /// Outcome of [`Interfaces::emit_interface_message`].
 #[must_use]
 pub enum EmitInterfaceMessage {
     /// Message pushed on the interface can be instantly accepted and delivered.
     Deliver(MessageDelivery),
-    /// Message should be immediately rejected. Can only happen if `immediate` is `true`.
+    /// Message should be immediately rejected. Happens if `immediate` is `true` and the message
+    /// can't be delivered right now, or if the message doesn't need an answer and too many
+    /// messages are already queued.
     Reject,
     /// Message has been queued and might later be delivered when
     /// [`Interfaces::emit_message_query`] is called. Can only happen if `immediate` is `false`.
     Queued,
+    /// Message needs an answer, but too many messages are already queued. It is queued once
+    /// enough of them have been delivered, and its emitter must stay paused until then. Can only
+    /// happen if `immediate` is `false`.
+    Blocked,
 }
 
 /// Identifier of an interface registration.
@@ -281,3 +784,286 @@ impl From<RegistrationId> for NonZeroU64 {
         v.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EmitInterfaceMessage, Interfaces, MessageQuery, StalledMessage};
+    use core::{convert::TryFrom as _, time::Duration};
+    use hashbrown::HashMap;
+    use redshirt_syscalls::{InterfaceHash, MessageId, Pid};
+
+    #[test]
+    fn waiting_registration_granted_on_process_destroyed() {
+        let interfaces = Interfaces::new();
+        let interface = InterfaceHash::from_raw_hash([1; 32]);
+        let first_pid = Pid::from(1);
+        let second_pid = Pid::from(2);
+        let wait_message = MessageId::try_from(10).unwrap();
+
+        interfaces
+            .set_interface_handler(interface.clone(), first_pid, Vec::new())
+            .unwrap();
+        assert!(interfaces
+            .set_interface_handler_or_wait(interface.clone(), second_pid, wait_message, vec![7])
+            .is_none());
+
+        // Message that the first handler never had the chance to pull.
+        let pending_message = MessageId::try_from(11).unwrap();
+        match interfaces.emit_interface_message(
+            &interface,
+            pending_message,
+            Pid::from(3),
+            true,
+            false,
+        ) {
+            EmitInterfaceMessage::Queued => {}
+            _ => panic!(),
+        }
+
+        let granted = interfaces.process_destroyed(first_pid);
+        assert_eq!(granted.len(), 1);
+        assert_eq!(granted[0].message_id, wait_message);
+        assert_eq!(granted[0].interface, interface);
+        assert_eq!(interfaces.handler(&interface), Some((second_pid, vec![7])));
+
+        // The new handler inherits the message.
+        let query = MessageId::try_from(12).unwrap();
+        match interfaces.emit_message_query(granted[0].registration_id.into(), query, second_pid) {
+            Ok(Some(MessageQuery::Deliver(delivery))) => {
+                assert_eq!(delivery.to_deliver_message_id, pending_message);
+                assert_eq!(delivery.recipient_pid, second_pid);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn stalled_messages_reported() {
+        let interfaces = Interfaces::new();
+        let interface = InterfaceHash::from_raw_hash([0xab; 32]);
+        let emitter = Pid::from(12);
+        let message_id = MessageId::try_from(34).unwrap();
+
+        assert!(matches!(
+            interfaces.emit_interface_message(&interface, message_id, emitter, true, false),
+            EmitInterfaceMessage::Queued
+        ));
+
+        // The first call only starts measuring.
+        let min = Duration::from_secs(30);
+        assert!(interfaces
+            .stalled_messages(Duration::from_secs(100), min)
+            .is_empty());
+        assert!(interfaces
+            .stalled_messages(Duration::from_secs(120), min)
+            .is_empty());
+
+        let stalled = interfaces.stalled_messages(Duration::from_secs(135), min);
+        assert_eq!(
+            stalled,
+            vec![StalledMessage {
+                emitter_pid: emitter,
+                message_id,
+                interface: interface.clone(),
+                duration: Duration::from_secs(35),
+            }]
+        );
+
+        // No longer stalled once a handler exists.
+        interfaces
+            .set_interface_handler(interface, Pid::from(56), Vec::new())
+            .unwrap();
+        assert!(interfaces
+            .stalled_messages(Duration::from_secs(200), Duration::from_secs(0))
+            .is_empty());
+    }
+
+    #[test]
+    fn queued_messages_by_emitter() {
+        let interfaces = Interfaces::new();
+        let interface = InterfaceHash::from_raw_hash([0xcd; 32]);
+
+        for (id, pid, needs_answer) in &[(1, 10, true), (2, 10, false), (3, 11, true)] {
+            assert!(matches!(
+                interfaces.emit_interface_message(
+                    &interface,
+                    MessageId::try_from(*id).unwrap(),
+                    Pid::from(*pid),
+                    *needs_answer,
+                    false
+                ),
+                EmitInterfaceMessage::Queued
+            ));
+        }
+
+        assert_eq!(
+            interfaces.queued_messages_by_emitter(Pid::from(10)),
+            vec![(MessageId::try_from(1).unwrap(), interface.clone())]
+        );
+
+        // Messages stay listed after the interface gets a handler, as long as they haven't been
+        // delivered.
+        interfaces
+            .set_interface_handler(interface.clone(), Pid::from(12), Vec::new())
+            .unwrap();
+        assert_eq!(
+            interfaces.queued_messages_by_emitter(Pid::from(11)),
+            vec![(MessageId::try_from(3).unwrap(), interface)]
+        );
+    }
+
+    #[test]
+    fn messages_without_answer_rejected_when_queue_full() {
+        let interface = InterfaceHash::from_raw_hash([0xef; 32]);
+        let mut limits = HashMap::default();
+        limits.insert(interface.clone(), 2);
+        let interfaces = Interfaces::with_max_queued_messages(1, limits);
+
+        let emit = |id, needs_answer| {
+            interfaces.emit_interface_message(
+                &interface,
+                MessageId::try_from(id).unwrap(),
+                Pid::from(1),
+                needs_answer,
+                false,
+            )
+        };
+
+        assert!(matches!(emit(1, false), EmitInterfaceMessage::Queued));
+        assert!(matches!(emit(2, false), EmitInterfaceMessage::Queued));
+        assert!(matches!(emit(3, false), EmitInterfaceMessage::Reject));
+        assert!(matches!(emit(4, true), EmitInterfaceMessage::Blocked));
+
+        // Other interfaces use the default limit.
+        let other = InterfaceHash::from_raw_hash([0x12; 32]);
+        for (id, expected_queued) in &[(5, true), (6, false)] {
+            let outcome = interfaces.emit_interface_message(
+                &other,
+                MessageId::try_from(*id).unwrap(),
+                Pid::from(1),
+                false,
+                false,
+            );
+            assert_eq!(
+                matches!(outcome, EmitInterfaceMessage::Queued),
+                *expected_queued
+            );
+        }
+    }
+
+    #[test]
+    fn messages_with_answer_blocked_until_queue_drains() {
+        let interface = InterfaceHash::from_raw_hash([0x34; 32]);
+        let interfaces = Interfaces::with_max_queued_messages(1, Default::default());
+        let handler_pid = Pid::from(2);
+        let registration_id = interfaces
+            .set_interface_handler(interface.clone(), handler_pid, Vec::new())
+            .unwrap();
+
+        let emit = |id, needs_answer| {
+            interfaces.emit_interface_message(
+                &interface,
+                MessageId::try_from(id).unwrap(),
+                Pid::from(1),
+                needs_answer,
+                false,
+            )
+        };
+
+        assert!(matches!(emit(1, false), EmitInterfaceMessage::Queued));
+        assert!(matches!(emit(2, true), EmitInterfaceMessage::Blocked));
+        assert!(matches!(emit(3, true), EmitInterfaceMessage::Blocked));
+
+        // The queue is full, including when counting the messages waiting for room to be made.
+        assert!(matches!(emit(4, false), EmitInterfaceMessage::Reject));
+
+        // Blocked messages are still waiting for an answer.
+        assert_eq!(
+            interfaces.queued_messages_by_emitter(Pid::from(1)),
+            vec![
+                (MessageId::try_from(2).unwrap(), interface.clone()),
+                (MessageId::try_from(3).unwrap(), interface.clone())
+            ]
+        );
+
+        // Messages are delivered in order, each delivery letting one blocked message in.
+        for (query, expected) in &[(10, Some(1)), (11, Some(2)), (12, Some(3)), (13, None)] {
+            let outcome = interfaces
+                .emit_message_query(
+                    registration_id.into(),
+                    MessageId::try_from(*query).unwrap(),
+                    handler_pid,
+                )
+                .unwrap();
+            match (outcome, expected) {
+                (Some(MessageQuery::Deliver(delivery)), Some(expected)) => {
+                    assert_eq!(
+                        delivery.to_deliver_message_id,
+                        MessageId::try_from(*expected).unwrap()
+                    );
+                }
+                (None, None) => {}
+                _ => panic!(),
+            }
+        }
+
+        assert!(interfaces
+            .queued_messages_by_emitter(Pid::from(1))
+            .is_empty());
+    }
+
+    #[test]
+    fn messages_discarded_when_emitter_destroyed() {
+        let interface = InterfaceHash::from_raw_hash([0x56; 32]);
+        let interfaces = Interfaces::with_max_queued_messages(1, Default::default());
+        let dead = Pid::from(1);
+        let alive = Pid::from(2);
+
+        let emit = |id, emitter| {
+            interfaces.emit_interface_message(
+                &interface,
+                MessageId::try_from(id).unwrap(),
+                emitter,
+                true,
+                false,
+            )
+        };
+
+        assert!(matches!(emit(1, dead), EmitInterfaceMessage::Queued));
+        assert!(matches!(emit(2, dead), EmitInterfaceMessage::Blocked));
+        assert!(matches!(emit(3, alive), EmitInterfaceMessage::Blocked));
+
+        assert!(interfaces.process_destroyed(dead).is_empty());
+        assert!(interfaces.queued_messages_by_emitter(dead).is_empty());
+        assert!(interfaces
+            .stalled_messages(Duration::from_secs(0), Duration::from_secs(0))
+            .iter()
+            .all(|m| m.emitter_pid != dead));
+
+        // The message of the other process takes the room that has been made, and is the only
+        // one delivered.
+        let handler_pid = Pid::from(3);
+        let registration_id = interfaces
+            .set_interface_handler(interface.clone(), handler_pid, Vec::new())
+            .unwrap();
+        for (query, expected) in &[(10, Some(3)), (11, None)] {
+            let outcome = interfaces
+                .emit_message_query(
+                    registration_id.into(),
+                    MessageId::try_from(*query).unwrap(),
+                    handler_pid,
+                )
+                .unwrap();
+            match (outcome, expected) {
+                (Some(MessageQuery::Deliver(delivery)), Some(expected)) => {
+                    assert_eq!(
+                        delivery.to_deliver_message_id,
+                        MessageId::try_from(*expected).unwrap()
+                    );
+                }
+                (None, None) => {}
+                _ => panic!(),
+            }
+        }
+    }
+}
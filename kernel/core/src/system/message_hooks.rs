@@ -0,0 +1,85 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Hooks called at each step of the lifetime of a message.
+//!
+//! The [`MessageHooks`] struct forwards these steps to the optional features that observe
+//! messages, which are the `tracing` spans of the `message_spans` module and the recording of
+//! the `recording` module. Features that are disabled aren't compiled, and the hooks do nothing
+//! if all of them are disabled.
+
+use redshirt_syscalls::{EncodedMessage, InterfaceHash, MessageId, Pid};
+
+#[cfg(feature = "tracing")]
+use super::message_spans::MessageSpans;
+#[cfg(feature = "message-recording")]
+use super::recording::Recorder;
+
+pub struct MessageHooks {
+    /// Tracing spans of the messages being processed.
+    #[cfg(feature = "tracing")]
+    pub spans: MessageSpans,
+
+    /// Records the messages and replays recorded answers.
+    #[cfg(feature = "message-recording")]
+    pub recorder: Recorder,
+}
+
+// Some parameters are only used by some of the features.
+#[cfg_attr(
+    not(all(feature = "tracing", feature = "message-recording")),
+    allow(unused_variables)
+)]
+impl MessageHooks {
+    /// Must be called when a message has been emitted by a process.
+    pub fn emitted(
+        &self,
+        message_id: MessageId,
+        emitter_pid: Pid,
+        interface: &InterfaceHash,
+        needs_answer: bool,
+    ) {
+        #[cfg(feature = "tracing")]
+        self.spans.emitted(message_id, emitter_pid, interface);
+        #[cfg(feature = "message-recording")]
+        self.recorder
+            .emitted(message_id, emitter_pid, interface, needs_answer);
+    }
+
+    /// Must be called when a message has been delivered to the handler of its interface.
+    ///
+    /// `handler_pid` is `None` if the interface is handled natively or by a recording being
+    /// replayed.
+    pub fn delivered(
+        &self,
+        message_id: MessageId,
+        handler_pid: Option<Pid>,
+        needs_answer: bool,
+        body: &EncodedMessage,
+    ) {
+        #[cfg(feature = "tracing")]
+        self.spans.delivered(message_id, handler_pid, needs_answer);
+        #[cfg(feature = "message-recording")]
+        self.recorder.delivered(message_id, handler_pid, body);
+    }
+
+    /// Must be called when a message has been answered or cancelled.
+    pub fn answered(&self, message_id: MessageId, response: &Result<EncodedMessage, ()>) {
+        #[cfg(feature = "tracing")]
+        self.spans.answered(message_id);
+        #[cfg(feature = "message-recording")]
+        self.recorder.answered(message_id, response);
+    }
+}
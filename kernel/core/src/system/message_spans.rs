@@ -0,0 +1,130 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Tracing of messages. Only compiled if the `tracing` feature is enabled.
+//!
+//! The [`MessageSpans`] struct opens a `tracing` span when a message is emitted, and closes it
+//! when the message is answered. The span is entered every time the message progresses, which
+//! makes it possible to measure the time a message takes to be handled, for example with
+//! `tracing-chrome`.
+
+use hashbrown::HashMap;
+use nohash_hasher::BuildNoHashHasher;
+use redshirt_syscalls::{InterfaceHash, MessageId, Pid};
+
+#[derive(Default)]
+pub struct MessageSpans {
+    /// Span of each message that has been emitted and not answered yet.
+    spans: spinning_top::Spinlock<HashMap<MessageId, tracing::Span, BuildNoHashHasher<u64>>>,
+}
+
+impl MessageSpans {
+    /// Must be called when a message has been emitted by a process.
+    pub fn emitted(&self, message_id: MessageId, emitter_pid: Pid, interface: &InterfaceHash) {
+        let span = tracing::debug_span!(
+            "message",
+            message_id = u64::from(message_id),
+            emitter_pid = u64::from(emitter_pid),
+            interface = ?interface,
+        );
+        span.in_scope(|| tracing::debug!("emitted"));
+        self.spans.lock().insert(message_id, span);
+    }
+
+    /// Must be called when a message has been delivered to the handler of its interface.
+    ///
+    /// If `needs_answer` is false, the span of the message is closed.
+    pub fn delivered(&self, message_id: MessageId, handler_pid: Option<Pid>, needs_answer: bool) {
+        let mut spans = self.spans.lock();
+        if let Some(span) = spans.get(&message_id) {
+            span.in_scope(|| tracing::debug!(handler_pid = ?handler_pid, "delivered"));
+        }
+        if !needs_answer {
+            spans.remove(&message_id);
+        }
+    }
+
+    /// Must be called when a message has been answered or cancelled. Closes its span.
+    pub fn answered(&self, message_id: MessageId) {
+        if let Some(span) = self.spans.lock().remove(&message_id) {
+            span.in_scope(|| tracing::debug!("answered"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageSpans;
+    use core::{
+        convert::TryFrom as _,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    };
+    use redshirt_syscalls::{InterfaceHash, MessageId, Pid};
+    use std::sync::Arc;
+    use tracing::{span, subscriber::Subscriber, Event, Metadata};
+
+    /// Subscriber that counts how many times spans are entered and closed.
+    #[derive(Default)]
+    struct Counters {
+        next_id: AtomicU64,
+        entered: AtomicUsize,
+        closed: AtomicUsize,
+    }
+
+    struct CountingSubscriber(Arc<Counters>);
+
+    impl Subscriber for CountingSubscriber {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+        fn new_span(&self, _: &span::Attributes) -> span::Id {
+            span::Id::from_u64(self.0.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event) {}
+        fn enter(&self, _: &span::Id) {
+            self.0.entered.fetch_add(1, Ordering::Relaxed);
+        }
+        fn exit(&self, _: &span::Id) {}
+        fn try_close(&self, _: span::Id) -> bool {
+            self.0.closed.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+    }
+
+    #[test]
+    fn spans_entered_and_closed() {
+        let counters = Arc::new(Counters::default());
+        let interface = InterfaceHash::from_raw_hash([1; 32]);
+        let id = |n| MessageId::try_from(n).unwrap();
+
+        let spans = MessageSpans::default();
+        tracing::subscriber::with_default(CountingSubscriber(counters.clone()), || {
+            spans.emitted(id(1), Pid::from(1), &interface);
+            spans.delivered(id(1), Some(Pid::from(2)), true);
+            assert_eq!(counters.closed.load(Ordering::Relaxed), 0);
+            spans.answered(id(1));
+            assert_eq!(counters.closed.load(Ordering::Relaxed), 1);
+
+            // Messages that don't need an answer are done once delivered.
+            spans.emitted(id(2), Pid::from(1), &interface);
+            spans.delivered(id(2), None, false);
+            assert_eq!(counters.closed.load(Ordering::Relaxed), 2);
+        });
+
+        assert_eq!(counters.entered.load(Ordering::Relaxed), 5);
+    }
+}
@@ -21,7 +21,7 @@
 use alloc::vec::Vec;
 use hashbrown::HashMap;
 use nohash_hasher::BuildNoHashHasher;
-use redshirt_syscalls::{MessageId, Pid};
+use redshirt_syscalls::{InterfaceHash, MessageId, Pid};
 
 pub struct PendingAnswers {
     // TODO: smarter than a spinloop?
@@ -30,7 +30,16 @@ pub struct PendingAnswers {
 
 struct Inner {
     // TODO: call shrink_to_fit from time to time?
-    messages: HashMap<MessageId, Pid, BuildNoHashHasher<u64>>,
+    messages: HashMap<MessageId, PendingAnswer, BuildNoHashHasher<u64>>,
+}
+
+struct PendingAnswer {
+    /// Process that is expected to answer the message.
+    answerer_pid: Pid,
+    /// Process that has emitted the message.
+    emitter_pid: Pid,
+    /// Interface the message has been emitted on.
+    interface: InterfaceHash,
 }
 
 impl PendingAnswers {
@@ -42,18 +51,31 @@ impl PendingAnswers {
         }
     }
 
-    pub fn add(&self, message_id: MessageId, answerer_pid: Pid) {
-        let _inserted = self.inner.lock().messages.insert(message_id, answerer_pid);
+    pub fn add(
+        &self,
+        message_id: MessageId,
+        answerer_pid: Pid,
+        emitter_pid: Pid,
+        interface: InterfaceHash,
+    ) {
+        let _inserted = self.inner.lock().messages.insert(
+            message_id,
+            PendingAnswer {
+                answerer_pid,
+                emitter_pid,
+                interface,
+            },
+        );
         debug_assert!(_inserted.is_none());
     }
 
     pub fn remove(&self, message_id: &MessageId, if_answerer_equal: &Pid) -> Result<(), ()> {
         let mut inner = self.inner.lock();
         match inner.messages.remove(message_id) {
-            Some(pid) if pid == *if_answerer_equal => Ok(()),
-            Some(pid) => {
+            Some(pending) if pending.answerer_pid == *if_answerer_equal => Ok(()),
+            Some(pending) => {
                 // Cancel the removal.
-                inner.messages.insert(message_id.clone(), pid);
+                inner.messages.insert(message_id.clone(), pending);
                 Err(())
             }
             None => Err(()),
@@ -68,7 +90,7 @@ impl PendingAnswers {
         let list = inner
             .messages
             .iter()
-            .filter(|(_, a)| *a == answerer_pid)
+            .filter(|(_, p)| p.answerer_pid == *answerer_pid)
             .map(|(m, _)| *m)
             .collect::<Vec<_>>();
 
@@ -79,6 +101,24 @@ impl PendingAnswers {
 
         list
     }
+
+    /// Removes all the messages from the collection.
+    pub fn drain(&self) -> Vec<MessageId> {
+        self.inner.lock().messages.drain().map(|(m, _)| m).collect()
+    }
+
+    /// Returns the list of messages emitted by the given PID, alongside with the interface they
+    /// have been emitted on.
+    pub fn by_emitter(&self, emitter_pid: &Pid) -> Vec<(MessageId, InterfaceHash)> {
+        // TODO: O(n) complexity
+        self.inner
+            .lock()
+            .messages
+            .iter()
+            .filter(|(_, p)| p.emitter_pid == *emitter_pid)
+            .map(|(m, p)| (*m, p.interface.clone()))
+            .collect()
+    }
 }
 
 impl Default for PendingAnswers {
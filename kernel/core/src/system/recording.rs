@@ -0,0 +1,241 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Recording of messages, and replaying of recorded answers. Only compiled if the
+//! `message-recording` feature is enabled.
+//!
+//! The [`Recorder`] can keep a log of all the messages that are emitted, delivered and
+//! answered, and can answer the messages emitted on some interfaces with answers taken from a
+//! previous recording.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use hashbrown::HashMap;
+use redshirt_syscalls::{EncodedMessage, InterfaceHash, MessageId, Pid};
+use spinning_top::Spinlock;
+
+/// Event that has been recorded. See [`System::take_recorded_events`](super::System::take_recorded_events).
+///
+/// > **Note**: The core has no access to a clock, and events therefore don't contain any
+/// >           timestamp. The order of the events is the order in which they happened.
+#[cfg_attr(docsrs, doc(cfg(feature = "message-recording")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedEvent {
+    /// A process has emitted a message on an interface.
+    Emitted {
+        /// Identifier of the message. Identical in all the events concerning this message.
+        message_id: MessageId,
+        /// Process that has emitted the message.
+        emitter_pid: Pid,
+        /// Interface the message has been emitted on.
+        interface: InterfaceHash,
+        /// True if the emitter expects an answer.
+        needs_answer: bool,
+    },
+    /// A message has been delivered to the handler of its interface.
+    Delivered {
+        /// Identifier of the message.
+        message_id: MessageId,
+        /// Process the message has been delivered to. `None` if the interface is handled
+        /// natively or by the recording being replayed.
+        handler_pid: Option<Pid>,
+        /// Content of the message.
+        body: EncodedMessage,
+    },
+    /// A message has been answered, or cancelled in which case `response` is `Err`.
+    Answered {
+        /// Identifier of the message.
+        message_id: MessageId,
+        /// Answer to the message.
+        response: Result<EncodedMessage, ()>,
+    },
+}
+
+/// Extracts from a recording the answers to the messages emitted on the given interface, in the
+/// order in which these messages have been emitted. The returned list can be passed to
+/// [`SystemBuilder::with_replayed_interface`](super::SystemBuilder::with_replayed_interface).
+///
+/// Messages that haven't been answered within the recording are considered as having been
+/// answered with an error.
+#[cfg_attr(docsrs, doc(cfg(feature = "message-recording")))]
+pub fn replay_responses(
+    events: &[RecordedEvent],
+    interface: &InterfaceHash,
+) -> Vec<Result<EncodedMessage, ()>> {
+    let mut emitted = Vec::new();
+    let mut answers = HashMap::<_, _, fnv::FnvBuildHasher>::default();
+
+    for event in events {
+        match event {
+            RecordedEvent::Emitted {
+                message_id,
+                interface: i,
+                needs_answer: true,
+                ..
+            } if i == interface => emitted.push(*message_id),
+            RecordedEvent::Answered {
+                message_id,
+                response,
+            } => {
+                answers.insert(*message_id, response.clone());
+            }
+            _ => {}
+        }
+    }
+
+    emitted
+        .into_iter()
+        .map(|message_id| answers.remove(&message_id).unwrap_or(Err(())))
+        .collect()
+}
+
+pub struct Recorder {
+    /// Events recorded so far, or `None` if recording is disabled.
+    events: Option<Spinlock<Vec<RecordedEvent>>>,
+    /// For each interface whose messages must be answered from a recording, the answers that
+    /// haven't been used yet.
+    replayed:
+        HashMap<InterfaceHash, Spinlock<VecDeque<Result<EncodedMessage, ()>>>, fnv::FnvBuildHasher>,
+}
+
+impl Recorder {
+    /// Initializes a new [`Recorder`].
+    pub fn new(
+        record: bool,
+        replayed: HashMap<InterfaceHash, Vec<Result<EncodedMessage, ()>>, fnv::FnvBuildHasher>,
+    ) -> Self {
+        Recorder {
+            events: if record {
+                Some(Spinlock::new(Vec::new()))
+            } else {
+                None
+            },
+            replayed: replayed
+                .into_iter()
+                .map(|(interface, responses)| (interface, Spinlock::new(responses.into())))
+                .collect(),
+        }
+    }
+
+    /// Returns all the events recorded since the last call, and clears the list.
+    pub fn take_events(&self) -> Vec<RecordedEvent> {
+        match &self.events {
+            Some(events) => core::mem::take(&mut *events.lock()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns true if the messages emitted on the given interface must be answered using
+    /// [`Recorder::next_replayed_response`].
+    pub fn is_replayed(&self, interface: &InterfaceHash) -> bool {
+        self.replayed.contains_key(interface)
+    }
+
+    /// Returns the answer to the next message that needs an answer emitted on the given
+    /// interface. Returns an error if the recording has been exhausted.
+    pub fn next_replayed_response(&self, interface: &InterfaceHash) -> Result<EncodedMessage, ()> {
+        self.replayed
+            .get(interface)
+            .and_then(|responses| responses.lock().pop_front())
+            .unwrap_or(Err(()))
+    }
+
+    /// Must be called when a message has been emitted by a process.
+    pub fn emitted(
+        &self,
+        message_id: MessageId,
+        emitter_pid: Pid,
+        interface: &InterfaceHash,
+        needs_answer: bool,
+    ) {
+        self.record(|| RecordedEvent::Emitted {
+            message_id,
+            emitter_pid,
+            interface: interface.clone(),
+            needs_answer,
+        });
+    }
+
+    /// Must be called when a message has been delivered to the handler of its interface.
+    pub fn delivered(
+        &self,
+        message_id: MessageId,
+        handler_pid: Option<Pid>,
+        body: &EncodedMessage,
+    ) {
+        self.record(|| RecordedEvent::Delivered {
+            message_id,
+            handler_pid,
+            body: body.clone(),
+        });
+    }
+
+    /// Must be called when a message has been answered or cancelled.
+    pub fn answered(&self, message_id: MessageId, response: &Result<EncodedMessage, ()>) {
+        self.record(|| RecordedEvent::Answered {
+            message_id,
+            response: response.clone(),
+        });
+    }
+
+    fn record(&self, event: impl FnOnce() -> RecordedEvent) {
+        if let Some(events) = &self.events {
+            events.lock().push(event());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay_responses, RecordedEvent};
+    use core::convert::TryFrom as _;
+    use redshirt_syscalls::{EncodedMessage, InterfaceHash, MessageId, Pid};
+
+    #[test]
+    fn replay_responses_in_emission_order() {
+        let interface = InterfaceHash::from_raw_hash([1; 32]);
+        let other = InterfaceHash::from_raw_hash([2; 32]);
+        let id = |n| MessageId::try_from(n).unwrap();
+        let emitted = |n, interface: &InterfaceHash, needs_answer| RecordedEvent::Emitted {
+            message_id: id(n),
+            emitter_pid: Pid::from(1),
+            interface: interface.clone(),
+            needs_answer,
+        };
+        let answered = |n, byte| RecordedEvent::Answered {
+            message_id: id(n),
+            response: Ok(EncodedMessage(vec![byte])),
+        };
+
+        let events = vec![
+            emitted(1, &interface, true),
+            emitted(2, &other, true),
+            emitted(3, &interface, false),
+            emitted(4, &interface, true),
+            emitted(5, &interface, true),
+            answered(4, 40),
+            answered(2, 20),
+            answered(1, 10),
+        ];
+
+        assert_eq!(
+            replay_responses(&events, &interface),
+            vec![
+                Ok(EncodedMessage(vec![10])),
+                Ok(EncodedMessage(vec![40])),
+                Err(())
+            ]
+        );
+    }
+}
@@ -102,6 +102,12 @@ impl PlatformSpecific {
         Pin::new(&self.as_ref().0).num_cpus()
     }
 
+    /// Returns the command line that the kernel has been started with. Empty if there isn't
+    /// any, or if the platform doesn't support passing one.
+    pub fn command_line(&self) -> &str {
+        self.0.command_line()
+    }
+
     /// Prints a log message. You are strongly encouraged to only use ASCII characters. The
     /// implementation is free to discard any non-supported character.
     pub fn write_log(&self, message: &str) {
@@ -171,6 +177,36 @@ impl PlatformSpecific {
     pub unsafe fn read_port_u32(self: Pin<&Self>, port: u32) -> Result<u32, PortErr> {
         Pin::new(&self.as_ref().0).read_port_u32(port)
     }
+
+    /// Returns true if [`PlatformSpecific::shutdown`] is supported.
+    pub fn supports_shutdown(self: Pin<&Self>) -> bool {
+        Pin::new(&self.as_ref().0).supports_shutdown()
+    }
+
+    /// Turns the machine off.
+    ///
+    /// # Panic
+    ///
+    /// Panics if [`PlatformSpecific::supports_shutdown`] returns false.
+    ///
+    pub fn shutdown(self: Pin<&Self>) -> ! {
+        Pin::new(&self.as_ref().0).shutdown()
+    }
+
+    /// Returns true if [`PlatformSpecific::reboot`] is supported.
+    pub fn supports_reboot(self: Pin<&Self>) -> bool {
+        Pin::new(&self.as_ref().0).supports_reboot()
+    }
+
+    /// Restarts the machine.
+    ///
+    /// # Panic
+    ///
+    /// Panics if [`PlatformSpecific::supports_reboot`] returns false.
+    ///
+    pub fn reboot(self: Pin<&Self>) -> ! {
+        Pin::new(&self.as_ref().0).reboot()
+    }
 }
 
 /// Error when requesting to read/write a hardware port.
@@ -208,6 +208,11 @@ impl PlatformSpecificImpl {
         future::pending()
     }
 
+    pub fn command_line(&self) -> &str {
+        // TODO: read the command line from the device tree
+        ""
+    }
+
     pub fn write_log(&self, message: &str) {
         fmt::Write::write_str(&mut log::PANIC_LOGGER.log_printer(), message).unwrap();
     }
@@ -239,6 +244,22 @@ impl PlatformSpecificImpl {
     pub unsafe fn read_port_u32(self: Pin<&Self>, _: u32) -> Result<u32, PortErr> {
         Err(PortErr::Unsupported)
     }
+
+    pub fn supports_shutdown(self: Pin<&Self>) -> bool {
+        false
+    }
+
+    pub fn shutdown(self: Pin<&Self>) -> ! {
+        panic!("Shutting down isn't supported on this platform")
+    }
+
+    pub fn supports_reboot(self: Pin<&Self>) -> bool {
+        false
+    }
+
+    pub fn reboot(self: Pin<&Self>) -> ! {
+        panic!("Rebooting isn't supported on this platform")
+    }
 }
 
 pub type TimerFuture = time::TimerFuture;
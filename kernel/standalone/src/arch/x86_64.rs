@@ -15,7 +15,7 @@
 
 use crate::arch::PortErr;
 
-use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use core::{
     convert::TryFrom as _,
     fmt::Write as _,
@@ -112,6 +112,12 @@ pub unsafe fn entry_point_step3<
         }
     };
 
+    // Copy the command line passed by the bootloader, in order to report it to programs.
+    let command_line = multiboot_info
+        .command_line_tag()
+        .map(|tag| String::from(tag.command_line()))
+        .unwrap_or_default();
+
     // Now that we have a memory allocator, initialize the logging system.
     panic::PANIC_LOGGER.set_method(if let Some(fb_info) = multiboot_info.framebuffer_tag() {
         KernelLogMethod {
@@ -170,7 +176,7 @@ pub unsafe fn entry_point_step3<
     // TODO: remove these tables from the memory ranges used as heap? `acpi_tables` is a copy of
     // the table, so once we are past this line there's no problem anymore. But in theory,
     // the `acpi_tables` variable might allocate over the actual ACPI tables.
-    let acpi_tables = acpi::parse_acpi_tables(&multiboot_info);
+    let (acpi_tables, power_control) = acpi::parse_acpi_tables(&multiboot_info);
 
     // This function is only executed by the main processor of the machine, called the **boot
     // processor**. The other processors are called the **associated processors** and must be
@@ -342,6 +348,8 @@ pub unsafe fn entry_point_step3<
             .unwrap(),
             next_irq_futures,
             next_next_irq_id: From::from(0),
+            command_line,
+            power_control,
         };
 
         Arc::pin(super::PlatformSpecific::from(platform_specific))
@@ -507,6 +515,12 @@ pub struct PlatformSpecificImpl {
     /// Contains an `AtomicBool` to set to true when the IRQ happens, and the waker to wake up.
     next_irq_futures:
         Arc<Spinlock<HashMap<u64, (Arc<atomic::AtomicBool>, Option<Waker>), fnv::FnvBuildHasher>>>,
+
+    /// Command line passed by the multiboot2 bootloader.
+    command_line: String,
+
+    /// How to turn off or restart the machine, according to the ACPI tables.
+    power_control: acpi::PowerControl,
 }
 
 impl From<PlatformSpecificImpl> for super::PlatformSpecific {
@@ -553,6 +567,10 @@ impl PlatformSpecificImpl {
         }
     }
 
+    pub fn command_line(&self) -> &str {
+        &self.command_line
+    }
+
     pub fn write_log(&self, message: &str) {
         writeln!(panic::PANIC_LOGGER.log_printer(), "{}", message).unwrap();
     }
@@ -611,6 +629,50 @@ impl PlatformSpecificImpl {
             Err(PortErr::OutOfRange)
         }
     }
+
+    pub fn supports_shutdown(self: Pin<&Self>) -> bool {
+        self.power_control.supports_soft_off()
+    }
+
+    pub fn shutdown(self: Pin<&Self>) -> ! {
+        unsafe {
+            x86_64::instructions::interrupts::disable();
+            self.power_control.soft_off();
+        }
+
+        // The machine doesn't turn off instantly. If it doesn't turn off at all, we stay here.
+        loop {
+            x86_64::instructions::hlt();
+        }
+    }
+
+    pub fn supports_reboot(self: Pin<&Self>) -> bool {
+        true
+    }
+
+    pub fn reboot(self: Pin<&Self>) -> ! {
+        unsafe {
+            x86_64::instructions::interrupts::disable();
+
+            // We first try the reset register found in the ACPI tables, then the legacy method
+            // of asking the 8042 keyboard controller to pulse the reset line.
+            self.power_control.reset();
+            u8::write_to_port(0x64, 0xfe);
+
+            // If none of the above worked, we trigger a triple fault by loading an empty IDT
+            // then raising an interrupt. The processor can't handle the interrupt nor the
+            // subsequent faults, and resets.
+            x86_64::instructions::tables::lidt(&x86_64::structures::DescriptorTablePointer {
+                limit: 0,
+                base: x86_64::VirtAddr::zero(),
+            });
+            x86_64::instructions::interrupts::int3();
+        }
+
+        loop {
+            x86_64::instructions::hlt();
+        }
+    }
 }
 
 pub type TimerFuture = apic::timers::TimerFuture;
@@ -14,7 +14,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use alloc::boxed::Box;
-use core::{convert::TryFrom as _, mem, ptr::NonNull};
+use core::{convert::TryFrom as _, iter, mem, ptr::NonNull};
 use x86_64::structures::port::{PortRead as _, PortWrite as _};
 
 /// Loads ACPI tables from physical memory.
@@ -73,7 +73,7 @@ pub fn load_acpi_tables(
 ///
 pub fn parse_acpi_tables(
     multiboot_info: &multiboot2::BootInformation,
-) -> acpi::AcpiTables<DummyAcpiHandler> {
+) -> (acpi::AcpiTables<DummyAcpiHandler>, PowerControl) {
     let acpi_tables = load_acpi_tables(multiboot_info);
     let mut aml = aml::AmlContext::new(Box::new(DummyAmlHandler), false, aml::DebugVerbosity::None);
 
@@ -89,7 +89,152 @@ pub fn parse_acpi_tables(
         let _ = aml.parse_table(stream);
     }
 
-    acpi_tables
+    let power_control = PowerControl::from_tables(&acpi_tables, &aml);
+    (acpi_tables, power_control)
+}
+
+/// Information found in the ACPI tables about how to turn off or restart the machine.
+#[derive(Debug)]
+pub struct PowerControl {
+    /// How to enter the S5 ("soft off") sleep state, or `None` if the ACPI tables don't describe
+    /// it.
+    soft_off: Option<SoftOff>,
+    /// I/O port to write to in order to restart the machine, and the value to write, or `None`
+    /// if the ACPI tables don't describe a reset register.
+    reset: Option<(u16, u8)>,
+}
+
+#[derive(Debug)]
+struct SoftOff {
+    /// I/O port of the PM1a control register.
+    pm1a_control: u16,
+    /// I/O port of the PM1b control register, if any.
+    pm1b_control: Option<u16>,
+    /// Value to put in the `SLP_TYPa` field of the PM1a control register.
+    sleep_type_a: u16,
+    /// Value to put in the `SLP_TYPb` field of the PM1b control register.
+    sleep_type_b: u16,
+}
+
+impl PowerControl {
+    fn from_tables(
+        acpi_tables: &acpi::AcpiTables<DummyAcpiHandler>,
+        aml: &aml::AmlContext,
+    ) -> Self {
+        let fadt =
+            match unsafe { acpi_tables.get_sdt::<acpi::fadt::Fadt>(acpi::sdt::Signature::FADT) } {
+                Ok(Some(fadt)) => fadt,
+                _ => {
+                    return PowerControl {
+                        soft_off: None,
+                        reset: None,
+                    }
+                }
+            };
+
+        // The FADT is a packed structure, so its fields must be copied before being used.
+        let flags = fadt.flags;
+        let reset = if flags.supports_system_reset_via_fadt() {
+            fadt.reset_register()
+                .ok()
+                .and_then(|reg| io_port(&reg))
+                .map(|port| (port, fadt.reset_value))
+        } else {
+            None
+        };
+
+        // The values to put in the `SLP_TYPx` fields are found in the `\_S5` package of the
+        // DSDT.
+        let sleep_types = aml::AmlName::from_str("\\_S5")
+            .and_then(|name| aml.namespace.get_by_path(&name))
+            .ok()
+            .and_then(|value| match value {
+                aml::AmlValue::Package(values) if values.len() >= 2 => {
+                    let sleep_type_a = values[0].as_integer(aml).ok()?;
+                    let sleep_type_b = values[1].as_integer(aml).ok()?;
+                    Some((
+                        u16::try_from(sleep_type_a).ok()?,
+                        u16::try_from(sleep_type_b).ok()?,
+                    ))
+                }
+                _ => None,
+            });
+
+        let soft_off = sleep_types.and_then(|(sleep_type_a, sleep_type_b)| {
+            let pm1a_control = io_port(&fadt.pm1a_control_block().ok()?)?;
+            let pm1b_control = match fadt.pm1b_control_block() {
+                Ok(Some(reg)) => Some(io_port(&reg)?),
+                Ok(None) => None,
+                Err(_) => return None,
+            };
+
+            Some(SoftOff {
+                pm1a_control,
+                pm1b_control,
+                sleep_type_a,
+                sleep_type_b,
+            })
+        });
+
+        PowerControl { soft_off, reset }
+    }
+
+    /// Returns true if [`PowerControl::soft_off`] is supported.
+    pub fn supports_soft_off(&self) -> bool {
+        self.soft_off.is_some()
+    }
+
+    /// Puts the machine in the S5 ("soft off") sleep state, in other words turns it off.
+    ///
+    /// Does nothing if [`PowerControl::supports_soft_off`] returns false. Can also return if the
+    /// hardware didn't react to the request.
+    ///
+    /// # Safety
+    ///
+    /// The machine is turned off, which is obviously not something that the rest of the kernel
+    /// expects.
+    ///
+    pub unsafe fn soft_off(&self) {
+        let soft_off = match &self.soft_off {
+            Some(s) => s,
+            None => return,
+        };
+
+        // The `SLP_TYPx` field occupies bits 10 to 12 of the control registers, and setting
+        // `SLP_EN` (bit 13) triggers the transition. The other bits are preserved.
+        for (port, sleep_type) in iter::once((soft_off.pm1a_control, soft_off.sleep_type_a)).chain(
+            soft_off
+                .pm1b_control
+                .map(|port| (port, soft_off.sleep_type_b)),
+        ) {
+            let value = u16::read_from_port(port) & !(0b111 << 10);
+            u16::write_to_port(port, value | ((sleep_type & 0b111) << 10) | (1 << 13));
+        }
+    }
+
+    /// Writes to the reset register described in the ACPI tables, if any. Returns if the
+    /// register doesn't exist or if the hardware didn't react to the write.
+    ///
+    /// # Safety
+    ///
+    /// The machine is restarted, which is obviously not something that the rest of the kernel
+    /// expects.
+    ///
+    pub unsafe fn reset(&self) {
+        if let Some((port, value)) = self.reset {
+            u8::write_to_port(port, value);
+        }
+    }
+}
+
+/// Returns the I/O port designated by the given register, or `None` if it isn't in the I/O
+/// address space.
+fn io_port(register: &acpi::platform::address::GenericAddress) -> Option<u16> {
+    if register.address_space != acpi::platform::address::AddressSpace::SystemIo {
+        return None;
+    }
+
+    u16::try_from(register.address).ok()
 }
 
 /// Implementation of the `AcpiHandler` trait that is responsible for mapping physical memory
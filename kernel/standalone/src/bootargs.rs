@@ -0,0 +1,52 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Native program that handles the `bootargs` interface.
+
+use crate::arch::PlatformSpecific;
+
+use alloc::sync::Arc;
+use core::pin::Pin;
+use redshirt_bootargs_interface::ffi::{BootArgsMessage, GetResponse};
+use redshirt_core::{
+    extrinsics::Extrinsics, system::NativeInterfaceMessage, Decode as _, Encode as _,
+    EncodedMessage,
+};
+
+/// State machine for `bootargs` interface messages handling.
+pub struct BootArgsNativeProgram {
+    /// Platform-specific hooks.
+    platform_specific: Pin<Arc<PlatformSpecific>>,
+}
+
+impl BootArgsNativeProgram {
+    /// Initializes the new state machine for boot arguments messages handling.
+    pub fn new(platform_specific: Pin<Arc<PlatformSpecific>>) -> Self {
+        BootArgsNativeProgram { platform_specific }
+    }
+
+    pub fn interface_message<TExtr: Extrinsics>(
+        &self,
+        message: NativeInterfaceMessage<TExtr>,
+    ) -> Result<EncodedMessage, ()> {
+        match BootArgsMessage::decode(message.extract()) {
+            Ok(BootArgsMessage::Get) => Ok(GetResponse {
+                command_line: self.platform_specific.command_line().as_bytes().to_vec(),
+            }
+            .encode()),
+            Err(_) => Err(()),
+        }
+    }
+}
@@ -21,14 +21,16 @@
 use crate::arch::PlatformSpecific;
 
 use alloc::{sync::Arc, vec::Vec};
-use core::{convert::TryFrom as _, pin::Pin};
+use core::{cmp, convert::TryFrom as _, mem, pin::Pin};
 use hashbrown::HashMap;
 use nohash_hasher::BuildNoHashHasher;
 use redshirt_core::{
     extrinsics::Extrinsics, system::NativeInterfaceMessage, Decode as _, Encode as _,
     EncodedMessage, Pid,
 };
-use redshirt_hardware_interface::ffi::{HardwareAccessResponse, HardwareMessage, Operation};
+use redshirt_hardware_interface::ffi::{
+    HardwareAccessResponse, HardwareAccessResponseEntry, HardwareMessage, Operation,
+};
 use spinning_top::Spinlock;
 
 /// State machine for `hardware` interface messages handling.
@@ -61,12 +63,18 @@ impl HardwareHandler {
         match HardwareMessage::decode(message.extract()) {
             Ok(HardwareMessage::HardwareAccess(operations)) => {
                 let mut response = Vec::with_capacity(operations.len());
-                for operation in operations {
+                for (operation_index, operation) in operations.into_iter().enumerate() {
+                    // Messages are bounded in size, and each operation occupies at least one
+                    // byte once encoded.
+                    let operation_index = u32::try_from(operation_index).unwrap();
                     unsafe {
                         if let Some(outcome) =
                             perform_operation(self.platform_specific.as_ref(), operation)
                         {
-                            response.push(outcome);
+                            response.push(HardwareAccessResponseEntry {
+                                operation_index,
+                                response: outcome,
+                            });
                         }
                     }
                 }
@@ -127,17 +135,23 @@ where
             len,
             value,
         } => {
-            if let Ok(mut address) = usize::try_from(address) {
-                for _ in 0..len {
-                    if address != 0 {
-                        (address as *mut u8).write_volatile(value);
-                    }
-                    if let Some(addr_next) = address.checked_add(1) {
-                        address = addr_next;
-                    } else {
-                        break;
-                    }
-                }
+            if let Ok(address) = usize::try_from(address) {
+                let len = usize::try_from(len).unwrap_or(usize::MAX);
+                memset_volatile(address, len, &[value]);
+            }
+            None
+        }
+        Operation::PhysicalMemoryMemsetU32 {
+            address,
+            len,
+            value,
+        } => {
+            if let Ok(address) = usize::try_from(address) {
+                let len = usize::try_from(len)
+                    .ok()
+                    .and_then(|len| len.checked_mul(4))
+                    .unwrap_or(usize::MAX);
+                memset_volatile(address, len, &value.to_ne_bytes());
             }
             None
         }
@@ -261,5 +275,52 @@ where
         Operation::PortReadU32 { port } => Some(HardwareAccessResponse::PortReadU32(
             platform_specific.read_port_u32(port).unwrap_or(0),
         )),
+        Operation::PortReadU64 { port } => {
+            let low = platform_specific.read_port_u32(port).unwrap_or(0);
+            let high = port
+                .checked_add(4)
+                .and_then(|port| platform_specific.read_port_u32(port).ok())
+                .unwrap_or(0);
+            Some(HardwareAccessResponse::PortReadU64(
+                (u64::from(high) << 32) | u64::from(low),
+            ))
+        }
+    }
+}
+
+/// Fills `len` bytes starting at `address` with copies of `pattern`.
+///
+/// The length of `pattern` must divide the size of a `usize`. Neither `address` nor `len` need
+/// to be aligned: the unaligned head and tail are written byte by byte, and the rest is written
+/// with `usize`-sized writes in order to reduce the number of memory accesses.
+///
+/// Nothing is written in the first copy of `pattern` if `address` is 0, and writing stops if the
+/// end of the address space is reached.
+unsafe fn memset_volatile(address: usize, len: usize, pattern: &[u8]) {
+    debug_assert_eq!(mem::size_of::<usize>() % pattern.len(), 0);
+
+    let len = cmp::min(len, usize::MAX - address);
+    let mut offset = if address == 0 { pattern.len() } else { 0 };
+
+    while offset < len && (address + offset) % mem::align_of::<usize>() != 0 {
+        ((address + offset) as *mut u8).write_volatile(pattern[offset % pattern.len()]);
+        offset += 1;
+    }
+
+    // Since the length of `pattern` divides the size of a `usize`, the same word can be written
+    // at every aligned address.
+    let mut word = [0; mem::size_of::<usize>()];
+    for (n, byte) in word.iter_mut().enumerate() {
+        *byte = pattern[(offset + n) % pattern.len()];
+    }
+    let word = usize::from_ne_bytes(word);
+    while len.saturating_sub(offset) >= mem::size_of::<usize>() {
+        ((address + offset) as *mut usize).write_volatile(word);
+        offset += mem::size_of::<usize>();
+    }
+
+    while offset < len {
+        ((address + offset) as *mut u8).write_volatile(pattern[offset % pattern.len()]);
+        offset += 1;
     }
 }
@@ -23,19 +23,21 @@
 //!
 
 use crate::{
-    arch::PlatformSpecific, hardware::HardwareHandler, klog::KernelLogNativeProgram,
-    pci::native::PciNativeProgram, random::native::RandomNativeProgram, time::TimeHandler,
+    arch::PlatformSpecific, bootargs::BootArgsNativeProgram, hardware::HardwareHandler,
+    klog::KernelLogNativeProgram, pci::native::PciNativeProgram,
+    random::native::RandomNativeProgram, time::TimeHandler,
 };
 
-use alloc::{format, string::String, sync::Arc, vec::Vec};
-use core::{pin::Pin, sync::atomic::Ordering};
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+use core::{iter, pin::Pin, sync::atomic::Ordering};
 use futures::prelude::*;
 use redshirt_core::{
     build_wasm_module,
-    extrinsics::wasi::WasiExtrinsics,
+    extrinsics::{wasi::WasiExtrinsics, ProcessArgs},
     system::{KernelDebugMetricsRequest, SystemRunOutcome},
     System,
 };
+use redshirt_power_interface::ffi::PowerMessage;
 
 /// Main struct of this crate. Runs everything.
 pub struct Kernel {
@@ -50,6 +52,7 @@ pub struct Kernel {
     hardware: HardwareHandler,
     pci: PciNativeProgram,
     klog: KernelLogNativeProgram,
+    bootargs: BootArgsNativeProgram,
 }
 
 #[derive(Debug)]
@@ -79,28 +82,76 @@ impl Kernel {
             .with_native_interface_handler(redshirt_random_interface::ffi::INTERFACE)
             .with_native_interface_handler(redshirt_pci_interface::ffi::INTERFACE)
             .with_native_interface_handler(redshirt_kernel_log_interface::ffi::INTERFACE)
-            .with_startup_process(build_wasm_module!(
-                "../../../programs/p2p-loader",
-                "programs-loader"
-            ))
-            .with_startup_process(build_wasm_module!("../../../programs/compositor"))
-            .with_startup_process(build_wasm_module!("../../../programs/pci-printer"))
-            // TODO: actually implement system-time and remove this dummy; https://github.com/tomaka/redshirt/issues/542
-            .with_startup_process(build_wasm_module!("../../../programs/dummy-system-time"))
-            .with_startup_process(build_wasm_module!("../../../programs/log-to-kernel"))
-            .with_startup_process(build_wasm_module!("../../../programs/vga-vbe"))
-            .with_startup_process(build_wasm_module!(
-                "../../../programs/diagnostics-http-server"
-            ))
-            .with_startup_process(build_wasm_module!("../../../programs/hello-world"))
-            .with_startup_process(build_wasm_module!("../../../programs/network-manager"))
-            .with_startup_process(build_wasm_module!("../../../programs/e1000"));
+            .with_native_interface_handler(redshirt_bootargs_interface::ffi::INTERFACE)
+            .with_privileged_interface(redshirt_hardware_interface::ffi::INTERFACE)
+            .with_privileged_interface(redshirt_process_interface::ffi::INTERFACE)
+            // Only the drivers are given access to the hardware, only `top` can enumerate the
+            // processes, and only the compositor (on Ctrl+Alt+Del) can restart the machine. The
+            // other startup processes can't use any privileged interface.
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/p2p-loader", "programs-loader"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/compositor"),
+                iter::once(redshirt_power_interface::ffi::INTERFACE),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/pci-printer"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/system-time"),
+                iter::once(redshirt_hardware_interface::ffi::INTERFACE),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/sysinfo"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/clipboard"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/top"),
+                iter::once(redshirt_process_interface::ffi::INTERFACE),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/log-to-kernel"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/vga-vbe"),
+                iter::once(redshirt_hardware_interface::ffi::INTERFACE),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/diagnostics-http-server"),
+                iter::empty(),
+            )
+            .with_startup_process_args(
+                build_wasm_module!("../../../programs/hello-world"),
+                iter::empty(),
+                ProcessArgs {
+                    args: vec![b"hello-world".to_vec()],
+                    env_vars: Vec::new(),
+                },
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/network-manager"),
+                iter::empty(),
+            )
+            .with_restricted_startup_process(
+                build_wasm_module!("../../../programs/e1000"),
+                iter::once(redshirt_hardware_interface::ffi::INTERFACE),
+            );
 
         // TODO: remove the cfg guards once rpi-framebuffer is capable of auto-detecting whether
         // it should enable itself
         #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
-        let system_builder = system_builder
-            .with_startup_process(build_wasm_module!("../../../programs/rpi-framebuffer"));
+        let system_builder = system_builder.with_restricted_startup_process(
+            build_wasm_module!("../../../programs/rpi-framebuffer"),
+            iter::once(redshirt_hardware_interface::ffi::INTERFACE),
+        );
 
         // TODO: temporary; uncomment to test
         /*system_builder = system_builder.with_main_program(
@@ -124,6 +175,7 @@ impl Kernel {
             hardware: HardwareHandler::new(platform_specific.clone()),
             pci: PciNativeProgram::new(pci_devices, platform_specific.clone()),
             klog: KernelLogNativeProgram::new(platform_specific.clone()),
+            bootargs: BootArgsNativeProgram::new(platform_specific.clone()),
         }
     }
 
@@ -219,6 +271,20 @@ impl Kernel {
             SystemRunOutcome::KernelDebugMetricsRequest(report) => {
                 self.report_kernel_metrics(report, monotonic_clock_value);
             }
+            SystemRunOutcome::PowerRequest(request) => match request.action() {
+                PowerMessage::Shutdown if self.platform_specific.as_ref().supports_shutdown() => {
+                    request.teardown();
+                    self.platform_specific.as_ref().shutdown()
+                }
+                PowerMessage::Reboot if self.platform_specific.as_ref().supports_reboot() => {
+                    request.teardown();
+                    self.platform_specific.as_ref().reboot()
+                }
+                // TODO: implement suspension
+                PowerMessage::Shutdown | PowerMessage::Reboot | PowerMessage::Suspend => {
+                    request.reject()
+                }
+            },
 
             // Time handling.
             SystemRunOutcome::NativeInterfaceMessage {
@@ -306,6 +372,22 @@ impl Kernel {
                 self.klog.interface_message(message);
             }
 
+            // Boot arguments handling.
+            SystemRunOutcome::NativeInterfaceMessage {
+                interface,
+                message_id: Some(message_id),
+                message,
+                ..
+            } if interface == redshirt_bootargs_interface::ffi::INTERFACE => {
+                let response = self.bootargs.interface_message(message);
+                self.system.answer_message(message_id, response);
+            }
+            SystemRunOutcome::NativeInterfaceMessage {
+                interface,
+                message_id: None,
+                ..
+            } if interface == redshirt_bootargs_interface::ffi::INTERFACE => {}
+
             SystemRunOutcome::NativeInterfaceMessage { .. } => {
                 unreachable!()
             }
@@ -60,6 +60,7 @@ use core::{pin::Pin, sync::atomic};
 #[macro_use]
 pub mod arch;
 
+mod bootargs;
 mod hardware;
 mod pci;
 mod random;
@@ -0,0 +1,84 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Implements the `clipboard` interface.
+//!
+//! The content of the clipboard is kept in memory and shared between all the programs. It is
+//! empty when the program starts.
+
+use redshirt_clipboard_interface::ffi as clipboard_ffi;
+use redshirt_interface_interface::DecodedInterfaceOrDestroyed;
+use redshirt_syscalls::Decode as _;
+
+fn main() {
+    redshirt_log_interface::init();
+    redshirt_syscalls::block_on(async_main())
+}
+
+async fn async_main() {
+    let mut registration =
+        redshirt_interface_interface::register_interface(clipboard_ffi::INTERFACE)
+            .await
+            .unwrap();
+
+    // UTF-8 text content of the clipboard, or `None` if it is empty.
+    let mut content: Option<String> = None;
+
+    loop {
+        let interface_event = registration.next_message_raw().await;
+        let msg = match interface_event {
+            DecodedInterfaceOrDestroyed::Interface(msg) => msg,
+            DecodedInterfaceOrDestroyed::ProcessDestroyed(_) => continue,
+            DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => continue,
+        };
+
+        match clipboard_ffi::ClipboardMessage::decode(msg.actual_data) {
+            Ok(clipboard_ffi::ClipboardMessage::GetText) => {
+                if let Some(message_id) = msg.message_id {
+                    let content = match &content {
+                        Some(text) => {
+                            clipboard_ffi::ClipboardContent::Text(text.clone().into_bytes())
+                        }
+                        None => clipboard_ffi::ClipboardContent::Empty,
+                    };
+                    redshirt_interface_interface::emit_answer(
+                        message_id,
+                        &clipboard_ffi::GetTextResponse { content },
+                    );
+                }
+            }
+            Ok(clipboard_ffi::ClipboardMessage::SetText(text)) => match String::from_utf8(text) {
+                Ok(text) => {
+                    content = Some(text);
+                    if let Some(message_id) = msg.message_id {
+                        redshirt_interface_interface::emit_answer(message_id, &());
+                    }
+                }
+                Err(_) => {
+                    log::warn!("Non-UTF-8 clipboard text from {:?}", msg.emitter_pid);
+                    if let Some(message_id) = msg.message_id {
+                        redshirt_interface_interface::emit_message_error(message_id);
+                    }
+                }
+            },
+            Err(_) => {
+                log::warn!("Bad clipboard message from {:?}", msg.emitter_pid);
+                if let Some(message_id) = msg.message_id {
+                    redshirt_interface_interface::emit_message_error(message_id);
+                }
+            }
+        }
+    }
+}
@@ -53,25 +53,68 @@ use core::{cmp::Eq, convert::TryFrom as _, hash::Hash, iter, mem, ops::Range};
 mod rect;
 
 pub struct Compositor<TFbId, TOutId, TFb, TOut> {
-    framebuffers: hashbrown::HashMap<TFbId, Framebuffer<TFb>, ahash::RandomState>,
+    framebuffers: hashbrown::HashMap<TFbId, Framebuffer<TFb, TOutId>, ahash::RandomState>,
     video_outputs: hashbrown::HashMap<TOutId, VideoOutput<TOut>, ahash::RandomState>,
 
+    /// List of all the framebuffers, from the bottom-most to the top-most.
+    stacking: Vec<TFbId>,
+    /// Framebuffer that has focus, if any. Never a minimized framebuffer.
+    focused: Option<TFbId>,
+
     next_framebuffer_position: (u32, u32),
 }
 
-struct Framebuffer<TFb> {
+struct Framebuffer<TFb, TOutId> {
     position: rect::Rect,
+    /// If `Some`, the framebuffer fills the given video output, and `position` is equal to the
+    /// position of this video output. Also contains the position to restore when the
+    /// framebuffer goes back to windowed mode.
+    fullscreen: Option<(TOutId, rect::Rect)>,
     user_data: TFb,
     /// Rows of pixels. Each pixel is a RGBA color.
     rgb_data: Vec<[u8; 4]>,
+    /// Opacity of the whole framebuffer, multiplied with the alpha of each pixel.
+    global_opacity: u8,
+    /// If true, the framebuffer isn't composited.
+    minimized: bool,
+    /// True if the dimensions of the framebuffer have changed since the last call to
+    /// [`FramebufferAccess::take_resized`].
+    resized: bool,
 }
 
 struct VideoOutput<TOut> {
     position: rect::Rect,
     format: Format,
+    /// Raw EDID of the monitor, if known.
+    edid: Option<Vec<u8>>,
     user_data: TOut,
     /// List of areas that need to be refreshed. In local coordinates.
     needs_refresh: VecDeque<rect::Rect>,
+    /// Gamma of the red, green and blue channels.
+    gamma: [f32; 3],
+    /// Multiplier applied to all channels.
+    brightness: f32,
+    /// Lookup tables of the red, green and blue channels, built from `gamma` and `brightness`.
+    color_lut: [[u8; 256]; 3],
+}
+
+impl<TOut> VideoOutput<TOut> {
+    /// Rebuilds `color_lut` and marks the entire video output as needing a refresh.
+    fn refresh_colors(&mut self) {
+        self.color_lut = build_color_lut(self.gamma, self.brightness);
+        self.invalidate_all();
+    }
+
+    /// Marks the entire video output as needing a refresh.
+    fn invalidate_all(&mut self) {
+        self.needs_refresh.clear();
+        self.needs_refresh.push_back(rect::Rect {
+            x: 0,
+            y: 0,
+            width: self.position.width,
+            height: self.position.height,
+        });
+    }
 }
 
 impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
@@ -97,6 +140,8 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                     u64::from_ne_bytes(<[u8; 8]>::try_from(&seed[56..64]).unwrap()),
                 ),
             ),
+            stacking: Vec::with_capacity(256),
+            focused: None,
             next_framebuffer_position: (20, 20),
         }
     }
@@ -107,9 +152,13 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
         width: u32,
         height: u32,
         format: Format,
+        edid: Option<Vec<u8>>,
         user_data: TOut,
     ) -> VideoOutputAccess<TFbId, TOutId, TFb, TOut> {
-        debug_assert!(self.video_outputs.values().any(|out| out.position.x == 0));
+        debug_assert!(
+            self.video_outputs.is_empty()
+                || self.video_outputs.values().any(|out| out.position.x == 0)
+        );
         let x_position = self
             .video_outputs
             .values()
@@ -130,6 +179,7 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                     y: 0,
                 },
                 format,
+                edid,
                 needs_refresh: {
                     let mut list = VecDeque::with_capacity(16);
                     list.push_back(rect::Rect {
@@ -140,13 +190,39 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                     });
                     list
                 },
+                gamma: [1.0; 3],
+                brightness: 1.0,
+                color_lut: build_color_lut([1.0; 3], 1.0),
                 user_data,
             },
         );
 
+        // If the video output replaces an existing one, the framebuffers that were fullscreen
+        // on it must follow its new position and dimensions.
+        self.move_fullscreen_framebuffers(&id);
+
         VideoOutputAccess { parent: self, id }
     }
 
+    /// Moves the framebuffers that are fullscreen on the given video output to its current
+    /// position and dimensions.
+    fn move_fullscreen_framebuffers(&mut self, output_id: &TOutId) {
+        let new_position = self.video_outputs.get(output_id).unwrap().position;
+        let fullscreen_fbs = self
+            .framebuffers
+            .iter()
+            .filter(|(_, fb)| {
+                fb.fullscreen
+                    .as_ref()
+                    .map_or(false, |(out, _)| out == output_id)
+            })
+            .map(|(fb_id, _)| fb_id.clone())
+            .collect::<Vec<_>>();
+        for fb_id in fullscreen_fbs {
+            self.move_framebuffer(&fb_id, new_position);
+        }
+    }
+
     pub fn video_output_by_id(
         &mut self,
         id: &TOutId,
@@ -180,24 +256,61 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
         };
 
         // TODO: error if duplicate
-        self.framebuffers.insert(
+        let previous = self.framebuffers.insert(
             id.clone(),
             Framebuffer {
                 position: fb_position,
+                fullscreen: None,
                 user_data,
                 // TODO: return error instead of panicking if width*height is too large; there is clearly some attack vector with these width and height values
                 rgb_data: (0..usize::try_from(width * height).unwrap())
                     .map(|_| [0; 4])
                     .collect(),
+                global_opacity: 255,
+                minimized: false,
+                resized: false,
             },
         );
+        if previous.is_none() {
+            self.stacking.push(id.clone());
+        }
 
         self.next_framebuffer_position.0 = (self.next_framebuffer_position.0 + 20) % 300;
         self.next_framebuffer_position.1 = (self.next_framebuffer_position.1 + 20) % 200;
 
         // Invalidate areas from video outputs that overlap with the newly-created framebuffer.
+        self.invalidate_desktop_area(&fb_position);
+
+        FramebufferAccess { parent: self, id }
+    }
+
+    /// Changes the area of the desktop covered by a framebuffer.
+    ///
+    /// If the dimensions change, the content of the framebuffer is reset.
+    fn move_framebuffer(&mut self, id: &TFbId, new_position: rect::Rect) {
+        let framebuffer = self.framebuffers.get_mut(id).unwrap();
+        let old_position = framebuffer.position;
+        if old_position == new_position {
+            return;
+        }
+
+        if old_position.width != new_position.width || old_position.height != new_position.height {
+            // TODO: same attack vector as in `add_framebuffer`
+            let num_pixels = u64::from(new_position.width) * u64::from(new_position.height);
+            framebuffer.rgb_data = vec![[0; 4]; usize::try_from(num_pixels).unwrap()];
+            framebuffer.resized = true;
+        }
+        framebuffer.position = new_position;
+
+        self.invalidate_desktop_area(&old_position);
+        self.invalidate_desktop_area(&new_position);
+    }
+
+    /// Marks the given desktop area as needing a refresh on all the video outputs that overlap
+    /// with it.
+    fn invalidate_desktop_area(&mut self, area: &rect::Rect) {
         for video_output in self.video_outputs.values_mut() {
-            let overlap = match video_output.position.intersection(&fb_position) {
+            let overlap = match video_output.position.intersection(area) {
                 Some(ov) => ov,
                 None => continue,
             };
@@ -211,8 +324,6 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                 height: overlap.height,
             });
         }
-
-        FramebufferAccess { parent: self, id }
     }
 
     pub fn framebuffer_by_id(
@@ -233,6 +344,24 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
         self.framebuffers.keys()
     }
 
+    /// Returns the framebuffer that has focus, if any.
+    pub fn focused_framebuffer(&self) -> Option<&TFbId> {
+        self.focused.as_ref()
+    }
+
+    /// Returns the top-most framebuffer visible at the given desktop coordinates, if any.
+    pub fn framebuffer_at(&self, x: u32, y: u32) -> Option<&TFbId> {
+        let visible = self.stacking.iter().rev().filter(|id| {
+            let framebuffer = self.framebuffers.get(id).unwrap();
+            !framebuffer.minimized && framebuffer.position.contains(x, y)
+        });
+        // Fullscreen framebuffers are above the other ones. See `desktop_pixel`.
+        let fullscreen = visible
+            .clone()
+            .find(|id| self.framebuffers.get(id).unwrap().fullscreen.is_some());
+        fullscreen.or_else(|| visible.clone().next())
+    }
+
     /// Updates the state machine after one frame has passed.
     pub fn next_frame(&mut self) {
         // TODO: is this necessary? consider removing if this does nothing
@@ -241,11 +370,20 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
     /// Finds the color of the pixel at the given desktop coordinates.
     fn desktop_pixel(&self, x: u32, y: u32) -> [u8; 3] {
         // TODO: this method is probably naive and super slow
-        // TODO: properly handle z layers
 
         let mut accumulator = [255, 255, 255];
 
-        for framebuffer in self.framebuffers.values() {
+        // Framebuffers are composited in stacking order, except that fullscreen framebuffers
+        // are above the other ones.
+        let visible = self
+            .stacking
+            .iter()
+            .map(|id| self.framebuffers.get(id).unwrap())
+            .filter(|fb| !fb.minimized);
+        let windowed = visible.clone().filter(|fb| fb.fullscreen.is_none());
+        let fullscreen = visible.filter(|fb| fb.fullscreen.is_some());
+
+        for framebuffer in windowed.chain(fullscreen) {
             let fb_offset_x = match x.checked_sub(framebuffer.position.x) {
                 Some(off) => off,
                 None => continue,
@@ -264,8 +402,11 @@ impl<TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                 continue;
             }
 
-            let fb_pixel = framebuffer.rgb_data
+            let mut fb_pixel = framebuffer.rgb_data
                 [usize::try_from(fb_offset_y * framebuffer.position.width + fb_offset_x).unwrap()];
+            fb_pixel[3] =
+                u8::try_from(u16::from(fb_pixel[3]) * u16::from(framebuffer.global_opacity) / 255)
+                    .unwrap();
             accumulator = blend(fb_pixel, accumulator);
         }
 
@@ -277,8 +418,8 @@ fn blend(a: [u8; 4], b: [u8; 3]) -> [u8; 3] {
     let b_alpha = u16::from(255 - a[3]);
 
     let r = u16::from(a[0]) * u16::from(a[3]) + u16::from(b[0]) * b_alpha;
-    let g = u16::from(a[2]) * u16::from(a[3]) + u16::from(b[1]) * b_alpha;
-    let b = u16::from(a[1]) * u16::from(a[3]) + u16::from(b[2]) * b_alpha;
+    let g = u16::from(a[1]) * u16::from(a[3]) + u16::from(b[1]) * b_alpha;
+    let b = u16::from(a[2]) * u16::from(a[3]) + u16::from(b[2]) * b_alpha;
 
     [
         u8::try_from(r / 255).unwrap(),
@@ -297,8 +438,19 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
     FramebufferAccess<'a, TFbId, TOutId, TFb, TOut>
 {
     /// Removes the framebuffer from the compositor state machine.
+    ///
+    /// If the framebuffer had focus, no framebuffer has focus afterwards.
     pub fn remove(self) -> TFb {
-        self.parent.framebuffers.remove(&self.id).unwrap().user_data
+        let framebuffer = self.parent.framebuffers.remove(&self.id).unwrap();
+        let this_id = &self.id;
+        self.parent.stacking.retain(|id| id != this_id);
+        if self.parent.focused.as_ref() == Some(&self.id) {
+            self.parent.focused = None;
+        }
+        if !framebuffer.minimized {
+            self.parent.invalidate_desktop_area(&framebuffer.position);
+        }
+        framebuffer.user_data
     }
 
     pub fn user_data(&self) -> &TFb {
@@ -314,11 +466,224 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
             .user_data
     }
 
+    /// Returns the position of the top-left hand corner of the framebuffer on the desktop.
+    pub fn position(&self) -> (u32, u32) {
+        let position = self.parent.framebuffers.get(&self.id).unwrap().position;
+        (position.x, position.y)
+    }
+
+    /// Returns the width and height of the framebuffer.
+    pub fn dimensions(&self) -> (u32, u32) {
+        let position = self.parent.framebuffers.get(&self.id).unwrap().position;
+        (position.width, position.height)
+    }
+
+    /// Returns true if the dimensions of the framebuffer have changed since the last time this
+    /// method has been called, for example because it has been made fullscreen. Its content has
+    /// then been reset.
+    pub fn take_resized(&mut self) -> bool {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        mem::replace(&mut framebuffer.resized, false)
+    }
+
+    /// Returns the video output where the largest area of the framebuffer is visible, or `None`
+    /// if the framebuffer doesn't overlap any video output or is minimized.
+    pub fn video_output(&self) -> Option<&TOutId> {
+        let framebuffer = self.parent.framebuffers.get(&self.id).unwrap();
+        if framebuffer.minimized {
+            return None;
+        }
+
+        let position = framebuffer.position;
+        self.parent
+            .video_outputs
+            .iter()
+            .filter_map(|(id, out)| {
+                let overlap = out.position.intersection(&position)?;
+                let area = u64::from(overlap.width) * u64::from(overlap.height);
+                if area == 0 {
+                    return None;
+                }
+                Some((id, area))
+            })
+            .max_by_key(|(_, area)| *area)
+            .map(|(id, _)| id)
+    }
+
+    /// Makes the framebuffer fill the given video output. Its position and dimensions then
+    /// follow the ones of the video output, and it is composited above non-fullscreen
+    /// framebuffers.
+    ///
+    /// If the dimensions of the framebuffer change, its content is reset and must be set again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `output_id` isn't a valid video output.
+    ///
+    pub fn set_fullscreen(&mut self, output_id: &TOutId) {
+        let output_position = self.parent.video_outputs.get(output_id).unwrap().position;
+
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        let windowed_position = match framebuffer.fullscreen.take() {
+            Some((_, windowed_position)) => windowed_position,
+            None => framebuffer.position,
+        };
+        framebuffer.fullscreen = Some((output_id.clone(), windowed_position));
+
+        self.parent.move_framebuffer(&self.id, output_position);
+    }
+
+    /// Puts the framebuffer back at the position and dimensions it had before
+    /// [`FramebufferAccess::set_fullscreen`] was called. Does nothing if the framebuffer isn't
+    /// fullscreen.
+    ///
+    /// If the dimensions of the framebuffer change, its content is reset and must be set again.
+    pub fn set_windowed(&mut self) {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        if let Some((_, windowed_position)) = framebuffer.fullscreen.take() {
+            self.parent.move_framebuffer(&self.id, windowed_position);
+        }
+    }
+
+    /// Returns the video output the framebuffer is fullscreen on, or `None` if it isn't
+    /// fullscreen.
+    pub fn fullscreen_output(&self) -> Option<&TOutId> {
+        self.parent
+            .framebuffers
+            .get(&self.id)
+            .unwrap()
+            .fullscreen
+            .as_ref()
+            .map(|(out, _)| out)
+    }
+
+    /// Gives focus to the framebuffer and brings it above the other framebuffers. If the
+    /// framebuffer is minimized, it is restored.
+    ///
+    /// Returns the framebuffer that had focus before, if any and if it isn't this one.
+    pub fn focus(&mut self) -> Option<TFbId> {
+        self.restore();
+
+        let this_id = &self.id;
+        self.parent.stacking.retain(|id| id != this_id);
+        self.parent.stacking.push(self.id.clone());
+        let position = self.parent.framebuffers.get(&self.id).unwrap().position;
+        self.parent.invalidate_desktop_area(&position);
+
+        match self.parent.focused.replace(self.id.clone()) {
+            Some(previous) if previous != self.id => Some(previous),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the framebuffer has focus.
+    pub fn has_focus(&self) -> bool {
+        self.parent.focused.as_ref() == Some(&self.id)
+    }
+
+    /// Hides the framebuffer until [`FramebufferAccess::focus`] or
+    /// [`FramebufferAccess::maximize`] is called.
+    ///
+    /// Returns true if the framebuffer had focus, in which case no framebuffer has focus
+    /// afterwards.
+    pub fn minimize(&mut self) -> bool {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        if !framebuffer.minimized {
+            framebuffer.minimized = true;
+            let position = framebuffer.position;
+            self.parent.invalidate_desktop_area(&position);
+        }
+
+        if self.has_focus() {
+            self.parent.focused = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns true if the framebuffer is minimized.
+    pub fn is_minimized(&self) -> bool {
+        self.parent.framebuffers.get(&self.id).unwrap().minimized
+    }
+
+    /// Makes the framebuffer fill the video output where the largest area of it is visible,
+    /// restoring it first if it is minimized. See [`FramebufferAccess::set_fullscreen`].
+    ///
+    /// Does nothing if the framebuffer doesn't overlap any video output.
+    pub fn maximize(&mut self) {
+        self.restore();
+        if let Some(output_id) = self.video_output().cloned() {
+            self.set_fullscreen(&output_id);
+        }
+    }
+
+    /// Shows the framebuffer again if it is minimized.
+    fn restore(&mut self) {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        if framebuffer.minimized {
+            framebuffer.minimized = false;
+            let position = framebuffer.position;
+            self.parent.invalidate_desktop_area(&position);
+        }
+    }
+
+    /// Sets the opacity of the whole framebuffer, where `0` is fully transparent and `255` is
+    /// fully opaque. The default is `255`.
+    ///
+    /// This is multiplied with the alpha channel of each individual pixel, and pushes pending
+    /// changes to the video outputs that overlap with the framebuffer.
+    pub fn set_opacity(&mut self, opacity: u8) {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        if framebuffer.global_opacity == opacity {
+            return;
+        }
+
+        framebuffer.global_opacity = opacity;
+        let position = framebuffer.position;
+        self.parent.invalidate_desktop_area(&position);
+    }
+
     /// Sets the content of the framebuffer.
     ///
     /// This potentially pushes pending changes to the various video outputs that can later be
     /// retreived using [`VideoOutputAccess::drain_pending_changes`].
-    pub fn set_content(&mut self, x_range: Range<u32>, y_range: Range<u32>, data: &[u8]) {}
+    ///
+    /// `data` must contain RGB triplets, row by row, covering the given area.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the area isn't within the framebuffer, or if the size of `data` doesn't match
+    /// the area.
+    pub fn set_content(&mut self, x_range: Range<u32>, y_range: Range<u32>, data: &[u8]) {
+        let framebuffer = self.parent.framebuffers.get_mut(&self.id).unwrap();
+        let fb_width = framebuffer.position.width;
+        assert!(x_range.start <= x_range.end && x_range.end <= fb_width);
+        assert!(y_range.start <= y_range.end && y_range.end <= framebuffer.position.height);
+
+        let area_width = usize::try_from(x_range.end - x_range.start).unwrap();
+        let area_height = usize::try_from(y_range.end - y_range.start).unwrap();
+        assert_eq!(data.len(), area_width * area_height * 3);
+        if area_width == 0 || area_height == 0 {
+            return;
+        }
+
+        for (row, y) in data.chunks(area_width * 3).zip(y_range.clone()) {
+            let row_start = usize::try_from(y * fb_width + x_range.start).unwrap();
+            let dest = &mut framebuffer.rgb_data[row_start..row_start + area_width];
+            for (pixel, rgb) in dest.iter_mut().zip(row.chunks(3)) {
+                *pixel = [rgb[0], rgb[1], rgb[2], 255];
+            }
+        }
+
+        let area = rect::Rect {
+            x: framebuffer.position.x + x_range.start,
+            y: framebuffer.position.y + y_range.start,
+            width: x_range.end - x_range.start,
+            height: y_range.end - y_range.start,
+        };
+        self.parent.invalidate_desktop_area(&area);
+    }
 }
 
 /// Access to a video output within a [`Compositor`].
@@ -331,7 +696,27 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
     VideoOutputAccess<'a, TFbId, TOutId, TFb, TOut>
 {
     /// Removes the video output from the compositor state machine.
+    ///
+    /// The framebuffers that were fullscreen on this video output go back to windowed mode.
     pub fn remove(self) -> TOut {
+        let fullscreen_fbs = self
+            .parent
+            .framebuffers
+            .iter()
+            .filter(|(_, fb)| {
+                fb.fullscreen
+                    .as_ref()
+                    .map_or(false, |(out, _)| *out == self.id)
+            })
+            .map(|(fb_id, _)| fb_id.clone())
+            .collect::<Vec<_>>();
+        for fb_id in fullscreen_fbs {
+            self.parent
+                .framebuffer_by_id(&fb_id)
+                .unwrap()
+                .set_windowed();
+        }
+
         self.parent
             .video_outputs
             .remove(&self.id)
@@ -352,12 +737,40 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
             .user_data
     }
 
+    /// Returns the position of the top-left hand corner of the video output on the desktop.
+    pub fn position(&self) -> (u32, u32) {
+        let position = self.parent.video_outputs.get(&self.id).unwrap().position;
+        (position.x, position.y)
+    }
+
+    /// Returns the width and height of the video output.
+    pub fn dimensions(&self) -> (u32, u32) {
+        let position = self.parent.video_outputs.get(&self.id).unwrap().position;
+        (position.width, position.height)
+    }
+
+    /// Returns the format of the pixels of the video output.
+    pub fn format(&self) -> Format {
+        self.parent.video_outputs.get(&self.id).unwrap().format
+    }
+
+    /// Returns the raw EDID of the monitor, as passed to [`Compositor::add_video_output`].
+    pub fn edid(&self) -> Option<&[u8]> {
+        self.parent
+            .video_outputs
+            .get(&self.id)
+            .unwrap()
+            .edid
+            .as_deref()
+    }
+
     pub fn drain_pending_changes<'b: 'a>(&'b mut self) -> impl Iterator<Item = PendingChange> + 'b {
         iter::from_fn(move || {
             let video_output = self.parent.video_outputs.get_mut(&self.id).unwrap();
             let area = video_output.needs_refresh.pop_front()?;
             let video_output_position = video_output.position;
             let video_output_format = video_output.format;
+            let color_lut = video_output.color_lut;
 
             Some(PendingChange {
                 screen_x_start: area.x,
@@ -370,7 +783,7 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
                             .flat_map(|x| {
                                 let desktop_x = x + video_output_position.x;
                                 let pixel = self.parent.desktop_pixel(desktop_x, desktop_y);
-                                convert_format(pixel, &video_output_format)
+                                convert_format(pixel, &color_lut, &video_output_format)
                             })
                             .collect()
                     })
@@ -378,6 +791,94 @@ impl<'a, TFbId: Clone + Eq + Hash, TOutId: Clone + Eq + Hash, TFb, TOut>
             })
         })
     }
+
+    /// Changes the width and height of the video output, for example after its video mode has
+    /// been switched.
+    ///
+    /// The video outputs located to the right of this one on the desktop are moved so that they
+    /// stay adjacent to it. The framebuffers that are fullscreen on the affected video outputs
+    /// follow their new dimensions, and the affected video outputs are entirely refreshed.
+    /// Pending changes that haven't been drained yet are discarded, as they might no longer fit.
+    pub fn set_dimensions(&mut self, width: u32, height: u32) {
+        let video_output = self.parent.video_outputs.get_mut(&self.id).unwrap();
+        let old_position = video_output.position;
+        if old_position.width == width && old_position.height == height {
+            return;
+        }
+
+        video_output.position.width = width;
+        video_output.position.height = height;
+        video_output.invalidate_all();
+
+        let mut affected = vec![self.id.clone()];
+        for (id, other) in self.parent.video_outputs.iter_mut() {
+            if other.position.x <= old_position.x {
+                continue;
+            }
+
+            other.position.x = other.position.x - old_position.width + width;
+            other.invalidate_all();
+            affected.push(id.clone());
+        }
+
+        for id in affected {
+            self.parent.move_fullscreen_framebuffers(&id);
+        }
+    }
+
+    /// Sets the gamma of the red, green and blue channels of the video output. The default is
+    /// `1.0` for each channel.
+    ///
+    /// The entire video output is refreshed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if one of the values isn't strictly positive.
+    ///
+    pub fn set_gamma(&mut self, r: f32, g: f32, b: f32) {
+        assert!(r > 0.0 && g > 0.0 && b > 0.0);
+        let video_output = self.parent.video_outputs.get_mut(&self.id).unwrap();
+        video_output.gamma = [r, g, b];
+        video_output.refresh_colors();
+    }
+
+    /// Sets the multiplier applied to all the channels of the video output. The default is `1.0`.
+    ///
+    /// The entire video output is refreshed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is negative.
+    ///
+    pub fn set_brightness(&mut self, brightness: f32) {
+        assert!(brightness >= 0.0);
+        let video_output = self.parent.video_outputs.get_mut(&self.id).unwrap();
+        video_output.brightness = brightness;
+        video_output.refresh_colors();
+    }
+
+    /// Returns the full content of the video output, in the format of the video output.
+    ///
+    /// Pixels are ordered row by row. Contrary to [`VideoOutputAccess::drain_pending_changes`],
+    /// this always composites the entire area of the video output, and doesn't modify the list
+    /// of pending changes.
+    pub fn capture(&self) -> Vec<u8> {
+        let video_output = self.parent.video_outputs.get(&self.id).unwrap();
+        let position = video_output.position;
+        let format = video_output.format;
+        let color_lut = video_output.color_lut;
+
+        (position.y..position.y + position.height)
+            .flat_map(|desktop_y| {
+                (position.x..position.x + position.width)
+                    .map(move |desktop_x| (desktop_x, desktop_y))
+            })
+            .flat_map(|(desktop_x, desktop_y)| {
+                let pixel = self.parent.desktop_pixel(desktop_x, desktop_y);
+                convert_format(pixel, &color_lut, &format)
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -395,7 +896,31 @@ pub enum Format {
     R8G8B8X8,
 }
 
-fn convert_format(pixel: [u8; 3], format: &Format) -> impl Iterator<Item = u8> {
+/// Builds the lookup tables mapping the value of each channel of a desktop pixel to the value
+/// presented on the video output.
+fn build_color_lut(gamma: [f32; 3], brightness: f32) -> [[u8; 256]; 3] {
+    let mut out = [[0; 256]; 3];
+    for (table, gamma) in out.iter_mut().zip(gamma.iter()) {
+        for (value, entry) in table.iter_mut().enumerate() {
+            let normalized = f32::from(u8::try_from(value).unwrap()) / 255.0;
+            let adjusted = libm::powf(normalized, 1.0 / gamma) * brightness * 255.0;
+            *entry = libm::roundf(adjusted.min(255.0)) as u8;
+        }
+    }
+    out
+}
+
+fn convert_format(
+    pixel: [u8; 3],
+    color_lut: &[[u8; 256]; 3],
+    format: &Format,
+) -> impl Iterator<Item = u8> {
+    let pixel = [
+        color_lut[0][usize::from(pixel[0])],
+        color_lut[1][usize::from(pixel[1])],
+        color_lut[2][usize::from(pixel[2])],
+    ];
+
     match format {
         Format::R8G8B8X8 => iter::once(pixel[0])
             .chain(iter::once(pixel[1]))
@@ -403,3 +928,209 @@ fn convert_format(pixel: [u8; 3], format: &Format) -> impl Iterator<Item = u8> {
             .chain(iter::once(0xff)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{blend, build_color_lut, Compositor, Format};
+
+    #[test]
+    fn framebuffer_at_follows_stacking() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 100, 100, Format::R8G8B8X8, None, ());
+        // Framebuffers are placed at (20, 20) then (40, 40).
+        compositor.add_framebuffer(0, 30, 30, ());
+        compositor.add_framebuffer(1, 30, 30, ());
+
+        assert_eq!(compositor.framebuffer_at(10, 10), None);
+        assert_eq!(compositor.framebuffer_at(20, 20), Some(&0));
+        assert_eq!(compositor.framebuffer_at(45, 45), Some(&1));
+        assert_eq!(compositor.framebuffer_at(69, 69), Some(&1));
+        assert_eq!(compositor.framebuffer_at(70, 70), None);
+
+        compositor.framebuffer_by_id(&0).unwrap().focus();
+        assert_eq!(compositor.framebuffer_at(45, 45), Some(&0));
+        compositor.framebuffer_by_id(&0).unwrap().minimize();
+        assert_eq!(compositor.framebuffer_at(45, 45), Some(&1));
+        assert_eq!(compositor.framebuffer_at(20, 20), None);
+
+        // Fullscreen framebuffers are above the other ones.
+        compositor.framebuffer_by_id(&0).unwrap().maximize();
+        compositor.framebuffer_by_id(&1).unwrap().focus();
+        assert_eq!(compositor.framebuffer_at(45, 45), Some(&0));
+    }
+
+    #[test]
+    fn focus_and_minimize() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 60, 60, Format::R8G8B8X8, None, ());
+        // Framebuffers are placed at (20, 20) then (40, 40), and overlap at (45, 45).
+        compositor.add_framebuffer(0, 30, 30, ());
+        compositor.add_framebuffer(1, 30, 30, ());
+        for (id, color) in [(0, [255, 0, 0]), (1, [0, 0, 0])] {
+            let data = color.repeat(30 * 30);
+            let mut framebuffer = compositor.framebuffer_by_id(&id).unwrap();
+            framebuffer.set_content(0..30, 0..30, &data);
+        }
+
+        let overlap_pixel = |compositor: &mut Compositor<u32, u32, (), ()>| {
+            let capture = compositor.video_output_by_id(&0).unwrap().capture();
+            let offset = (45 * 60 + 45) * 4;
+            [capture[offset], capture[offset + 1], capture[offset + 2]]
+        };
+
+        assert_eq!(compositor.focused_framebuffer(), None);
+        assert_eq!(overlap_pixel(&mut compositor), [0, 0, 0]);
+        assert_eq!(compositor.framebuffer_by_id(&0).unwrap().focus(), None);
+        assert_eq!(overlap_pixel(&mut compositor), [255, 0, 0]);
+        assert_eq!(compositor.framebuffer_by_id(&1).unwrap().focus(), Some(0));
+        assert_eq!(compositor.focused_framebuffer(), Some(&1));
+        assert_eq!(compositor.framebuffer_by_id(&1).unwrap().focus(), None);
+        assert_eq!(overlap_pixel(&mut compositor), [0, 0, 0]);
+
+        let mut framebuffer = compositor.framebuffer_by_id(&1).unwrap();
+        assert!(framebuffer.minimize());
+        assert!(framebuffer.is_minimized());
+        assert_eq!(compositor.focused_framebuffer(), None);
+        assert_eq!(overlap_pixel(&mut compositor), [255, 0, 0]);
+
+        // Minimizing a framebuffer that doesn't have focus doesn't change the focus.
+        assert_eq!(compositor.framebuffer_by_id(&1).unwrap().focus(), None);
+        assert!(!compositor.framebuffer_by_id(&1).unwrap().is_minimized());
+        assert!(!compositor.framebuffer_by_id(&0).unwrap().minimize());
+        assert_eq!(compositor.focused_framebuffer(), Some(&1));
+
+        compositor.framebuffer_by_id(&1).unwrap().remove();
+        assert_eq!(compositor.focused_framebuffer(), None);
+        assert_eq!(overlap_pixel(&mut compositor), [255, 255, 255]);
+    }
+
+    #[test]
+    fn maximize_restores_and_fills_output() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 100, 50, Format::R8G8B8X8, None, ());
+        compositor.add_framebuffer(0, 10, 10, ());
+
+        let mut framebuffer = compositor.framebuffer_by_id(&0).unwrap();
+        framebuffer.minimize();
+        framebuffer.maximize();
+        assert!(!framebuffer.is_minimized());
+        assert_eq!(framebuffer.fullscreen_output(), Some(&0));
+        assert_eq!(framebuffer.dimensions(), (100, 50));
+    }
+
+    #[test]
+    fn color_lut_identity() {
+        let lut = build_color_lut([1.0; 3], 1.0);
+        for table in &lut {
+            for (value, entry) in table.iter().enumerate() {
+                assert_eq!(usize::from(*entry), value);
+            }
+        }
+    }
+
+    #[test]
+    fn color_lut_gamma_and_brightness() {
+        let lut = build_color_lut([2.0, 1.0, 1.0], 0.5);
+        assert_eq!(lut[0][0], 0);
+        assert_eq!(lut[0][64], 64);
+        assert_eq!(lut[0][255], 128);
+        assert_eq!(lut[1][64], 32);
+        assert_eq!(lut[1][255], 128);
+
+        // Values above the maximum are saturated.
+        let lut = build_color_lut([1.0; 3], 2.0);
+        assert_eq!(lut[2][100], 200);
+        assert_eq!(lut[2][200], 255);
+    }
+
+    #[test]
+    fn blend_keeps_channels_apart() {
+        assert_eq!(blend([10, 20, 30, 255], [0, 0, 0]), [10, 20, 30]);
+        assert_eq!(blend([10, 20, 30, 0], [40, 50, 60]), [40, 50, 60]);
+        assert_eq!(blend([255, 0, 0, 51], [0, 0, 255]), [51, 0, 204]);
+    }
+
+    #[test]
+    fn capture_applies_brightness() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 3, 1, Format::R8G8B8X8, None, ());
+        compositor.add_framebuffer(0, 1, 1, ());
+
+        let mut framebuffer = compositor.framebuffer_by_id(&0).unwrap();
+        framebuffer.set_fullscreen(&0);
+        framebuffer.set_content(0..3, 0..1, &[255, 0, 0, 0, 255, 0, 0, 0, 64]);
+
+        let mut video_output = compositor.video_output_by_id(&0).unwrap();
+        assert_eq!(
+            video_output.capture(),
+            [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 64, 255]
+        );
+
+        video_output.set_brightness(0.5);
+        assert_eq!(
+            video_output.capture(),
+            [128, 0, 0, 255, 0, 128, 0, 255, 0, 0, 32, 255]
+        );
+    }
+
+    #[test]
+    fn fullscreen_reports_resize() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 100, 50, Format::R8G8B8X8, None, ());
+        compositor.add_framebuffer(0, 10, 10, ());
+
+        let mut framebuffer = compositor.framebuffer_by_id(&0).unwrap();
+        assert!(!framebuffer.take_resized());
+
+        framebuffer.set_fullscreen(&0);
+        assert_eq!(framebuffer.dimensions(), (100, 50));
+        assert!(framebuffer.take_resized());
+        assert!(!framebuffer.take_resized());
+
+        // Making the framebuffer fullscreen again on the same output doesn't resize it.
+        framebuffer.set_fullscreen(&0);
+        assert!(!framebuffer.take_resized());
+
+        framebuffer.set_windowed();
+        assert_eq!(framebuffer.dimensions(), (10, 10));
+        assert!(framebuffer.take_resized());
+    }
+
+    #[test]
+    fn set_dimensions_resizes_fullscreen_framebuffers() {
+        let mut compositor = Compositor::<u32, u32, (), ()>::with_seed([0; 64]);
+        compositor.add_video_output(0, 100, 50, Format::R8G8B8X8, None, ());
+        compositor.add_video_output(1, 100, 50, Format::R8G8B8X8, None, ());
+        compositor.add_framebuffer(0, 10, 10, ());
+        compositor.add_framebuffer(1, 10, 10, ());
+
+        compositor.framebuffer_by_id(&0).unwrap().set_fullscreen(&0);
+        compositor.framebuffer_by_id(&1).unwrap().set_fullscreen(&1);
+        for id in 0..2 {
+            assert!(compositor.framebuffer_by_id(&id).unwrap().take_resized());
+        }
+
+        compositor
+            .video_output_by_id(&0)
+            .unwrap()
+            .set_dimensions(200, 80);
+        assert_eq!(
+            compositor.video_output_by_id(&0).unwrap().position(),
+            (0, 0)
+        );
+        assert_eq!(
+            compositor.video_output_by_id(&1).unwrap().position(),
+            (200, 0)
+        );
+
+        let mut framebuffer = compositor.framebuffer_by_id(&0).unwrap();
+        assert_eq!(framebuffer.dimensions(), (200, 80));
+        assert!(framebuffer.take_resized());
+
+        // The other framebuffer follows its video output, but keeps its dimensions.
+        let mut framebuffer = compositor.framebuffer_by_id(&1).unwrap();
+        assert_eq!(framebuffer.position(), (200, 0));
+        assert_eq!(framebuffer.dimensions(), (100, 50));
+        assert!(!framebuffer.take_resized());
+    }
+}
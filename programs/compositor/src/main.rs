@@ -15,7 +15,7 @@
 
 #![recursion_limit = "2048"]
 
-use futures::prelude::*;
+use futures::{future::FusedFuture as _, prelude::*};
 use rand::RngCore as _;
 use redshirt_framebuffer_interface::ffi as fb_ffi;
 use redshirt_interface_interface::DecodedInterfaceOrDestroyed;
@@ -24,6 +24,344 @@ use redshirt_time_interface::Delay;
 use redshirt_video_output_interface::ffi as vid_ffi;
 use std::{collections::VecDeque, convert::TryFrom as _, time::Duration};
 
+struct VideoOutput {
+    next_frame_messages: VecDeque<MessageId>,
+    /// Refresh rate of the current mode, in millihertz.
+    refresh_rate_mhz: u32,
+    /// Modes supported by the video output, as reported at registration.
+    modes: Vec<vid_ffi::Mode>,
+    /// Mode to report alongside the next image, if it has been changed since the last one.
+    pending_mode: Option<vid_ffi::Mode>,
+}
+
+struct Framebuffer {
+    next_event_messages: VecDeque<MessageId>,
+    /// Messages to answer once the next frame has been handed to the video outputs.
+    present_messages: Vec<MessageId>,
+}
+
+/// State of the mouse cursor, shared by all the video outputs.
+#[derive(Default)]
+struct Pointer {
+    /// Position of the cursor on the desktop in millipixels, or `None` if it isn't on any video
+    /// output.
+    position: Option<(u64, u64)>,
+    /// Framebuffer the cursor is over, if any.
+    hovered: Option<(Pid, u32)>,
+    /// Framebuffer that has grabbed the pointer, if any. Always the framebuffer that has focus.
+    ///
+    /// The compositor doesn't draw the cursor itself, and video outputs aren't told about grabs.
+    /// As such, the cursor is locked but not hidden while the pointer is grabbed.
+    grab: Option<(Pid, u32)>,
+}
+
+impl Pointer {
+    /// Forgets about a framebuffer that has been destroyed.
+    fn framebuffer_removed(&mut self, framebuffer_id: &(Pid, u32)) {
+        if self.hovered.as_ref() == Some(framebuffer_id) {
+            self.hovered = None;
+        }
+        if self.grab.as_ref() == Some(framebuffer_id) {
+            self.grab = None;
+        }
+    }
+}
+
+/// State of the modifier keys of the keyboard, shared by all the video outputs.
+#[derive(Default)]
+struct Modifiers {
+    /// Whether the left and right Ctrl keys are pressed.
+    ctrl: [bool; 2],
+    /// Whether the left and right Alt keys are pressed.
+    alt: [bool; 2],
+}
+
+impl Modifiers {
+    /// Updates the state after a key has been pressed or released. Returns true if this is the
+    /// Delete key being pressed while Ctrl and Alt are pressed.
+    fn update(&mut self, scancode: u16, pressed: bool) -> bool {
+        // Scancodes from the USB HID Usage tables.
+        match scancode {
+            0xe0 => self.ctrl[0] = pressed,
+            0xe4 => self.ctrl[1] = pressed,
+            0xe2 => self.alt[0] = pressed,
+            0xe6 => self.alt[1] = pressed,
+            0x4c => return pressed && self.ctrl.iter().any(|k| *k) && self.alt.iter().any(|k| *k),
+            _ => {}
+        }
+
+        false
+    }
+}
+
+type Compositor = compositor::Compositor<(Pid, u32), (Pid, u64), Framebuffer, VideoOutput>;
+
+/// Reports an event to a framebuffer by answering its oldest message asking for an event. The
+/// event is lost if there isn't any such message.
+fn send_event(compositor: &mut Compositor, framebuffer_id: &(Pid, u32), event: fb_ffi::Event) {
+    let mut framebuffer = match compositor.framebuffer_by_id(framebuffer_id) {
+        Some(fb) => fb,
+        None => return,
+    };
+
+    if let Some(message_id) = framebuffer.user_data_mut().next_event_messages.pop_front() {
+        redshirt_interface_interface::emit_answer(message_id, event);
+    }
+}
+
+/// Gives focus to a framebuffer, and reports the change to the framebuffers concerned.
+fn focus_framebuffer(
+    compositor: &mut Compositor,
+    pointer: &mut Pointer,
+    framebuffer_id: &(Pid, u32),
+) {
+    let mut framebuffer = match compositor.framebuffer_by_id(framebuffer_id) {
+        Some(fb) => fb,
+        None => return,
+    };
+
+    let had_focus = framebuffer.has_focus();
+    if let Some(previous) = framebuffer.focus() {
+        focus_lost(compositor, pointer, &previous);
+    }
+    if !had_focus {
+        send_event(
+            compositor,
+            framebuffer_id,
+            fb_ffi::Event::FocusChanged { focused: true },
+        );
+    }
+}
+
+/// Reports to a framebuffer that it has lost focus, and releases the pointer grab if it held it.
+fn focus_lost(compositor: &mut Compositor, pointer: &mut Pointer, framebuffer_id: &(Pid, u32)) {
+    send_event(
+        compositor,
+        framebuffer_id,
+        fb_ffi::Event::FocusChanged { focused: false },
+    );
+
+    if pointer.grab.as_ref() == Some(framebuffer_id) {
+        pointer.grab = None;
+        send_event(
+            compositor,
+            framebuffer_id,
+            fb_ffi::Event::PointerGrabReleased,
+        );
+    }
+}
+
+/// Forwards an input event reported by a video output to the framebuffer concerned.
+///
+/// Keyboard and character events go to the framebuffer that has focus. Mouse events go to the
+/// framebuffer under the cursor, and pressing a mouse button gives focus to it. While the pointer
+/// is grabbed, mouse events go to the framebuffer that has grabbed it, and only raw mouse motions
+/// are reported instead of cursor movements.
+///
+/// Returns true if Ctrl+Alt+Del has been pressed, in which case the machine should be restarted.
+/// This key combination isn't reported to any framebuffer.
+fn handle_input(
+    compositor: &mut Compositor,
+    pointer: &mut Pointer,
+    modifiers: &mut Modifiers,
+    output_id: &(Pid, u64),
+    event: vid_ffi::InputEvent,
+) -> bool {
+    match event {
+        vid_ffi::InputEvent::KeyboardChange { scancode, pressed } => {
+            if modifiers.update(scancode, pressed) {
+                return true;
+            }
+
+            if let Some(focused) = compositor.focused_framebuffer().cloned() {
+                let new_state = element_state(pressed);
+                send_event(
+                    compositor,
+                    &focused,
+                    fb_ffi::Event::KeyboardChange {
+                        scancode,
+                        new_state,
+                    },
+                );
+            }
+        }
+        vid_ffi::InputEvent::Character { character } => {
+            if let Some(focused) = compositor.focused_framebuffer().cloned() {
+                send_event(compositor, &focused, fb_ffi::Event::Character { character });
+            }
+        }
+        vid_ffi::InputEvent::CursorMoved { position } => {
+            let (output_x, output_y) = match compositor.video_output_by_id(output_id) {
+                Some(vo) => vo.position(),
+                None => return false,
+            };
+            pointer.position = position.map(|(x, y)| {
+                (
+                    u64::from(output_x).saturating_mul(1000).saturating_add(x),
+                    u64::from(output_y).saturating_mul(1000).saturating_add(y),
+                )
+            });
+
+            // The cursor is locked while the pointer is grabbed.
+            if pointer.grab.is_some() {
+                return false;
+            }
+
+            let hovered = pointer.position.and_then(|(x, y)| {
+                let x = u32::try_from(x / 1000).ok()?;
+                let y = u32::try_from(y / 1000).ok()?;
+                compositor.framebuffer_at(x, y).cloned()
+            });
+
+            if let Some(previous) = pointer.hovered.take() {
+                if Some(&previous) != hovered.as_ref() {
+                    send_event(
+                        compositor,
+                        &previous,
+                        fb_ffi::Event::CursorMoved { new_position: None },
+                    );
+                }
+            }
+
+            if let (Some(hovered), Some((x, y))) = (&hovered, pointer.position) {
+                let (fb_x, fb_y) = compositor.framebuffer_by_id(hovered).unwrap().position();
+                let new_position = Some((x - u64::from(fb_x) * 1000, y - u64::from(fb_y) * 1000));
+                send_event(
+                    compositor,
+                    hovered,
+                    fb_ffi::Event::CursorMoved { new_position },
+                );
+            }
+
+            pointer.hovered = hovered;
+        }
+        vid_ffi::InputEvent::MouseButtonChange { button, pressed } => {
+            let target = match pointer.grab.or(pointer.hovered) {
+                Some(fb) => fb,
+                None => return false,
+            };
+
+            if pressed {
+                focus_framebuffer(compositor, pointer, &target);
+            }
+
+            let button = match button {
+                vid_ffi::MouseButton::Main => fb_ffi::MouseButton::Main,
+                vid_ffi::MouseButton::Secondary => fb_ffi::MouseButton::Secondary,
+            };
+            send_event(
+                compositor,
+                &target,
+                fb_ffi::Event::MouseButtonChange {
+                    button,
+                    new_state: element_state(pressed),
+                },
+            );
+        }
+        vid_ffi::InputEvent::RawMouseMotion { dx, dy } => {
+            if let Some(grab) = pointer.grab {
+                send_event(compositor, &grab, fb_ffi::Event::RawMouseMotion { dx, dy });
+            }
+        }
+    }
+
+    false
+}
+
+fn element_state(pressed: bool) -> fb_ffi::ElementState {
+    if pressed {
+        fb_ffi::ElementState::Pressed
+    } else {
+        fb_ffi::ElementState::Released
+    }
+}
+
+/// Builds the description of a video output, as answered to framebuffer messages 10 and 11.
+fn output_info<TFb>(
+    id: (Pid, u64),
+    video_output: &compositor::VideoOutputAccess<(Pid, u32), (Pid, u64), TFb, VideoOutput>,
+) -> fb_ffi::OutputInfo {
+    let (x, y) = video_output.position();
+    let (width, height) = video_output.dimensions();
+    let edid = video_output
+        .edid()
+        .and_then(redshirt_video_output_interface::edid::parse);
+    fb_ffi::OutputInfo {
+        id: fb_ffi::OutputId {
+            handler_pid: u64::from(id.0),
+            handler_id: id.1,
+        },
+        x,
+        y,
+        width,
+        height,
+        format: match video_output.format() {
+            compositor::Format::R8G8B8X8 => fb_ffi::OutputFormat::R8G8B8X8,
+        },
+        physical_size_mm: edid.as_ref().and_then(|edid| edid.physical_size_mm),
+        native_resolution: edid
+            .as_ref()
+            .and_then(|edid| edid.native_mode.as_ref())
+            .map(|mode| (mode.width, mode.height)),
+        refresh_rate_mhz: video_output.user_data().refresh_rate_mhz,
+        modes: video_output
+            .user_data()
+            .modes
+            .iter()
+            .map(|mode| fb_ffi::VideoMode {
+                width: mode.width,
+                height: mode.height,
+                refresh_rate_mhz: mode.refresh_rate_mhz,
+            })
+            .collect(),
+    }
+}
+
+/// Switches the video output to the given mode on behalf of `emitter`. Returns an error if the
+/// video output doesn't exist or doesn't support this mode.
+///
+/// Also returns an error unless `emitter` owns the framebuffer that has focus, and this
+/// framebuffer is displayed on the video output.
+fn set_video_mode(
+    compositor: &mut Compositor,
+    emitter: Pid,
+    output_id: fb_ffi::OutputId,
+    mode: fb_ffi::VideoMode,
+) -> Result<(), ()> {
+    let id = (Pid::from(output_id.handler_pid), output_id.handler_id);
+
+    let focused = compositor.focused_framebuffer().cloned().ok_or(())?;
+    if focused.0 != emitter
+        || compositor
+            .framebuffer_by_id(&focused)
+            .unwrap()
+            .video_output()
+            != Some(&id)
+    {
+        return Err(());
+    }
+
+    let mut video_output = compositor.video_output_by_id(&id).ok_or(())?;
+
+    let mode = vid_ffi::Mode {
+        width: mode.width,
+        height: mode.height,
+        refresh_rate_mhz: mode.refresh_rate_mhz,
+    };
+    if !video_output.user_data().modes.contains(&mode) {
+        return Err(());
+    }
+
+    // The images that have already been sent to the video output are still in the previous
+    // mode. The new mode is sent alongside the next image, whose changes are computed with the
+    // new dimensions.
+    video_output.set_dimensions(mode.width, mode.height);
+    let user_data = video_output.user_data_mut();
+    user_data.refresh_rate_mhz = mode.refresh_rate_mhz;
+    user_data.pending_mode = Some(mode);
+    Ok(())
+}
+
 fn main() {
     redshirt_syscalls::block_on(async_main())
 }
@@ -50,13 +388,12 @@ async fn async_main() {
         seed
     });
 
-    struct VideoOutput {
-        next_frame_messages: VecDeque<MessageId>,
-    }
+    let mut pointer = Pointer::default();
+    let mut modifiers = Modifiers::default();
 
-    struct Framebuffer {
-        next_event_messages: VecDeque<MessageId>,
-    }
+    // Request to restart the machine, if Ctrl+Alt+Del has been pressed. The kernel only answers
+    // it if the machine couldn't be restarted.
+    let mut reboot = future::Fuse::terminated();
 
     let mut next_frame = Delay::new(Duration::from_secs(0)).fuse();
 
@@ -65,14 +402,27 @@ async fn async_main() {
             video_output_event = video_registration.next_message_raw().fuse() => {
                 match video_output_event {
                     DecodedInterfaceOrDestroyed::Interface(msg) => {
-                        match vid_ffi::VideoOutputMessage::decode(msg.actual_data).unwrap() {
-                            vid_ffi::VideoOutputMessage::Register { id, width, height, format } => {
+                        let msg_data = match vid_ffi::VideoOutputMessage::decode(msg.actual_data) {
+                            Ok(m) => m,
+                            Err(_) => {
+                                if let Some(message_id) = msg.message_id {
+                                    redshirt_interface_interface::emit_message_error(message_id);
+                                }
+                                continue;
+                            }
+                        };
+
+                        match msg_data {
+                            vid_ffi::VideoOutputMessage::Register { id, width, height, refresh_rate_mhz, modes, format, edid } => {
                                 let format = match format {
                                     vid_ffi::Format::R8G8B8X8 => compositor::Format::R8G8B8X8,
                                 };
 
-                                compositor.add_video_output((msg.emitter_pid, id), width, height, format, VideoOutput {
+                                compositor.add_video_output((msg.emitter_pid, id), width, height, format, edid, VideoOutput {
                                     next_frame_messages: VecDeque::with_capacity(16),
+                                    refresh_rate_mhz,
+                                    modes,
+                                    pending_mode: None,
                                 });
                             }
                             vid_ffi::VideoOutputMessage::Unregister(id) => {
@@ -93,6 +443,12 @@ async fn async_main() {
                                     }
                                 }
                             }
+                            vid_ffi::VideoOutputMessage::Input { id, event } => {
+                                let reboot_requested = handle_input(&mut compositor, &mut pointer, &mut modifiers, &(msg.emitter_pid, id), event);
+                                if reboot_requested && reboot.is_terminated() {
+                                    reboot = Box::pin(redshirt_power_interface::reboot()).fuse();
+                                }
+                            }
                         }
                     },
                     DecodedInterfaceOrDestroyed::ProcessDestroyed(destroyed) => {
@@ -104,6 +460,7 @@ async fn async_main() {
                             compositor.video_output_by_id(&video_output_id).unwrap().remove();
                         }
                     }
+                    DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => {}
                 }
             },
 
@@ -112,25 +469,40 @@ async fn async_main() {
                     DecodedInterfaceOrDestroyed::Interface(msg) => {
                         match msg.actual_data.0.get(0) {
                             Some(0) if msg.actual_data.0.len() == 13 => {
-                                let fb_id = u32::from_le_bytes(<[u8; 4]>::try_from(&msg.actual_data.0[1..5]).unwrap());
-                                let width = u32::from_le_bytes(<[u8; 4]>::try_from(&msg.actual_data.0[5..9]).unwrap());
-                                let height = u32::from_le_bytes(<[u8; 4]>::try_from(&msg.actual_data.0[9..13]).unwrap());
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                let width: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 5).unwrap();
+                                let height: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 9).unwrap();
                                 compositor.add_framebuffer((msg.emitter_pid, fb_id), width, height, Framebuffer {
                                     next_event_messages: VecDeque::with_capacity(16),
+                                    present_messages: Vec::new(),
                                 });
                             }
                             Some(1) if msg.actual_data.0.len() == 5 => {
-                                let fb_id = u32::from_le_bytes(<[u8; 4]>::try_from(&msg.actual_data.0[1..5]).unwrap());
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
                                 if let Some(fb) = compositor.framebuffer_by_id(&(msg.emitter_pid, fb_id)) {
                                     let framebuffer = fb.remove();
+                                    pointer.framebuffer_removed(&(msg.emitter_pid, fb_id));
                                     for message_id in framebuffer.next_event_messages {
                                         redshirt_interface_interface::emit_message_error(message_id);
                                     }
+                                    for message_id in framebuffer.present_messages {
+                                        redshirt_interface_interface::emit_message_error(message_id);
+                                    }
+                                }
+                            }
+                            Some(2) if msg.actual_data.0.len() >= 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                if let Some(mut fb) = compositor.framebuffer_by_id(&(msg.emitter_pid, fb_id)) {
+                                    // Messages whose data doesn't cover the whole framebuffer are ignored.
+                                    let (width, height) = fb.dimensions();
+                                    let expected_len = usize::try_from(u64::from(width) * u64::from(height) * 3).ok();
+                                    if expected_len == Some(msg.actual_data.0.len() - 5) {
+                                        fb.set_content(0..width, 0..height, &msg.actual_data.0[5..]);
+                                    }
                                 }
                             }
-                            // TODO: Some(2) handling
                             Some(3) if msg.actual_data.0.len() == 5 => {
-                                let fb_id = u32::from_le_bytes(<[u8; 4]>::try_from(&msg.actual_data.0[1..5]).unwrap());
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
                                 if let Some(message_id) = msg.message_id {
                                     if let Some(mut fb) = compositor.framebuffer_by_id(&(msg.emitter_pid, fb_id)) {
                                         // TODO: add some limit to the number of events
@@ -140,6 +512,119 @@ async fn async_main() {
                                     }
                                 }
                             }
+                            Some(4) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                let id = (msg.emitter_pid, fb_id);
+                                if compositor.focused_framebuffer() == Some(&id) {
+                                    pointer.grab = Some(id);
+                                }
+                            }
+                            Some(5) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                if pointer.grab == Some((msg.emitter_pid, fb_id)) {
+                                    pointer.grab = None;
+                                }
+                            }
+                            Some(6) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                focus_framebuffer(&mut compositor, &mut pointer, &(msg.emitter_pid, fb_id));
+                            }
+                            Some(7) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                let id = (msg.emitter_pid, fb_id);
+                                if let Some(mut fb) = compositor.framebuffer_by_id(&id) {
+                                    if fb.minimize() {
+                                        focus_lost(&mut compositor, &mut pointer, &id);
+                                    }
+                                }
+                            }
+                            Some(8) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                if let Some(mut fb) = compositor.framebuffer_by_id(&(msg.emitter_pid, fb_id)) {
+                                    fb.maximize();
+                                }
+                            }
+                            Some(10) if msg.actual_data.0.len() == 1 => {
+                                if let Some(message_id) = msg.message_id {
+                                    let mut infos = Vec::with_capacity(compositor.video_outputs().len());
+                                    for id in compositor.video_outputs().cloned().collect::<Vec<_>>() {
+                                        infos.push(output_info(id, &compositor.video_output_by_id(&id).unwrap()));
+                                    }
+                                    redshirt_interface_interface::emit_answer(message_id, infos);
+                                }
+                            }
+                            Some(11) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                if let Some(message_id) = msg.message_id {
+                                    let output_id = compositor
+                                        .framebuffer_by_id(&(msg.emitter_pid, fb_id))
+                                        .map(|fb| fb.video_output().cloned());
+                                    match output_id {
+                                        Some(output_id) => {
+                                            let info = output_id.map(|id| {
+                                                output_info(id, &compositor.video_output_by_id(&id).unwrap())
+                                            });
+                                            redshirt_interface_interface::emit_answer(message_id, info);
+                                        }
+                                        None => redshirt_interface_interface::emit_message_error(message_id),
+                                    }
+                                }
+                            }
+                            Some(12) if msg.actual_data.0.len() == 5 => {
+                                let fb_id: u32 = redshirt_syscalls::read_le(&msg.actual_data.0, 1).unwrap();
+                                if let Some(message_id) = msg.message_id {
+                                    if let Some(mut fb) = compositor.framebuffer_by_id(&(msg.emitter_pid, fb_id)) {
+                                        fb.user_data_mut().present_messages.push(message_id);
+                                    } else {
+                                        redshirt_interface_interface::emit_message_error(message_id);
+                                    }
+                                }
+                            }
+                            Some(13) => {
+                                let result = match fb_ffi::decode_set_video_mode(&msg.actual_data.0) {
+                                    Some((output_id, mode)) => set_video_mode(&mut compositor, msg.emitter_pid, output_id, mode),
+                                    None => Err(()),
+                                };
+
+                                if let Some(message_id) = msg.message_id {
+                                    match result {
+                                        Ok(()) => redshirt_interface_interface::emit_answer(message_id, ()),
+                                        Err(()) => redshirt_interface_interface::emit_message_error(message_id),
+                                    }
+                                }
+                            }
+                            Some(9) => {
+                                let updates = match fb_ffi::decode_batched_update(&msg.actual_data.0) {
+                                    Some(u) => u,
+                                    None => {
+                                        if let Some(message_id) = msg.message_id {
+                                            redshirt_interface_interface::emit_message_error(message_id);
+                                        }
+                                        continue;
+                                    }
+                                };
+
+                                // Updates are all applied before the next frame. Updates that
+                                // don't fit in their framebuffer are ignored.
+                                for update in updates {
+                                    let mut fb = match compositor.framebuffer_by_id(&(msg.emitter_pid, update.framebuffer_id)) {
+                                        Some(fb) => fb,
+                                        None => continue,
+                                    };
+
+                                    let (width, height) = fb.dimensions();
+                                    let x_range = match update.x.checked_add(update.width) {
+                                        Some(end) if end <= width => update.x..end,
+                                        _ => continue,
+                                    };
+                                    let y_range = match update.y.checked_add(update.height) {
+                                        Some(end) if end <= height => update.y..end,
+                                        _ => continue,
+                                    };
+
+                                    fb.set_content(x_range, y_range, update.data);
+                                }
+                            }
                             _ => {
                                 if let Some(message_id) = msg.message_id {
                                     redshirt_interface_interface::emit_message_error(message_id);
@@ -154,11 +639,17 @@ async fn async_main() {
                             }
 
                             compositor.framebuffer_by_id(&framebuffer_id).unwrap().remove();
+                            pointer.framebuffer_removed(&framebuffer_id);
                         }
                     }
+                    DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => {}
                 }
             },
 
+            _ = reboot => {
+                // The machine couldn't be restarted. There is nothing more we can do.
+            },
+
             () = next_frame => {
                 compositor.next_frame();
                 next_frame = Delay::new(Duration::new(0, 16666667)).fuse();
@@ -171,6 +662,7 @@ async fn async_main() {
                     };
 
                     redshirt_interface_interface::emit_answer(message_id, vid_ffi::NextImage {
+                        mode: video_output.user_data_mut().pending_mode.take(),
                         changes: video_output.drain_pending_changes().map(|change| {
                             vid_ffi::NextImageChange {
                                 screen_x_start: change.screen_x_start,
@@ -181,6 +673,25 @@ async fn async_main() {
                         }).collect(),
                     });
                 }
+
+                // Updates received before a presentation request have been included in the
+                // frame that was just drawn. Framebuffers that aren't visible on any video output
+                // haven't been drawn, and their requests stay pending until they are.
+                for framebuffer_id in compositor.framebuffers().cloned().collect::<Vec<_>>() {
+                    let mut framebuffer = compositor.framebuffer_by_id(&framebuffer_id).unwrap();
+                    if framebuffer.video_output().is_some() {
+                        for message_id in framebuffer.user_data_mut().present_messages.drain(..) {
+                            redshirt_interface_interface::emit_answer(message_id, ());
+                        }
+                    }
+
+                    // Resizes are only marked as reported if there is a message to answer, so
+                    // that they are never lost.
+                    if !framebuffer.user_data().next_event_messages.is_empty() && framebuffer.take_resized() {
+                        let (width, height) = framebuffer.dimensions();
+                        send_event(&mut compositor, &framebuffer_id, fb_ffi::Event::Resized { width, height });
+                    }
+                }
             }
         }
     }
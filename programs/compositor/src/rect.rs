@@ -24,6 +24,12 @@ pub struct Rect {
 }
 
 impl Rect {
+    /// Returns true if the given point is within this rectangle.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x.checked_sub(self.x).map_or(false, |dx| dx < self.width)
+            && y.checked_sub(self.y).map_or(false, |dy| dy < self.height)
+    }
+
     /// Returns the intersection between this rectangle and another.
     ///
     /// Returns `None` if the two rectangles don't overlap.
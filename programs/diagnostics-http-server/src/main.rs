@@ -49,11 +49,15 @@ fn main() {
         .serve(hyper::service::make_service_fn(|_| async {
             Ok::<_, hyper::Error>(hyper::service::service_fn(|req| async move {
                 if req.uri().path() == "/metrics" {
-                    let metrics = redshirt_kernel_debug_interface::get_prometheus_metrics().await;
-                    hyper::Response::builder()
-                        .status(hyper::StatusCode::OK)
-                        .header("Content-Type", "text/plain; version=0.0.4")
-                        .body(hyper::Body::from(metrics))
+                    match redshirt_kernel_debug_interface::get_prometheus_metrics().await {
+                        Ok(metrics) => hyper::Response::builder()
+                            .status(hyper::StatusCode::OK)
+                            .header("Content-Type", "text/plain; version=0.0.4")
+                            .body(hyper::Body::from(metrics)),
+                        Err(()) => hyper::Response::builder()
+                            .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
+                            .body(hyper::Body::from("Failed to load metrics")),
+                    }
                 } else {
                     hyper::Response::builder()
                         .status(hyper::StatusCode::NOT_FOUND)
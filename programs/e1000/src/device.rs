@@ -21,7 +21,7 @@
 
 use core::{convert::TryFrom as _, fmt, mem, time::Duration};
 use futures::lock::Mutex;
-use redshirt_hardware_interface::malloc::PhysicalBuffer;
+use redshirt_hardware_interface::{malloc::PhysicalBuffer, ResponseMismatchError};
 use redshirt_time_interface::Delay;
 
 /// State of a device.
@@ -144,7 +144,9 @@ impl Device {
         // Set the RST flag in order to reset the device.
         redshirt_hardware_interface::write_one_u32(
             regs_base_address + REGS_CTRL,
-            redshirt_hardware_interface::read_one_u32(regs_base_address + REGS_CTRL).await
+            redshirt_hardware_interface::read_one_u32(regs_base_address + REGS_CTRL)
+                .await
+                .map_err(|_| InitErr::HardwareAccess)?
                 | (1 << 26),
         );
 
@@ -160,8 +162,9 @@ impl Device {
                     return Err(InitErr::Timeout);
                 }
 
-                let val =
-                    redshirt_hardware_interface::read_one_u32(regs_base_address + REGS_CTRL).await;
+                let val = redshirt_hardware_interface::read_one_u32(regs_base_address + REGS_CTRL)
+                    .await
+                    .map_err(|_| InitErr::HardwareAccess)?;
                 if (val & (1 << 26)) == 0 {
                     break;
                 }
@@ -180,11 +183,12 @@ impl Device {
 
     /// Reads the next pending Ethernet frame waiting to be delivered, if any.
     ///
-    /// Returns `None` if there's no packet available.
+    /// Returns `None` if there's no packet available, or an error if the memory shared with the
+    /// device couldn't be read.
     ///
     /// > **Note**: This function is asynchronous, but it returns as soon as it determines that
     /// >           no packet is available. It does *not* wait for one packet to be available.
-    pub async unsafe fn read_one_incoming(&self) -> Option<Vec<u8>> {
+    pub async unsafe fn read_one_incoming(&self) -> Result<Option<Vec<u8>>, ResponseMismatchError> {
         let mut receive_next = self.receive_next.lock().await;
         debug_assert!(*receive_next < self.receive_descriptors.len());
 
@@ -192,13 +196,13 @@ impl Device {
         let mut next_descriptor: ReceiveDescriptor = self
             .receive_descriptors
             .read_one(*receive_next)
-            .await
+            .await?
             .unwrap();
 
         // When the hardware has written the descriptor, it sets this bit on the status to mark
         // it as ready. If the bit is not set, no packet is ready.
         if next_descriptor.status & (1 << 0) == 0 {
-            return None;
+            return Ok(None);
         }
 
         // Normally, there is a N-to-one mapping between descriptors and packets. In other words,
@@ -218,7 +222,7 @@ impl Device {
             packet.set_len(packet.capacity());
             self.receive_buffers[*receive_next]
                 .read_slice(0, &mut packet[..])
-                .await;
+                .await?;
             packet
         };
 
@@ -242,12 +246,14 @@ impl Device {
         *receive_next = (*receive_next + 1) % self.receive_descriptors.len();
 
         // Success!
-        Some(packet)
+        Ok(Some(packet))
     }
 
     /// Sends a packet out. Returns an error if the device's buffer is full, in which case we must
     /// try again later.
     ///
+    /// Descriptors whose status couldn't be read are considered as still in use by the device.
+    ///
     /// # Panic
     ///
     /// Panics if the packet is too large.
@@ -274,11 +280,14 @@ impl Device {
 
                 // Read the next descriptor that we expect the hardware to have finished
                 // processing.
-                let next_descriptor: TransmitDescriptor = self
+                let next_descriptor: TransmitDescriptor = match self
                     .transmit_descriptors
                     .read_one(transmit_state.next_reclaim)
                     .await
-                    .unwrap();
+                {
+                    Ok(d) => d.unwrap(),
+                    Err(_) => break,
+                };
 
                 // When the hardware has finished processing the descriptor, it sets this bit on
                 // the status to mark it as done. If the bit is not set, the packet is still
@@ -338,19 +347,20 @@ impl Device {
 
     /// Must be called when the device generates an interrupt.
     ///
-    /// Returns a packet of data received from the network, if any.
-    pub async unsafe fn on_interrupt(&self) -> Vec<Vec<u8>> {
+    /// Returns the packets of data received from the network, if any, or an error if the device
+    /// couldn't be accessed.
+    pub async unsafe fn on_interrupt(&self) -> Result<Vec<Vec<u8>>, ResponseMismatchError> {
         // Note: there exists a register indicating the cause for the interrupt, but we choose to
         // ignore it and check everything.
         // However, the action of reading it has the side-effect of clearing its bits, which is
         // necessary for the next interrupt to be triggered.
-        let _ = redshirt_hardware_interface::read_one_u32(self.regs_base_address + REGS_ICR).await;
+        redshirt_hardware_interface::read_one_u32(self.regs_base_address + REGS_ICR).await?;
 
         let mut out = Vec::with_capacity(8);
-        while let Some(packet) = self.read_one_incoming().await {
+        while let Some(packet) = self.read_one_incoming().await? {
             out.push(packet);
         }
-        out
+        Ok(out)
     }
 }
 
@@ -377,6 +387,10 @@ impl Drop for Device {
 pub enum InitErr {
     /// Device is taking too long to respond.
     Timeout,
+    /// Failed to allocate physical memory for the descriptors or buffers.
+    AllocationFailed,
+    /// Failed to read the registers of the device.
+    HardwareAccess,
 }
 
 /// A device after it has been reinitialized but before it is active.
@@ -401,7 +415,8 @@ impl DevicePrototype {
             redshirt_hardware_interface::write_one_u32(self.regs_base_address + REGS_CTRL, {
                 let mut ctl =
                     redshirt_hardware_interface::read_one_u32(self.regs_base_address + REGS_CTRL)
-                        .await;
+                        .await
+                        .map_err(|_| InitErr::HardwareAccess)?;
                 ctl &= !(1 << 3); // Stop resetting link
                 ctl |= 1 << 5; // Automatic speed detection
                 ctl |= 1 << 6; // Set link up
@@ -447,10 +462,12 @@ impl DevicePrototype {
                 let lo =
                     redshirt_hardware_interface::read_one_u32(self.regs_base_address + REGS_RAL)
                         .await
+                        .map_err(|_| InitErr::HardwareAccess)?
                         .to_le_bytes();
                 let hi =
                     redshirt_hardware_interface::read_one_u32(self.regs_base_address + REGS_RAH)
                         .await
+                        .map_err(|_| InitErr::HardwareAccess)?
                         .to_le_bytes();
                 [lo[0], lo[1], lo[2], lo[3], hi[0], hi[1]]
             };
@@ -459,6 +476,7 @@ impl DevicePrototype {
             let (receive_descriptors, receive_buffers) = {
                 let receive_descriptors = PhysicalBuffer::new_uninit_slice_with_align(32, 16)
                     .await
+                    .map_err(|()| InitErr::AllocationFailed)?
                     .assume_init();
 
                 // Each receive descriptor has an associated 16 kiB buffer where the packet will be
@@ -469,6 +487,7 @@ impl DevicePrototype {
                         b.push(
                             PhysicalBuffer::new_uninit_slice(16 * 1024)
                                 .await
+                                .map_err(|()| InitErr::AllocationFailed)?
                                 .assume_init(),
                         );
                     }
@@ -555,6 +574,7 @@ impl DevicePrototype {
                 // previously successfully performed the write.
                 let transmit_descriptors = PhysicalBuffer::new_uninit_slice_with_align(32, 16)
                     .await
+                    .map_err(|()| InitErr::AllocationFailed)?
                     .assume_init();
                 for n in 0..transmit_descriptors.len() {
                     transmit_descriptors.write_one(
@@ -624,6 +644,7 @@ impl DevicePrototype {
                     b.push(
                         PhysicalBuffer::new_uninit_slice(16 * 1024)
                             .await
+                            .map_err(|()| InitErr::AllocationFailed)?
                             .assume_init(),
                     );
                 }
@@ -642,7 +663,8 @@ impl DevicePrototype {
                     let val = redshirt_hardware_interface::read_one_u32(
                         self.regs_base_address + REGS_STATUS,
                     )
-                    .await;
+                    .await
+                    .map_err(|_| InitErr::HardwareAccess)?;
                     if (val & (1 << 1)) != 0 {
                         break;
                     }
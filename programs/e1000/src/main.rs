@@ -34,7 +34,11 @@ fn main() {
 async fn async_main() {
     let mut e1000_devices = Vec::new();
 
-    let pci_devices = redshirt_pci_interface::get_pci_devices().await;
+    let pci_devices = match redshirt_pci_interface::get_pci_devices().await {
+        Ok(d) => d,
+        // Without the list of devices, there is nothing this driver can do.
+        Err(()) => return,
+    };
     for device in pci_devices {
         // List of all the devices that we support.
         // While there exists a wide range of features that some devices support and some others
@@ -153,11 +157,19 @@ async fn async_main() {
                     // be caught.
                     let next_interrupt = pci_lock.next_interrupt();
 
-                    for packet in unsafe { device.on_interrupt().await } {
+                    // Stop listening to IRQs if the device can't be accessed.
+                    let packets = match unsafe { device.on_interrupt().await } {
+                        Ok(p) => p,
+                        Err(_) => break,
+                    };
+                    for packet in packets {
                         registration.packet_from_network().await.send(packet)
                     }
 
-                    next_interrupt.await;
+                    // Stop listening to IRQs if the PCI interface handler fails to report them.
+                    if next_interrupt.await.is_err() {
+                        break;
+                    }
                 }
             }
             .boxed_local(),
@@ -30,7 +30,8 @@ async fn async_main() -> ! {
     loop {
         let msg = match registration.next_message_raw().await {
             redshirt_interface_interface::DecodedInterfaceOrDestroyed::Interface(m) => m,
-            redshirt_interface_interface::DecodedInterfaceOrDestroyed::ProcessDestroyed(_) => {
+            redshirt_interface_interface::DecodedInterfaceOrDestroyed::ProcessDestroyed(_)
+            | redshirt_interface_interface::DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => {
                 continue
             }
         };
@@ -207,6 +207,8 @@ pub enum ConnectError {
     UnspecifiedDestinationIp,
     #[error("The destination port cannot be 0")]
     UnspecifiedDestinationPort,
+    #[error("The requested local IP address doesn't belong to this interface")]
+    BindAddrNotAvailable,
 }
 
 /// Opaque identifier of a socket within a [`NetInterfaceState`].
@@ -323,10 +325,14 @@ impl<TSockUd> NetInterfaceState<TSockUd> {
 
     /// Initializes a new TCP connection which tries to connect to the given
     /// [`SocketAddr`](std::net::SocketAddr).
+    ///
+    /// If `listen` is false and `bind_addr` is `Some`, the socket uses it as its local address.
+    /// Its IP address, if specified, must be the one of this interface.
     pub fn build_tcp_socket(
         &mut self,
         listen: bool,
         addr: &SocketAddr,
+        bind_addr: Option<&SocketAddr>,
         user_data: TSockUd,
     ) -> Result<TcpSocket<TSockUd>, (ConnectError, TSockUd)> {
         let mut socket = {
@@ -358,12 +364,35 @@ impl<TSockUd> NetInterfaceState<TSockUd> {
                 return Err((ConnectError::UnspecifiedDestinationIp, user_data));
             }
             assert!(!addr.ip().is_multicast()); // TODO: not supported? or is it?
-            let port = match self.tcp_ports_assign.reserve_any(1024) {
-                Some(p) => p,
-                None => return Err((ConnectError::NoPortAvailable, user_data)),
+
+            let bind_ip = match bind_addr {
+                Some(bind_addr) if !bind_addr.ip().is_unspecified() => {
+                    if self.local_ip_prefix().map(|(ip, _)| ip) != Some(bind_addr.ip()) {
+                        return Err((ConnectError::BindAddrNotAvailable, user_data));
+                    }
+                    Some(bind_addr.ip())
+                }
+                _ => None,
+            };
+
+            let port = match bind_addr.map(|a| a.port()).unwrap_or(0) {
+                0 => match self.tcp_ports_assign.reserve_any(1024) {
+                    Some(p) => p,
+                    None => return Err((ConnectError::NoPortAvailable, user_data)),
+                },
+                port => match self.tcp_ports_assign.reserve(port) {
+                    Ok(()) => port,
+                    Err(()) => return Err((ConnectError::PortNotAvailable, user_data)),
+                },
             };
+
             // `connect` can only fail if the socket was misconfigured.
-            socket.connect(addr.clone(), port).unwrap();
+            match bind_ip {
+                Some(ip) => socket
+                    .connect(addr.clone(), SocketAddr::new(ip, port))
+                    .unwrap(),
+                None => socket.connect(addr.clone(), port).unwrap(),
+            }
         }
 
         let id = SocketId(self.sockets.add(socket));
@@ -680,6 +709,21 @@ impl<'a, TSockUd> TcpSocket<'a, TSockUd> {
         Ok(())
     }
 
+    /// Sets the time-to-live (IPv4) or hop limit (IPv6) of the packets sent by this socket.
+    /// `None` restores the default value.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `hop_limit` is `Some(0)`.
+    ///
+    pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
+        let mut socket = self
+            .interface
+            .sockets
+            .get::<smoltcp::socket::TcpSocket<'static>>(self.id.0);
+        socket.set_hop_limit(hop_limit);
+    }
+
     /// Returns true if `close` has successfully been called earlier.
     pub fn close_called(&self) -> bool {
         self.interface
@@ -812,7 +856,9 @@ impl<'a, TSockUd> fmt::Debug for TcpSocket<'a, TSockUd> {
 
 // TODO: remove?
 async fn now() -> smoltcp::time::Instant {
-    let now = redshirt_time_interface::monotonic_clock().await;
+    let now = redshirt_time_interface::monotonic_clock()
+        .await
+        .expect("failed to query the monotonic clock");
     smoltcp::time::Instant::from_millis(i64::try_from(now / 1_000_000).unwrap())
 }
 
@@ -20,11 +20,12 @@ use hashbrown::HashMap;
 use network_manager::{NetworkManager, NetworkManagerEvent};
 use redshirt_ethernet_interface::ffi as eth_ffi;
 use redshirt_interface_interface::DecodedInterfaceOrDestroyed;
-use redshirt_syscalls::{Decode as _, MessageId};
+use redshirt_syscalls::{Decode as _, MessageId, Pid};
 use redshirt_tcp_interface::ffi as tcp_ffi;
 use std::{
     collections::VecDeque,
-    net::{IpAddr, Ipv6Addr, SocketAddr},
+    convert::TryFrom as _,
+    net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6},
 };
 
 fn main() {
@@ -34,6 +35,8 @@ fn main() {
 
 struct SocketState {
     id: u32,
+    /// Process that has emitted the `Open` message.
+    emitter: Pid,
     connected_message: Option<MessageId>,
     read_message: Option<MessageId>,
     write_finished_message: Option<MessageId>,
@@ -59,7 +62,16 @@ async fn async_main() {
             interface_event = eth_registration.next_message_raw().fuse() => {
                 match interface_event {
                     DecodedInterfaceOrDestroyed::Interface(msg) => {
-                        let msg_data = eth_ffi::NetworkMessage::decode(msg.actual_data).unwrap();
+                        let msg_data = match eth_ffi::NetworkMessage::decode(msg.actual_data) {
+                            Ok(m) => m,
+                            Err(_) => {
+                                log::warn!("Bad network message from {:?}", msg.emitter_pid);
+                                if let Some(message_id) = msg.message_id {
+                                    redshirt_interface_interface::emit_message_error(message_id);
+                                }
+                                continue;
+                            }
+                        };
                         match msg_data {
                             eth_ffi::NetworkMessage::RegisterInterface { id, mac_address } => {
                                 network
@@ -97,13 +109,22 @@ async fn async_main() {
                         continue;
                         // TODO: unimplemented
                     }
+                    DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => continue,
                 }
             }
             interface_event = tcp_registration.next_message_raw().fuse() => {
                 match interface_event {
                     DecodedInterfaceOrDestroyed::Interface(msg) => {
-                        // TODO: don't unwrap
-                        let msg_data = tcp_ffi::TcpMessage::decode(msg.actual_data).unwrap();
+                        let msg_data = match tcp_ffi::TcpMessage::decode(msg.actual_data) {
+                            Ok(m) => m,
+                            Err(_) => {
+                                log::warn!("Bad TCP message from {:?}", msg.emitter_pid);
+                                if let Some(message_id) = msg.message_id {
+                                    redshirt_interface_interface::emit_message_error(message_id);
+                                }
+                                continue;
+                            }
+                        };
                         match msg_data {
                             tcp_ffi::TcpMessage::Open(open_msg) => {
                                 let message_id = match msg.message_id {
@@ -114,6 +135,15 @@ async fn async_main() {
                                 let new_id = next_socket_id;
                                 next_socket_id += 1;
 
+                                let bind_addr = open_msg.bind_addr.map(|(ip, port)| {
+                                    let ip_addr = Ipv6Addr::from(ip);
+                                    let ip_addr = match ip_addr.to_ipv4() {
+                                        Some(ip_addr) => IpAddr::from(ip_addr),
+                                        None => IpAddr::from(ip_addr),
+                                    };
+                                    SocketAddr::new(ip_addr, port)
+                                });
+
                                 let inner_id = network
                                     .build_tcp_socket(
                                         open_msg.listen,
@@ -122,11 +152,21 @@ async fn async_main() {
                                             if let Some(ip_addr) = ip_addr.to_ipv4() {
                                                 SocketAddr::new(ip_addr.into(), open_msg.port)
                                             } else {
-                                                SocketAddr::new(ip_addr.into(), open_msg.port)
+                                                // TODO: the scope id isn't taken into account
+                                                // when picking the interface
+                                                SocketAddrV6::new(
+                                                    ip_addr,
+                                                    open_msg.port,
+                                                    0,
+                                                    open_msg.scope_id,
+                                                )
+                                                .into()
                                             }
                                         },
+                                        bind_addr.as_ref(),
                                         SocketState {
                                             id: new_id,
+                                            emitter: msg.emitter_pid,
                                             connected_message: Some(message_id),
                                             read_message: None,
                                             write_finished_message: None,
@@ -277,12 +317,55 @@ async fn async_main() {
                                     socket.reset();
                                 }
                             }
+                            tcp_ffi::TcpMessage::SetOption(set_option) => {
+                                let result = if let Some(inner_id) = sockets.get(&set_option.socket_id) {
+                                    let mut socket = network.tcp_socket_by_id(inner_id).unwrap();
+                                    match set_option.ttl.map(u8::try_from) {
+                                        None => Ok(()),
+                                        Some(Ok(ttl)) if ttl != 0 => {
+                                            socket.set_hop_limit(Some(ttl));
+                                            Ok(())
+                                        }
+                                        Some(_) => Err(tcp_ffi::TcpSetOptionError::InvalidValue),
+                                    }
+                                } else {
+                                    Err(tcp_ffi::TcpSetOptionError::InvalidSocket)
+                                };
+
+                                if let Some(message_id) = msg.message_id {
+                                    redshirt_interface_interface::emit_answer(
+                                        message_id,
+                                        &tcp_ffi::TcpSetOptionResponse { result },
+                                    );
+                                }
+                            }
+                            tcp_ffi::TcpMessage::CancelOpen(open_message_id) => {
+                                // Only the emitter of the `Open` message can cancel it.
+                                let emitter_pid = msg.emitter_pid;
+                                let socket_id = sockets
+                                    .iter()
+                                    .find(|(_, inner_id)| {
+                                        let mut socket = network.tcp_socket_by_id(inner_id).unwrap();
+                                        let state = socket.user_data_mut();
+                                        state.connected_message == Some(open_message_id)
+                                            && state.emitter == emitter_pid
+                                    })
+                                    .map(|(id, _)| *id);
+                                if let Some(inner_id) = socket_id.and_then(|id| sockets.remove(&id)) {
+                                    redshirt_interface_interface::emit_answer(
+                                        open_message_id,
+                                        &tcp_ffi::TcpOpenResponse { result: Err(()) },
+                                    );
+                                    network.tcp_socket_by_id(&inner_id).unwrap().reset();
+                                }
+                            }
                         }
                     },
                     DecodedInterfaceOrDestroyed::ProcessDestroyed(_) => {
                         continue;
                         // TODO: unimplemented
                     }
+                    DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => continue,
                 }
             }
             net_event = network.next_event().fuse() => {
@@ -49,6 +49,10 @@ enum SocketState<TIfId, TSockUd> {
         listen: bool,
         /// Socket address parameter passed to the socket constructor.
         addr: SocketAddr,
+        /// Local address parameter passed to the socket constructor.
+        bind_addr: Option<SocketAddr>,
+        /// Hop limit to apply once the socket is assigned. See [`TcpSocket::set_hop_limit`].
+        hop_limit: Option<u8>,
         /// User data for this socket.
         user_data: TSockUd,
     },
@@ -128,10 +132,13 @@ where
     /// Adds a new TCP socket to the state of the network manager.
     ///
     /// If `listen` is `true`, then `addr` is a local address that the socket will listen on.
+    /// Otherwise, `bind_addr` is the optional local address the socket must be bound to. The
+    /// socket is only assigned to an interface whose IP address matches it.
     pub fn build_tcp_socket(
         &mut self,
         listen: bool,
         addr: &SocketAddr,
+        bind_addr: Option<&SocketAddr>,
         user_data: TSockUd,
     ) -> TcpSocket<TIfId, TIfUser, TSockUd> {
         let socket_id = self.next_socket_id;
@@ -144,6 +151,7 @@ where
             match device.inner.build_tcp_socket(
                 listen,
                 addr,
+                bind_addr,
                 (socket_id, user_data.take().unwrap()),
             ) {
                 Ok(socket) => {
@@ -172,6 +180,8 @@ where
                 listen,
                 user_data: user_data.take().unwrap(),
                 addr: addr.clone(),
+                bind_addr: bind_addr.cloned(),
+                hop_limit: None,
             },
         );
 
@@ -300,12 +310,14 @@ where
                     };
 
                     for (socket_id, socket) in sockets {
-                        let (listen, addr, user_data) = match socket {
+                        let (listen, addr, bind_addr, hop_limit, user_data) = match socket {
                             SocketState::Pending {
                                 listen,
                                 addr,
+                                bind_addr,
+                                hop_limit,
                                 user_data,
-                            } => (listen, addr, user_data),
+                            } => (listen, addr, bind_addr, hop_limit, user_data),
                             s @ SocketState::Assigned { .. } => {
                                 self.sockets.insert(socket_id, s);
                                 continue;
@@ -316,9 +328,11 @@ where
                         match interface.inner.build_tcp_socket(
                             listen,
                             &addr,
+                            bind_addr.as_ref(),
                             (socket_id, user_data),
                         ) {
-                            Ok(inner_socket) => {
+                            Ok(mut inner_socket) => {
+                                inner_socket.set_hop_limit(hop_limit);
                                 self.sockets.insert(
                                     socket_id,
                                     SocketState::Assigned {
@@ -333,6 +347,8 @@ where
                                     SocketState::Pending {
                                         listen,
                                         addr,
+                                        bind_addr,
+                                        hop_limit,
                                         user_data,
                                     },
                                 );
@@ -574,6 +590,36 @@ where
         }
     }
 
+    /// Sets the time-to-live (IPv4) or hop limit (IPv6) of the packets sent by this socket.
+    /// `None` restores the default value.
+    ///
+    /// If the socket hasn't been assigned to an interface yet, the value is applied once it is.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `hop_limit` is `Some(0)`.
+    ///
+    pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
+        assert_ne!(hop_limit, Some(0));
+        match self.parent.sockets.get_mut(&self.id).unwrap() {
+            SocketState::Pending {
+                hop_limit: pending, ..
+            } => *pending = hop_limit,
+            SocketState::Assigned {
+                interface,
+                inner_id,
+            } => self
+                .parent
+                .devices
+                .get_mut(interface)
+                .unwrap()
+                .inner
+                .tcp_socket_by_id(*inner_id)
+                .unwrap()
+                .set_hop_limit(hop_limit),
+        }
+    }
+
     /// Returns true if `close` has successfully been called earlier.
     pub fn close_called(&mut self) -> bool {
         match self.parent.sockets.get(&self.id).unwrap() {
@@ -56,6 +56,9 @@ async fn async_main() {
             future::Either::Left(
                 redshirt_interface_interface::DecodedInterfaceOrDestroyed::ProcessDestroyed(_),
             ) => continue,
+            future::Either::Left(
+                redshirt_interface_interface::DecodedInterfaceOrDestroyed::InterfaceRegistered(_),
+            ) => continue,
             future::Either::Right(NetworkEvent::Readiness(true)) => {
                 if registration.is_none() {
                     registration = redshirt_interface_interface::register_interface(
@@ -85,9 +88,17 @@ async fn async_main() {
 
         assert!(registration.is_some());
         assert_eq!(msg.interface, redshirt_loader_interface::ffi::INTERFACE);
-        let msg_data =
-            redshirt_loader_interface::ffi::LoaderMessage::decode_all(&msg.actual_data.0).unwrap();
-        let redshirt_loader_interface::ffi::LoaderMessage::Load(hash_to_load) = msg_data;
+        let hash_to_load =
+            match redshirt_loader_interface::ffi::LoaderMessage::decode_all(&msg.actual_data.0) {
+                Ok(redshirt_loader_interface::ffi::LoaderMessage::Load(hash)) => hash,
+                Err(_) => {
+                    log::warn!("Bad loader message from {:?}", msg.emitter_pid);
+                    if let Some(message_id) = msg.message_id {
+                        redshirt_interface_interface::emit_message_error(message_id);
+                    }
+                    continue;
+                }
+            };
         log::info!("loading {}", bs58::encode(hash_to_load).into_string());
         network.start_fetch(&hash_to_load, msg.message_id.unwrap());
     }
@@ -0,0 +1,280 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage of values too large to fit in a single DHT record.
+//!
+//! A value larger than the chunk size is split into chunks of equal size, except for the last
+//! one. Chunk number `n` is stored under the key returned by [`chunk_key`], and the record whose
+//! key is the hash of the value contains a [`Manifest`] indicating how to reassemble it.
+//!
+//! Values that fit in a single chunk are stored as they are under their hash. When fetching
+//! a value, the record found under the hash is considered to be the value itself if it matches
+//! the hash, and a manifest otherwise.
+
+use parity_scale_codec::{Decode, Encode};
+use std::convert::TryFrom as _;
+
+/// Bytes found at the start of each encoded manifest.
+const MANIFEST_MAGIC: [u8; 8] = *b"rschunks";
+
+/// Maximum number of chunks a manifest can refer to. Manifests above this limit are considered
+/// invalid, in order to avoid allocating an unreasonable amount of memory.
+pub const MAX_CHUNKS: u32 = 4096;
+
+/// Description of how to reassemble a value that has been split into chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct Manifest {
+    /// Total length of the value, in bytes.
+    pub total_len: u64,
+    /// Length of each chunk, except for the last one that can be shorter.
+    pub chunk_size: u32,
+}
+
+impl Manifest {
+    /// Returns the number of chunks the value has been split into.
+    pub fn num_chunks(&self) -> u32 {
+        let num = (self.total_len + u64::from(self.chunk_size) - 1) / u64::from(self.chunk_size);
+        u32::try_from(num).unwrap_or(u32::max_value())
+    }
+
+    /// Returns the expected length of the chunk with the given index.
+    fn chunk_len(&self, index: u32) -> u64 {
+        let start = u64::from(index) * u64::from(self.chunk_size);
+        (self.total_len - start).min(u64::from(self.chunk_size))
+    }
+
+    fn encode_with_magic(&self) -> Vec<u8> {
+        let mut out = MANIFEST_MAGIC.to_vec();
+        self.encode_to(&mut out);
+        out
+    }
+}
+
+/// Returns the key of the record containing the chunk of the value with the given hash.
+pub fn chunk_key(hash: &[u8; 32], index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(36);
+    key.extend_from_slice(hash);
+    key.extend_from_slice(&index.to_le_bytes());
+    key
+}
+
+/// Parses a key returned by [`chunk_key`]. Returns `None` if it isn't a chunk key.
+pub fn parse_chunk_key(key: &[u8]) -> Option<([u8; 32], u32)> {
+    if key.len() != 36 {
+        return None;
+    }
+
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&key[..32]);
+    let mut index = [0; 4];
+    index.copy_from_slice(&key[32..]);
+    Some((hash, u32::from_le_bytes(index)))
+}
+
+/// Returns the list of records, as keys and values, to store in order to publish `data`,
+/// whose hash is `hash`.
+///
+/// # Panic
+///
+/// Panics if `chunk_size` is 0.
+///
+pub fn split(hash: &[u8; 32], data: Vec<u8>, chunk_size: usize) -> Vec<(Vec<u8>, Vec<u8>)> {
+    assert_ne!(chunk_size, 0);
+
+    if data.len() <= chunk_size {
+        return vec![(hash.to_vec(), data)];
+    }
+
+    let manifest = Manifest {
+        total_len: u64::try_from(data.len()).unwrap(),
+        chunk_size: u32::try_from(chunk_size).unwrap_or(u32::max_value()),
+    };
+
+    let mut records = Vec::with_capacity(usize::try_from(manifest.num_chunks()).unwrap() + 1);
+    records.push((hash.to_vec(), manifest.encode_with_magic()));
+    for (index, chunk) in data
+        .chunks(usize::try_from(manifest.chunk_size).unwrap())
+        .enumerate()
+    {
+        let index = u32::try_from(index).unwrap();
+        records.push((chunk_key(hash, index), chunk.to_vec()));
+    }
+    records
+}
+
+/// Interpretation of the record found under the hash of a value.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RootRecord {
+    /// The record is the value itself.
+    Complete(Vec<u8>),
+    /// The value has been split into chunks that must be fetched separately.
+    Chunked(Reassembly),
+    /// The record neither matches the hash nor is a valid manifest.
+    Invalid,
+}
+
+/// Interprets the record found under `hash`.
+pub fn decode_root(hash: &[u8; 32], value: Vec<u8>) -> RootRecord {
+    if blake3::hash(&value).as_bytes() == hash {
+        return RootRecord::Complete(value);
+    }
+
+    if !value.starts_with(&MANIFEST_MAGIC) {
+        return RootRecord::Invalid;
+    }
+
+    let manifest = match Manifest::decode(&mut &value[MANIFEST_MAGIC.len()..]) {
+        Ok(m) => m,
+        Err(_) => return RootRecord::Invalid,
+    };
+
+    if manifest.chunk_size == 0 || manifest.total_len == 0 || manifest.num_chunks() > MAX_CHUNKS {
+        return RootRecord::Invalid;
+    }
+
+    RootRecord::Chunked(Reassembly {
+        hash: *hash,
+        chunks: vec![None; usize::try_from(manifest.num_chunks()).unwrap()],
+        manifest,
+    })
+}
+
+/// Value being reassembled from its chunks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reassembly {
+    /// Hash of the full value.
+    hash: [u8; 32],
+    /// Manifest found under the hash.
+    manifest: Manifest,
+    /// Chunks received so far.
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl Reassembly {
+    /// Returns the hash of the value being reassembled.
+    pub fn hash(&self) -> &[u8; 32] {
+        &self.hash
+    }
+
+    /// Returns the keys of all the chunks of the value.
+    pub fn chunk_keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        (0..self.manifest.num_chunks()).map(move |index| chunk_key(&self.hash, index))
+    }
+
+    /// Stores a chunk that has been fetched. Returns an error if the index is out of range or if
+    /// the chunk doesn't have the expected length, in which case the value can't be reassembled.
+    pub fn insert(&mut self, index: u32, data: Vec<u8>) -> Result<(), ()> {
+        if index >= self.manifest.num_chunks()
+            || u64::try_from(data.len()).unwrap() != self.manifest.chunk_len(index)
+        {
+            return Err(());
+        }
+
+        self.chunks[usize::try_from(index).unwrap()] = Some(data);
+        Ok(())
+    }
+
+    /// Returns true if all the chunks have been inserted.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(|c| c.is_some())
+    }
+
+    /// Concatenates all the chunks, and verifies that the result matches the hash.
+    ///
+    /// Returns `None` if a chunk is missing or if the hash doesn't match.
+    pub fn finish(self) -> Option<Vec<u8>> {
+        let mut out = Vec::with_capacity(usize::try_from(self.manifest.total_len).ok()?);
+        for chunk in self.chunks {
+            out.extend_from_slice(&chunk?);
+        }
+
+        if blake3::hash(&out).as_bytes() == &self.hash {
+            Some(out)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_root, parse_chunk_key, split, RootRecord};
+
+    #[test]
+    fn small_value_not_split() {
+        let data = vec![5; 100];
+        let hash = *blake3::hash(&data).as_bytes();
+        let records = split(&hash, data.clone(), 100);
+        assert_eq!(records, vec![(hash.to_vec(), data.clone())]);
+        assert_eq!(
+            decode_root(&hash, records[0].1.clone()),
+            RootRecord::Complete(data)
+        );
+    }
+
+    #[test]
+    fn split_and_reassemble() {
+        let data = (0..2500u32).map(|n| n as u8).collect::<Vec<_>>();
+        let hash = *blake3::hash(&data).as_bytes();
+        let mut records = split(&hash, data.clone(), 1000);
+        assert_eq!(records.len(), 4);
+
+        let (root_key, root_value) = records.remove(0);
+        assert_eq!(root_key, hash.to_vec());
+        let mut reassembly = match decode_root(&hash, root_value) {
+            RootRecord::Chunked(r) => r,
+            _ => panic!(),
+        };
+
+        let keys = reassembly.chunk_keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            records.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()
+        );
+
+        for (key, value) in records.into_iter().rev() {
+            assert!(!reassembly.is_complete());
+            let (chunk_hash, index) = parse_chunk_key(&key).unwrap();
+            assert_eq!(chunk_hash, hash);
+            reassembly.insert(index, value).unwrap();
+        }
+
+        assert!(reassembly.is_complete());
+        assert_eq!(reassembly.finish().unwrap(), data);
+    }
+
+    #[test]
+    fn corrupted_chunk_detected() {
+        let data = vec![7; 2000];
+        let hash = *blake3::hash(&data).as_bytes();
+        let mut records = split(&hash, data, 1000);
+
+        let mut reassembly = match decode_root(&hash, records.remove(0).1) {
+            RootRecord::Chunked(r) => r,
+            _ => panic!(),
+        };
+        assert!(reassembly.insert(0, vec![7; 999]).is_err());
+        assert!(reassembly.insert(2, vec![7; 1000]).is_err());
+        reassembly.insert(0, vec![7; 1000]).unwrap();
+        reassembly.insert(1, vec![8; 1000]).unwrap();
+        assert!(reassembly.finish().is_none());
+    }
+
+    #[test]
+    fn invalid_root_record() {
+        let hash = *blake3::hash(b"hello").as_bytes();
+        assert_eq!(decode_root(&hash, b"world".to_vec()), RootRecord::Invalid);
+    }
+}
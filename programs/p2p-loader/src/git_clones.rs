@@ -19,7 +19,49 @@ use std::{error, path::Path};
 pub fn clone_git_repos(
     git_urls: impl IntoIterator<Item = impl AsRef<str>>,
 ) -> Result<GitClones, Box<dyn error::Error + Send + Sync>> {
-    clone_git_repos_inner(git_urls)
+    #[cfg(feature = "git")]
+    let backend = Git2Backend {
+        repositories: Vec::new(),
+    };
+    #[cfg(not(feature = "git"))]
+    let backend = DisabledBackend;
+
+    clone_git_repos_with(backend, git_urls)
+}
+
+/// Same as [`clone_git_repos`], but uses the given [`GitBackend`] to clone the repositories.
+pub fn clone_git_repos_with(
+    mut backend: impl GitBackend,
+    git_urls: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<GitClones, Box<dyn error::Error + Send + Sync>> {
+    let mut dirs = Vec::new();
+
+    for git_url in git_urls {
+        let target_dir = tempdir::TempDir::new("redshirt")?;
+        backend.clone_repo(git_url.as_ref(), target_dir.path())?;
+        dirs.push(target_dir);
+    }
+
+    backend.start_updates();
+    Ok(GitClones { paths: dirs })
+}
+
+/// Way to obtain local copies of git repositories.
+pub trait GitBackend {
+    /// Clones the repository found at `url` into the empty directory `target`.
+    fn clone_repo(
+        &mut self,
+        url: &str,
+        target: &Path,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>>;
+
+    /// Called once all the repositories have been cloned. Can be used to keep the clones up to
+    /// date in the background.
+    fn start_updates(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 /// Holds a list of clones repositories.
@@ -36,46 +78,104 @@ impl GitClones {
     }
 }
 
+/// [`GitBackend`] that uses `libgit2`, and fetches the repositories every minute.
 #[cfg(feature = "git")]
-fn clone_git_repos_inner(
-    git_urls: impl IntoIterator<Item = impl AsRef<str>>,
-) -> Result<GitClones, Box<dyn error::Error + Send + Sync>> {
-    let mut dirs = Vec::new();
-    let mut repositories = Vec::new();
+struct Git2Backend {
+    repositories: Vec<git2::Repository>,
+}
 
-    for git_url in git_urls {
-        let target_dir = tempdir::TempDir::new("redshirt")?;
-        let repo = git2::Repository::clone(git_url.as_ref(), target_dir.path())?;
-        dirs.push(target_dir);
-        repositories.push(repo);
+#[cfg(feature = "git")]
+impl GitBackend for Git2Backend {
+    fn clone_repo(
+        &mut self,
+        url: &str,
+        target: &Path,
+    ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let repo = git2::Repository::clone(url, target)?;
+        self.repositories.push(repo);
+        Ok(())
     }
 
-    // Spawns a thread that updates git repos every minute.
-    // TODO: have some way to shut down this thread
-    std::thread::spawn(move || loop {
-        std::thread::sleep(std::time::Duration::from_secs(60));
-
-        for repo in &mut repositories {
-            repo.find_remote("origin")
-                .unwrap()
-                .fetch(&["master"], None, None)
-                .unwrap();
-            repo.set_head("FETCH_HEAD").unwrap();
-            repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-                .unwrap();
-        }
-    });
+    fn start_updates(mut self) {
+        // Spawns a thread that updates git repos every minute.
+        // TODO: have some way to shut down this thread
+        std::thread::spawn(move || loop {
+            std::thread::sleep(std::time::Duration::from_secs(60));
 
-    Ok(GitClones { paths: dirs })
+            for repo in &mut self.repositories {
+                repo.find_remote("origin")
+                    .unwrap()
+                    .fetch(&["master"], None, None)
+                    .unwrap();
+                repo.set_head("FETCH_HEAD").unwrap();
+                repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
+                    .unwrap();
+            }
+        });
+    }
 }
 
+/// [`GitBackend`] used when the `git` feature is disabled. Panics if any repository is passed.
 #[cfg(not(feature = "git"))]
-fn clone_git_repos_inner(
-    iter: impl IntoIterator<Item = impl AsRef<str>>,
-) -> Result<GitClones, Box<dyn error::Error + Send + Sync>> {
-    let mut iter = iter.into_iter();
-    if iter.next().is_some() {
+struct DisabledBackend;
+
+#[cfg(not(feature = "git"))]
+impl GitBackend for DisabledBackend {
+    fn clone_repo(&mut self, _: &str, _: &Path) -> Result<(), Box<dyn error::Error + Send + Sync>> {
         panic!("The git feature is not enabled")
     }
-    Ok(GitClones { paths: Vec::new() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clone_git_repos, clone_git_repos_with, GitBackend};
+    use std::{collections::HashMap, error, fs, path::Path};
+
+    /// Backend whose repositories consist of files held in memory.
+    struct InMemory(HashMap<&'static str, Vec<(&'static str, &'static [u8])>>);
+
+    impl GitBackend for InMemory {
+        fn clone_repo(
+            &mut self,
+            url: &str,
+            target: &Path,
+        ) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+            let files = self.0.get(url).ok_or("unknown repository")?;
+            for (name, content) in files {
+                fs::write(target.join(name), content)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn clones_into_watchable_paths() {
+        let mut repos = HashMap::new();
+        repos.insert("foo", vec![("a.wasm", &b"hello"[..])]);
+        repos.insert("bar", vec![("b.wasm", &b"world"[..])]);
+
+        let clones = clone_git_repos_with(InMemory(repos), ["foo", "bar"]).unwrap();
+        let paths = clones.paths().collect::<Vec<_>>();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(fs::read(paths[0].join("a.wasm")).unwrap(), b"hello");
+        assert_eq!(fs::read(paths[1].join("b.wasm")).unwrap(), b"world");
+    }
+
+    #[test]
+    fn clone_error_reported() {
+        assert!(clone_git_repos_with(InMemory(HashMap::new()), ["foo"]).is_err());
+    }
+
+    #[test]
+    fn no_repository_never_panics() {
+        let clones = clone_git_repos(Vec::<String>::new()).unwrap();
+        assert_eq!(clones.paths().len(), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "git"))]
+    #[should_panic]
+    fn panics_without_git_feature() {
+        let _ = clone_git_repos(["foo"]);
+    }
 }
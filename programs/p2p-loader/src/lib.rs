@@ -22,16 +22,25 @@ use tcp_transport::TcpConfig;
 
 use futures::prelude::*;
 use libp2p::core::transport::Transport;
-use libp2p::core::{identity, muxing::StreamMuxerBox, upgrade};
+use libp2p::core::{identity, muxing::StreamMuxerBox, upgrade, PeerId};
 use libp2p::kad::{
     record::store::{MemoryStore, MemoryStoreConfig},
     record::Key,
-    Kademlia, KademliaConfig, KademliaEvent, QueryResult, Quorum,
+    Kademlia, KademliaConfig, KademliaEvent, QueryId, QueryResult, Quorum,
 };
 use libp2p::swarm::{Swarm, SwarmEvent};
 use libp2p::yamux;
-use std::{collections::VecDeque, io, path::PathBuf, pin::Pin, time::Duration};
+use std::{
+    cmp,
+    collections::{HashSet, VecDeque},
+    convert::TryFrom as _,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    time::Duration,
+};
 
+mod chunks;
 mod git_clones;
 mod notifier;
 
@@ -43,21 +52,43 @@ pub struct Network<T> {
     /// Stream from the files watcher.
     notifications: stream::SelectAll<Pin<Box<dyn Stream<Item = notifier::NotifierEvent> + Send>>>,
 
-    /// True if we are connected to any node and have reported it through a
+    /// True if we are connected to enough nodes and have reported it through a
     /// [`NetworkEvent::Readiness`].
-    // TODO: never set to false
     connected_to_network: bool,
 
+    /// List of peers we have at least one connection with.
+    connected_peers: HashSet<PeerId>,
+
+    /// See [`NetworkConfig::min_peers_for_ready`].
+    min_peers_for_ready: usize,
+
     /// Holds active git clones.
     _git_clones_directories: git_clones::GitClones,
 
-    /// List of keys that are currently being fetched.
-    active_fetches: Vec<(Key, T)>,
+    /// See [`NetworkConfig::value_chunk_size`].
+    value_chunk_size: usize,
+
+    /// List of keys that are currently being fetched, with the query that has been started for
+    /// each of them.
+    active_fetches: Vec<(Key, QueryId, T)>,
+
+    /// List of values whose manifest has been fetched and whose chunks are being fetched.
+    chunked_fetches: Vec<ChunkedFetch<T>>,
 
     /// Queue of events to return to the user.
     events_queue: VecDeque<NetworkEvent<T>>,
 }
 
+/// Value split into chunks whose chunks are being fetched.
+struct ChunkedFetch<T> {
+    /// Chunks fetched so far.
+    reassembly: chunks::Reassembly,
+    /// Queries started in order to fetch the chunks.
+    queries: Vec<QueryId>,
+    /// User datas passed to [`Network::start_fetch`] for this value.
+    user_datas: Vec<T>,
+}
+
 /// Event that can happen in a [`Network`].
 // TODO: better Debug impl? `data` might be huge
 #[derive(Debug)]
@@ -65,12 +96,14 @@ pub enum NetworkEvent<T> {
     /// If true, indicates that we're now connected to the peer-to-peer network. If false,
     /// indicates that we're not.
     ///
+    /// We are considered connected if we have connections with at least
+    /// [`NetworkConfig::min_peers_for_ready`] peers.
+    ///
     /// The [`Network`] starts in a "not ready" state, and this event indicates a switch in
     /// readiness.
     ///
     /// Not being ready has no incidence on how the API is allowed to be used, but queries will
     /// fail unless they hit the local cache.
-    // TODO: nothing ever reports false
     Readiness(bool),
 
     /// Successfully fetched a resource.
@@ -107,6 +140,58 @@ pub struct NetworkConfig {
     /// If `#[cfg(feature = "git")]` isn't enabled, passing a non-empty list will panic at
     /// initialization.
     pub watched_git_repositories: Vec<String>,
+
+    /// Maximum number of records that can be stored locally, including both the records we
+    /// publish and the records other nodes store on us. Defaults to 256.
+    ///
+    /// Files found in [`NetworkConfig::watched_directories`] or
+    /// [`NetworkConfig::watched_git_repositories`] past this limit are not pushed to the DHT.
+    pub max_records: usize,
+
+    /// Maximum size, in bytes, of the value of a record. Defaults to 10 MiB.
+    ///
+    /// Values larger than 10 MiB can't be transferred over the network at the moment, even if
+    /// this limit is raised.
+    pub max_record_value_bytes: usize,
+
+    /// Files larger than this size, in bytes, are split into multiple records before being
+    /// pushed to the DHT. Defaults to 4 MiB. A value of 0 is treated the same way as 1.
+    ///
+    /// Must be lower than [`NetworkConfig::max_record_value_bytes`]. Each chunk counts towards
+    /// [`NetworkConfig::max_records`].
+    pub value_chunk_size: usize,
+
+    /// Minimum number of peers we must be connected to before reporting a
+    /// [`NetworkEvent::Readiness`] of `true`. Readiness switches back to `false` if the number
+    /// of connected peers goes below this threshold. Defaults to 1.
+    ///
+    /// A value of 0 is treated the same way as 1.
+    pub min_peers_for_ready: usize,
+
+    /// Duration after which records stored in the DHT expire, or `None` for records to never
+    /// expire. Defaults to 36 hours.
+    ///
+    /// Should be larger than [`NetworkConfig::record_publication_interval`], otherwise records
+    /// expire before being published again.
+    pub record_ttl: Option<Duration>,
+
+    /// Interval at which the records that this node publishes are published again on the
+    /// network, or `None` to never republish them. Defaults to 24 hours.
+    ///
+    /// Lower values make the records available faster after nodes leave the network, at the cost
+    /// of more traffic.
+    pub record_publication_interval: Option<Duration>,
+
+    /// Interval at which the records stored locally are replicated to the closest nodes, or
+    /// `None` to never replicate them. Defaults to one hour.
+    pub record_replication_interval: Option<Duration>,
+
+    /// Maximum duration added to [`NetworkConfig::record_publication_interval`] and
+    /// [`NetworkConfig::record_replication_interval`]. Defaults to 10 minutes.
+    ///
+    /// The actual duration added is derived from the identity of the node. This avoids nodes
+    /// that start at the same time from also republishing their records at the same time.
+    pub republication_jitter: Duration,
 }
 
 impl<T> Network<T> {
@@ -159,20 +244,40 @@ impl<T> Network<T> {
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
             .boxed();
 
+        // Derive the jitter from the identity of the node, in order for it to be different for
+        // each node.
+        let jitter_seed = {
+            let hash = blake3::hash(local_peer_id.as_bytes());
+            let mut seed = [0; 16];
+            seed.copy_from_slice(&hash.as_bytes()[..16]);
+            u128::from_le_bytes(seed)
+        };
+        let republication_jitter = config.republication_jitter;
+        let add_jitter = |interval: Duration| {
+            let jitter_nanos = jitter_seed % (republication_jitter.as_nanos() + 1);
+            let jitter =
+                Duration::from_nanos(u64::try_from(jitter_nanos).unwrap_or(u64::max_value()));
+            interval.checked_add(jitter).unwrap_or(interval)
+        };
+
         let kademlia = Kademlia::with_config(
             local_peer_id.clone(),
             MemoryStore::with_config(
                 local_peer_id.clone(),
                 MemoryStoreConfig {
-                    // TODO: that's a max of 2GB; we should instead be writing this on disk
-                    max_value_bytes: 10 * 1024 * 1024,
-                    max_records: 256,
+                    // TODO: that's a max of 2.5GB with the default values; we should instead be
+                    // writing this on disk
+                    max_value_bytes: config.max_record_value_bytes,
+                    max_records: config.max_records,
                     ..Default::default()
                 },
             ),
             {
                 let mut cfg = KademliaConfig::default();
                 cfg.set_max_packet_size(10 * 1024 * 1024);
+                cfg.set_record_ttl(config.record_ttl);
+                cfg.set_publication_interval(config.record_publication_interval.map(add_jitter));
+                cfg.set_replication_interval(config.record_replication_interval.map(add_jitter));
                 cfg
             },
         );
@@ -209,19 +314,88 @@ impl<T> Network<T> {
             swarm,
             notifications,
             connected_to_network: false,
+            connected_peers: HashSet::new(),
+            min_peers_for_ready: cmp::max(config.min_peers_for_ready, 1),
             _git_clones_directories: git_clones_directories,
+            value_chunk_size: cmp::max(config.value_chunk_size, 1),
             active_fetches: Vec::new(),
+            chunked_fetches: Vec::new(),
             events_queue: VecDeque::new(),
         })
     }
 
     /// Starts fetching from the network the value corresponding to the given hash.
     ///
+    /// If the value has been split into chunks, all the chunks are fetched and reassembled. The
+    /// fetch only succeeds if the value matches the hash.
+    ///
     /// The `user_data` is an opaque value that is passed back when the fetch succeeds or fails.
     pub fn start_fetch(&mut self, hash: &[u8; 32], user_data: T) {
         let key = Key::new(hash);
-        self.swarm.get_record(&key, Quorum::One); // TODO: use Majority when network is large enough
-        self.active_fetches.push((key, user_data));
+        // TODO: use Majority when network is large enough
+        let query_id = self.swarm.get_record(&key, Quorum::One);
+        self.active_fetches.push((key, query_id, user_data));
+    }
+
+    /// Cancels all the fetches whose user data matches the given predicate, and returns their
+    /// user data. No [`NetworkEvent::FetchSuccess`] or [`NetworkEvent::FetchFail`] will be
+    /// generated for them.
+    ///
+    /// The underlying DHT queries are stopped, except for the chunks of a value that another
+    /// fetch is still waiting for.
+    pub fn cancel_fetch(&mut self, mut user_data_predicate: impl FnMut(&T) -> bool) -> Vec<T> {
+        let mut cancelled = Vec::new();
+        let mut cancelled_queries = Vec::new();
+
+        let mut n = 0;
+        while n < self.active_fetches.len() {
+            if user_data_predicate(&self.active_fetches[n].2) {
+                let (_, query_id, user_data) = self.active_fetches.remove(n);
+                cancelled.push(user_data);
+                cancelled_queries.push(query_id);
+            } else {
+                n += 1;
+            }
+        }
+
+        let mut n = 0;
+        while n < self.chunked_fetches.len() {
+            let fetch = &mut self.chunked_fetches[n];
+            let mut m = 0;
+            while m < fetch.user_datas.len() {
+                if user_data_predicate(&fetch.user_datas[m]) {
+                    cancelled.push(fetch.user_datas.remove(m));
+                } else {
+                    m += 1;
+                }
+            }
+
+            if fetch.user_datas.is_empty() {
+                let fetch = self.chunked_fetches.remove(n);
+                self.finish_queries(fetch.queries);
+            } else {
+                n += 1;
+            }
+        }
+
+        self.finish_queries(cancelled_queries);
+        cancelled
+    }
+
+    /// Returns the list of peers we are currently connected to.
+    pub fn connected_peers(&self) -> Vec<PeerId> {
+        self.connected_peers.iter().cloned().collect()
+    }
+
+    /// Returns the number of peers in the Kademlia routing table.
+    ///
+    /// Contrary to [`Network::connected_peers`], this includes peers we know about but aren't
+    /// necessarily connected to.
+    pub fn kbucket_size(&mut self) -> usize {
+        self.swarm
+            .kbuckets()
+            .map(|bucket| bucket.num_entries())
+            .sum()
     }
 
     /// Returns a future that returns the next event that happens on the network.
@@ -243,42 +417,29 @@ impl<T> Network<T> {
 
             match next_event {
                 future::Either::Left(SwarmEvent::Behaviour(KademliaEvent::QueryResult {
+                    id,
                     result: QueryResult::GetRecord(Ok(result)),
                     ..
                 })) => {
-                    for record in result.records {
-                        log::debug!(
-                            "Successfully loaded record from DHT: {:?}",
-                            record.record.key
-                        );
-                        while let Some(pos) = self
-                            .active_fetches
-                            .iter()
-                            .position(|(key, _)| *key == record.record.key)
-                        {
-                            let user_data = self.active_fetches.remove(pos).1;
-                            self.events_queue.push_back(NetworkEvent::FetchSuccess {
-                                data: record.record.value.clone(),
-                                user_data,
-                            });
+                    // With a quorum of one, there is only ever one record.
+                    match result.records.into_iter().next() {
+                        Some(record) => {
+                            log::debug!(
+                                "Successfully loaded record from DHT: {:?}",
+                                record.record.key
+                            );
+                            self.inject_record(id, record.record.key, record.record.value);
                         }
+                        None => self.fail_query(id),
                     }
                 }
                 future::Either::Left(SwarmEvent::Behaviour(KademliaEvent::QueryResult {
+                    id,
                     result: QueryResult::GetRecord(Err(err)),
                     ..
                 })) => {
                     log::info!("Failed to get record: {:?}", err);
-                    let fetch_failed_key = err.into_key();
-                    while let Some(pos) = self
-                        .active_fetches
-                        .iter()
-                        .position(|(key, _)| *key == fetch_failed_key)
-                    {
-                        let user_data = self.active_fetches.remove(pos).1;
-                        self.events_queue
-                            .push_back(NetworkEvent::FetchFail { user_data });
-                    }
+                    self.fail_query(id);
                 }
                 future::Either::Left(SwarmEvent::Behaviour(KademliaEvent::QueryResult {
                     result: QueryResult::Bootstrap(_),
@@ -287,15 +448,27 @@ impl<T> Network<T> {
                 future::Either::Left(SwarmEvent::Behaviour(ev)) => {
                     log::info!("Other event: {:?}", ev)
                 }
-                future::Either::Left(SwarmEvent::ConnectionEstablished { peer_id, .. }) => {
+                future::Either::Left(SwarmEvent::ConnectionEstablished {
+                    peer_id,
+                    num_established,
+                    ..
+                }) => {
                     log::trace!("Connected to {:?}", peer_id);
-                    if !self.connected_to_network {
-                        self.connected_to_network = true;
-                        self.events_queue.push_back(NetworkEvent::Readiness(true));
+                    if num_established.get() == 1 {
+                        self.connected_peers.insert(peer_id);
+                        self.update_readiness();
                     }
                 }
-                future::Either::Left(SwarmEvent::ConnectionClosed { peer_id, .. }) => {
-                    log::trace!("Disconnected from {:?}", peer_id)
+                future::Either::Left(SwarmEvent::ConnectionClosed {
+                    peer_id,
+                    num_established,
+                    ..
+                }) => {
+                    log::trace!("Disconnected from {:?}", peer_id);
+                    if num_established == 0 {
+                        self.connected_peers.remove(&peer_id);
+                        self.update_readiness();
+                    }
                 }
                 future::Either::Left(SwarmEvent::NewListenAddr(_)) => {}
                 future::Either::Left(SwarmEvent::ExpiredListenAddr(_)) => {}
@@ -309,21 +482,222 @@ impl<T> Network<T> {
                 future::Either::Left(SwarmEvent::ListenerClosed { reason, .. }) => {
                     log::warn!("Listener closed: {:?}", reason);
                 }
-                future::Either::Right(Some(notifier::NotifierEvent::InjectDht { hash, data })) => {
-                    // TODO: use Quorum::Majority when network is large enough
-                    // This stores the record in the local storage. Republication on the DHT
-                    // is then automatically handled by `libp2p-kad`.
-                    self.swarm
-                        .put_record(
-                            libp2p::kad::Record::new(hash.to_vec(), data),
+                future::Either::Right(Some(notifier::NotifierEvent::InjectDht {
+                    hash,
+                    data,
+                    stored,
+                })) => {
+                    let mut success = true;
+                    for (key, value) in chunks::split(&hash, data, self.value_chunk_size) {
+                        // TODO: use Quorum::Majority when network is large enough
+                        // This stores the record in the local storage. Republication on the DHT
+                        // is then automatically handled by `libp2p-kad`.
+                        let result = self.swarm.put_record(
+                            libp2p::kad::Record::new(key, value),
                             libp2p::kad::Quorum::One,
-                        )
-                        .unwrap();
+                        );
+
+                        // Failing to store a record isn't fatal, as the other records continue
+                        // to be served.
+                        if let Err(err) = result {
+                            log::error!(
+                                "Failed to push {} to the DHT: {:?}",
+                                bs58::encode(&hash).into_string(),
+                                err
+                            );
+                            success = false;
+                        }
+                    }
+
+                    // The notifier might have shut down in the meantime.
+                    let _ = stored.send(if success { Ok(()) } else { Err(()) });
                 }
                 future::Either::Right(None) => panic!(),
             }
         }
     }
+
+    /// Processes a record that has been fetched from the DHT by the given query, which is either
+    /// the record found under the hash passed to [`Network::start_fetch`], or a chunk of a value.
+    ///
+    /// Records fetched by queries that don't belong to any fetch, for example because the fetch
+    /// has been cancelled, are ignored.
+    fn inject_record(&mut self, query_id: QueryId, key: Key, value: Vec<u8>) {
+        if let Some(pos) = self
+            .active_fetches
+            .iter()
+            .position(|(_, id, _)| *id == query_id)
+        {
+            let (fetch_key, _, user_data) = self.active_fetches.remove(pos);
+            if key != fetch_key {
+                log::warn!("Received a record for a different key: {:?}", key);
+                self.events_queue
+                    .push_back(NetworkEvent::FetchFail { user_data });
+                return;
+            }
+
+            // Keys passed to `get_record` in `start_fetch` are always hashes.
+            let hash = <[u8; 32]>::try_from(key.as_ref()).unwrap();
+            match chunks::decode_root(&hash, value) {
+                chunks::RootRecord::Complete(data) => {
+                    self.events_queue
+                        .push_back(NetworkEvent::FetchSuccess { data, user_data });
+                }
+                chunks::RootRecord::Chunked(reassembly) => {
+                    // The chunks might already be being fetched for an earlier fetch of the same
+                    // value.
+                    if let Some(fetch) = self
+                        .chunked_fetches
+                        .iter_mut()
+                        .find(|f| f.reassembly.hash() == &hash)
+                    {
+                        fetch.user_datas.push(user_data);
+                        return;
+                    }
+
+                    let queries = reassembly
+                        .chunk_keys()
+                        .map(|chunk_key| self.swarm.get_record(&Key::new(&chunk_key), Quorum::One))
+                        .collect();
+                    self.chunked_fetches.push(ChunkedFetch {
+                        reassembly,
+                        queries,
+                        user_datas: vec![user_data],
+                    });
+                }
+                chunks::RootRecord::Invalid => {
+                    log::warn!(
+                        "Record for {} doesn't match its hash",
+                        bs58::encode(&hash).into_string()
+                    );
+                    self.events_queue
+                        .push_back(NetworkEvent::FetchFail { user_data });
+                }
+            }
+            return;
+        }
+
+        let pos = match self
+            .chunked_fetches
+            .iter()
+            .position(|f| f.queries.contains(&query_id))
+        {
+            Some(p) => p,
+            None => return,
+        };
+        self.chunked_fetches[pos]
+            .queries
+            .retain(|id| *id != query_id);
+
+        let hash = *self.chunked_fetches[pos].reassembly.hash();
+        let index = match chunks::parse_chunk_key(key.as_ref()) {
+            Some((chunk_hash, index)) if chunk_hash == hash => index,
+            _ => {
+                log::warn!(
+                    "Received a record for a different key while fetching chunks of {}",
+                    bs58::encode(&hash).into_string()
+                );
+                self.fail_chunked_fetch(pos);
+                return;
+            }
+        };
+
+        if self.chunked_fetches[pos]
+            .reassembly
+            .insert(index, value)
+            .is_err()
+        {
+            log::warn!(
+                "Invalid chunk {} for {}",
+                index,
+                bs58::encode(&hash).into_string()
+            );
+            self.fail_chunked_fetch(pos);
+            return;
+        }
+
+        if !self.chunked_fetches[pos].reassembly.is_complete() {
+            return;
+        }
+
+        let fetch = self.chunked_fetches.remove(pos);
+        match fetch.reassembly.finish() {
+            Some(data) => {
+                for user_data in fetch.user_datas {
+                    self.events_queue.push_back(NetworkEvent::FetchSuccess {
+                        data: data.clone(),
+                        user_data,
+                    });
+                }
+            }
+            None => {
+                log::warn!(
+                    "Reassembled value doesn't match {}",
+                    bs58::encode(&hash).into_string()
+                );
+                for user_data in fetch.user_datas {
+                    self.events_queue
+                        .push_back(NetworkEvent::FetchFail { user_data });
+                }
+            }
+        }
+    }
+
+    /// Reports the fetch that has started the given query as failed. If the query was fetching
+    /// a chunk, all the fetches of the value are reported as failed.
+    ///
+    /// Queries that don't belong to any fetch, for example because the fetch has been cancelled,
+    /// are ignored.
+    fn fail_query(&mut self, query_id: QueryId) {
+        if let Some(pos) = self
+            .active_fetches
+            .iter()
+            .position(|(_, id, _)| *id == query_id)
+        {
+            let user_data = self.active_fetches.remove(pos).2;
+            self.events_queue
+                .push_back(NetworkEvent::FetchFail { user_data });
+        } else if let Some(pos) = self
+            .chunked_fetches
+            .iter()
+            .position(|f| f.queries.contains(&query_id))
+        {
+            self.fail_chunked_fetch(pos);
+        }
+    }
+
+    /// Reports all the fetches of the chunked value at the given position in `chunked_fetches`
+    /// as failed, and stops fetching its chunks.
+    fn fail_chunked_fetch(&mut self, pos: usize) {
+        let fetch = self.chunked_fetches.remove(pos);
+        self.finish_queries(fetch.queries);
+        for user_data in fetch.user_datas {
+            self.events_queue
+                .push_back(NetworkEvent::FetchFail { user_data });
+        }
+    }
+
+    /// Stops the given DHT queries, if they're still running.
+    fn finish_queries(&mut self, queries: impl IntoIterator<Item = QueryId>) {
+        for query_id in queries {
+            // Finishing a query early still generates a `QueryResult` event. Its `QueryId` no
+            // longer belongs to any fetch, and the event is ignored.
+            if let Some(mut query) = self.swarm.query_mut(&query_id) {
+                query.finish();
+            }
+        }
+    }
+
+    /// Compares the number of connected peers with the readiness threshold, and pushes a
+    /// [`NetworkEvent::Readiness`] to the events queue if the readiness has changed.
+    fn update_readiness(&mut self) {
+        let now_ready = self.connected_peers.len() >= self.min_peers_for_ready;
+        if now_ready != self.connected_to_network {
+            self.connected_to_network = now_ready;
+            self.events_queue
+                .push_back(NetworkEvent::Readiness(now_ready));
+        }
+    }
 }
 
 impl Default for NetworkConfig {
@@ -332,6 +706,14 @@ impl Default for NetworkConfig {
             private_key: None,
             watched_directories: Vec::new(),
             watched_git_repositories: Vec::new(),
+            max_records: 256,
+            max_record_value_bytes: 10 * 1024 * 1024,
+            value_chunk_size: 4 * 1024 * 1024,
+            min_peers_for_ready: 1,
+            record_ttl: Some(Duration::from_secs(36 * 60 * 60)),
+            record_publication_interval: Some(Duration::from_secs(24 * 60 * 60)),
+            record_replication_interval: Some(Duration::from_secs(60 * 60)),
+            republication_jitter: Duration::from_secs(10 * 60),
         }
     }
 }
@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use futures::prelude::*;
+use futures::{channel::oneshot, prelude::*};
 use std::{fs, io, path::Path, time::Duration};
 use walkdir::WalkDir;
 
@@ -26,6 +26,10 @@ pub enum NotifierEvent {
         hash: [u8; 32],
         /// Data to insert.
         data: Vec<u8>,
+        /// Must be sent `Ok` once the data has been stored, or `Err` if storing it has failed.
+        /// Dropping the sender is equivalent to sending `Err`. The data will then be published
+        /// again the next time the file is modified.
+        stored: oneshot::Sender<Result<(), ()>>,
     }, // TODO: more event? remove event?
 }
 
@@ -41,6 +45,7 @@ fn start_notifier_inner(
     path: impl AsRef<Path>,
 ) -> Result<impl Stream<Item = NotifierEvent>, io::Error> {
     use notify::Watcher as _;
+    use std::{collections::HashMap, path::PathBuf};
 
     let path = path.as_ref().to_owned();
 
@@ -48,7 +53,9 @@ fn start_notifier_inner(
     tx.send(notify::DebouncedEvent::Rescan).unwrap();
     let (mut async_tx, async_rx) = futures::channel::mpsc::channel(2);
 
-    let mut watcher = notify::watcher(tx, Duration::from_secs(5))
+    // Editors often write a file in multiple steps. The watcher reports a path only once no
+    // change has happened to it for this duration, so that only the final content is published.
+    let mut watcher = notify::watcher(tx, Duration::from_millis(200))
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
     watcher
         .watch(&path, notify::RecursiveMode::Recursive)
@@ -61,19 +68,25 @@ fn start_notifier_inner(
                 // Make sure that the watcher is kept alive inside the thread.
                 let _watcher = watcher;
 
+                // Hash of the content that has last been published for each file.
+                let mut published = HashMap::<PathBuf, blake3::Hash>::new();
+
                 loop {
                     let files_to_try = match rx.recv().unwrap() {
                         notify::DebouncedEvent::Write(path)
-                        | notify::DebouncedEvent::Create(path) => vec![path],
+                        | notify::DebouncedEvent::Create(path) => list_files(&path),
+                        notify::DebouncedEvent::Rename(from, to) => {
+                            // `from` and `to` can also be directories.
+                            published.retain(|p, _| !p.starts_with(&from));
+                            list_files(&to)
+                        }
+                        notify::DebouncedEvent::Remove(path) => {
+                            published.retain(|p, _| !p.starts_with(&path));
+                            continue;
+                        }
                         notify::DebouncedEvent::Rescan => {
-                            let mut files = Vec::new();
-                            for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
-                                let path = entry.path();
-                                if !path.is_file() {
-                                    continue;
-                                }
-                                files.push(path.to_owned());
-                            }
+                            let files = list_files(&path);
+                            published.retain(|p, _| files.contains(p));
                             files
                         }
                         notify::DebouncedEvent::Error(err, path) => {
@@ -97,21 +110,31 @@ fn start_notifier_inner(
                         }
 
                         let hash = blake3::hash(&data);
+                        if published.get(&path) == Some(&hash) {
+                            continue;
+                        }
+
                         log::info!(
                             "File {:?} has hash {:?}",
                             path,
                             bs58::encode(hash.as_bytes()).into_string()
                         );
+                        let (stored_tx, stored_rx) = oneshot::channel();
                         if async_tx
                             .send(NotifierEvent::InjectDht {
                                 hash: *hash.as_bytes(),
                                 data,
+                                stored: stored_tx,
                             })
                             .await
                             .is_err()
                         {
                             break;
                         }
+
+                        if let Ok(Ok(())) = stored_rx.await {
+                            published.insert(path, hash);
+                        }
                     }
                 }
             })
@@ -127,6 +150,17 @@ fn start_notifier_inner(
     panic!("The notify feature is not enabled")
 }
 
+/// Returns the list of files found at the given path, which is either a file or a directory.
+#[cfg(feature = "notify")]
+fn list_files(path: &Path) -> Vec<std::path::PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.path().to_owned())
+        .collect()
+}
+
 /// Returns true if the given file content can potentially be a Wasm file.
 ///
 /// In other words: returns false if we are sure that this isn't a Wasm file, and true
@@ -28,7 +28,13 @@ fn main() {
 }
 
 async fn async_main() {
-    let devices = redshirt_pci_interface::get_pci_devices().await;
+    let devices = match redshirt_pci_interface::get_pci_devices().await {
+        Ok(d) => d,
+        Err(()) => {
+            log::error!("Failed to retrieve the list of PCI devices");
+            return;
+        }
+    };
 
     for device in devices {
         let (vendor_name, device_name) =
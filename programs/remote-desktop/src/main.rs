@@ -0,0 +1,283 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Exposes the desktop to remote clients over TCP.
+//!
+//! This program registers itself as a video output towards the compositor, and serves the
+//! content of this video output to clients connecting on port 5900.
+//!
+//! The protocol is intentionally simple. All integers are encoded in little endian.
+//!
+//! - Upon connection, the server sends the width and height of the output as two `u32`s.
+//! - The server then sends a sequence of rectangles. Each rectangle consists of its `x`, `y`,
+//!   `width` and `height` as four `u32`s, followed with `width * height` pixels in RGB format,
+//!   row by row. The first rectangle always covers the entire output.
+//! - The client can send input events, each encoded as a `u32` length followed with a
+//!   SCALE-encoded framebuffer [`Event`](redshirt_framebuffer_interface::ffi::Event). Keyboard,
+//!   character, cursor, mouse button and raw mouse motion events are forwarded to the
+//!   compositor. Cursor positions are relative to the top-left hand corner of the output. Other
+//!   events are ignored.
+//!
+//! A subset of the RFB (VNC) protocol could be supported in the future.
+
+use futures::{channel::mpsc, prelude::*, stream::FuturesUnordered};
+use parity_scale_codec::DecodeAll as _;
+use redshirt_framebuffer_interface::ffi as fb_ffi;
+use redshirt_video_output_interface::{ffi as vid_ffi, video_output};
+use std::{
+    convert::{Infallible, TryFrom as _},
+    io,
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+};
+
+/// Width in pixels of the output exposed to clients.
+const WIDTH: u32 = 1024;
+/// Height in pixels of the output exposed to clients.
+const HEIGHT: u32 = 768;
+
+/// Maximum size of an input event sent by a client.
+const MAX_EVENT_LEN: usize = 1024;
+
+/// Maximum number of rectangles waiting to be sent to a client. Clients that fall further behind
+/// are disconnected.
+const MAX_PENDING_UPDATES: usize = 256;
+
+fn main() {
+    redshirt_log_interface::init();
+    redshirt_syscalls::block_on(async_main())
+}
+
+async fn async_main() {
+    let video_output = match video_output::register(video_output::VideoOutputConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        refresh_rate_mhz: 0,
+        modes: Vec::new(),
+        format: vid_ffi::Format::R8G8B8X8,
+        edid: None,
+    })
+    .await
+    {
+        Ok(registration) => Rc::new(registration),
+        Err(()) => {
+            log::error!("Failed to register video output");
+            return;
+        }
+    };
+
+    let listener = redshirt_tcp_interface::TcpListener::bind(&"0.0.0.0:5900".parse().unwrap())
+        .await
+        .unwrap();
+
+    log::info!("Remote desktop now available on 0.0.0.0:5900");
+
+    // Copy of the content of the output, in RGB format. Sent to new clients.
+    let mut screen = vec![0; usize::try_from(WIDTH * HEIGHT * 3).unwrap()];
+
+    // Channels towards the tasks serving each client.
+    let mut clients = Vec::<mpsc::Sender<Arc<[u8]>>>::new();
+
+    let mut active_clients = FuturesUnordered::<Pin<Box<dyn Future<Output = ()>>>>::new();
+    active_clients.push(Box::pin(future::pending()));
+
+    loop {
+        futures::select! {
+            (connection, addr) = listener.accept().fuse() => {
+                log::debug!("New remote desktop client: {}", addr);
+                let (tx, rx) = mpsc::channel(MAX_PENDING_UPDATES);
+                clients.push(tx);
+                let initial = encode_rect(0, 0, WIDTH, HEIGHT, &screen);
+                active_clients.push(Box::pin(serve_client(connection, initial, rx, video_output.clone())));
+            },
+
+            frame = video_output.next_frame().fuse() => {
+                for change in frame.changes {
+                    let rect = match apply_change(&mut screen, &change) {
+                        Some(r) => r,
+                        None => {
+                            log::warn!("Received out of bounds change from the compositor");
+                            continue;
+                        }
+                    };
+
+                    broadcast(&mut clients, Arc::from(rect));
+                }
+            },
+
+            () = active_clients.select_next_some() => {},
+        }
+    }
+}
+
+/// Queues `rect` towards each client. Clients whose queue is full are disconnected.
+fn broadcast(clients: &mut Vec<mpsc::Sender<Arc<[u8]>>>, rect: Arc<[u8]>) {
+    // Dropping the sender of a client closes its connection.
+    let mut n = 0;
+    while n < clients.len() {
+        match clients[n].try_send(rect.clone()) {
+            Ok(()) => n += 1,
+            Err(err) => {
+                if err.is_full() {
+                    log::debug!("Disconnecting remote desktop client lagging behind");
+                }
+                clients.swap_remove(n);
+            }
+        }
+    }
+}
+
+/// Applies a change received from the compositor to `screen`. Returns the corresponding
+/// rectangle encoded in the format sent to clients, or `None` if the change doesn't fit within
+/// the output.
+fn apply_change(screen: &mut [u8], change: &vid_ffi::NextImageChange) -> Option<Vec<u8>> {
+    let height = u32::try_from(change.pixels.len()).ok()?;
+    let width = change.screen_x_len;
+    if change.screen_x_start.checked_add(width)? > WIDTH
+        || change.screen_y_start.checked_add(height)? > HEIGHT
+    {
+        return None;
+    }
+
+    let mut rgb = Vec::with_capacity(usize::try_from(width * height * 3).unwrap());
+    for (row_offset, row) in change.pixels.iter().enumerate() {
+        if row.len() != usize::try_from(width * 4).unwrap() {
+            return None;
+        }
+
+        let y = change.screen_y_start + u32::try_from(row_offset).unwrap();
+        let row_start = usize::try_from((y * WIDTH + change.screen_x_start) * 3).unwrap();
+        let row_rgb = row.chunks(4).flat_map(|pixel| pixel[..3].iter().copied());
+        for (dest, src) in screen[row_start..].iter_mut().zip(row_rgb) {
+            *dest = src;
+        }
+        rgb.extend_from_slice(&screen[row_start..row_start + usize::try_from(width * 3).unwrap()]);
+    }
+
+    Some(encode_rect(
+        change.screen_x_start,
+        change.screen_y_start,
+        width,
+        height,
+        &rgb,
+    ))
+}
+
+/// Encodes a rectangle of RGB pixels in the format sent to clients.
+fn encode_rect(x: u32, y: u32, width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    debug_assert_eq!(rgb.len(), usize::try_from(width * height * 3).unwrap());
+    let mut out = Vec::with_capacity(16 + rgb.len());
+    out.extend_from_slice(&x.to_le_bytes());
+    out.extend_from_slice(&y.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(rgb);
+    out
+}
+
+/// Serves a single client until the connection is closed or an error happens.
+async fn serve_client(
+    connection: redshirt_tcp_interface::TcpStream,
+    initial: Vec<u8>,
+    updates: mpsc::Receiver<Arc<[u8]>>,
+    video_output: Rc<video_output::VideoOutputRegistration>,
+) {
+    let (reader, writer) = connection.split();
+    let send = send_updates(writer, initial, updates);
+    let receive = receive_events(reader, &video_output);
+    futures::pin_mut!(send, receive);
+
+    match future::select(send, receive).await {
+        future::Either::Left((Ok(()), _)) => {}
+        future::Either::Left((Err(err), _)) | future::Either::Right((Err(err), _)) => {
+            log::debug!("Remote desktop client disconnected: {}", err)
+        }
+        future::Either::Right((Ok(never), _)) => match never {},
+    }
+}
+
+/// Sends the size of the output, then `initial`, then every element of `updates` to the client.
+async fn send_updates(
+    mut writer: impl AsyncWrite + Unpin,
+    initial: Vec<u8>,
+    mut updates: mpsc::Receiver<Arc<[u8]>>,
+) -> Result<(), io::Error> {
+    writer.write_all(&WIDTH.to_le_bytes()).await?;
+    writer.write_all(&HEIGHT.to_le_bytes()).await?;
+    writer.write_all(&initial).await?;
+    while let Some(update) = updates.next().await {
+        writer.write_all(&update).await?;
+    }
+    Ok(())
+}
+
+/// Reads input events sent by the client and reports them on `video_output`. Only ever returns
+/// in case of error.
+async fn receive_events(
+    mut reader: impl AsyncRead + Unpin,
+    video_output: &video_output::VideoOutputRegistration,
+) -> Result<Infallible, io::Error> {
+    loop {
+        let mut len = [0; 4];
+        reader.read_exact(&mut len).await?;
+        let len = usize::try_from(u32::from_le_bytes(len)).unwrap();
+        if len > MAX_EVENT_LEN {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let mut event = vec![0; len];
+        reader.read_exact(&mut event).await?;
+        let event = fb_ffi::Event::decode_all(&event)
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+
+        match input_event(event) {
+            Ok(event) => video_output.report_input(event),
+            Err(event) => log::debug!("Ignoring input event from remote client: {:?}", event),
+        }
+    }
+}
+
+/// Converts an event sent by a client into an input event of the video output. Returns the
+/// event back if it isn't an input event.
+fn input_event(event: fb_ffi::Event) -> Result<vid_ffi::InputEvent, fb_ffi::Event> {
+    let pressed = |state: &fb_ffi::ElementState| matches!(state, fb_ffi::ElementState::Pressed);
+    match event {
+        fb_ffi::Event::KeyboardChange {
+            scancode,
+            new_state,
+        } => Ok(vid_ffi::InputEvent::KeyboardChange {
+            scancode,
+            pressed: pressed(&new_state),
+        }),
+        fb_ffi::Event::CursorMoved { new_position } => Ok(vid_ffi::InputEvent::CursorMoved {
+            position: new_position,
+        }),
+        fb_ffi::Event::MouseButtonChange { button, new_state } => {
+            Ok(vid_ffi::InputEvent::MouseButtonChange {
+                button: match button {
+                    fb_ffi::MouseButton::Main => vid_ffi::MouseButton::Main,
+                    fb_ffi::MouseButton::Secondary => vid_ffi::MouseButton::Secondary,
+                },
+                pressed: pressed(&new_state),
+            })
+        }
+        fb_ffi::Event::Character { character } => Ok(vid_ffi::InputEvent::Character { character }),
+        fb_ffi::Event::RawMouseMotion { dx, dy } => {
+            Ok(vid_ffi::InputEvent::RawMouseMotion { dx, dy })
+        }
+        event => Err(event),
+    }
+}
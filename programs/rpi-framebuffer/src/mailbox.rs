@@ -15,6 +15,7 @@
 
 // TODO: more docs at https://github.com/raspberrypi/firmware/wiki/Mailbox-property-interface
 
+use redshirt_hardware_interface::ResponseMismatchError;
 use std::convert::TryFrom as _;
 
 /// Message to write to the mailbox, or read from the mailbox.
@@ -57,11 +58,13 @@ const BASE_IO_PERIPH: u64 = 0x3f000000; // 0x20000000 for raspi 1
 const MAILBOX_BASE: u64 = BASE_IO_PERIPH + 0xb880;
 
 /// Reads one message from the mailbox.
-pub async fn read_mailbox() -> Message {
+///
+/// Returns an error if the response of the hardware handler doesn't match the request.
+pub async fn read_mailbox() -> Result<Message, ResponseMismatchError> {
     unsafe {
         // Wait for status register to indicate a message.
         loop {
-            let val = redshirt_hardware_interface::read_one_u32(MAILBOX_BASE + 0x18).await;
+            let val = redshirt_hardware_interface::read_one_u32(MAILBOX_BASE + 0x18).await?;
             if val & (1 << 30) == 0 {
                 break;
             }
@@ -70,22 +73,25 @@ pub async fn read_mailbox() -> Message {
         let mut read = redshirt_hardware_interface::HardwareOperationsBuilder::new();
         let mut out = [0];
         read.read_u32(MAILBOX_BASE + 0x0, &mut out);
-        read.send().await;
-        Message { value: out[0] }
+        read.send().await?;
+        Ok(Message { value: out[0] })
     }
 }
 
 /// Writes one message from the mailbox.
-pub async fn write_mailbox(message: Message) {
+///
+/// Returns an error if the response of the hardware handler doesn't match the request.
+pub async fn write_mailbox(message: Message) -> Result<(), ResponseMismatchError> {
     unsafe {
         // Wait for status register to indicate a message.
         loop {
-            let val = redshirt_hardware_interface::read_one_u32(MAILBOX_BASE + 0x18).await;
+            let val = redshirt_hardware_interface::read_one_u32(MAILBOX_BASE + 0x18).await?;
             if val & (1 << 31) == 0 {
                 break;
             }
         }
 
         redshirt_hardware_interface::write_one_u32(MAILBOX_BASE + 0x20, message.value);
+        Ok(())
     }
 }
@@ -26,5 +26,10 @@ fn main() {
 }
 
 async fn async_main() {
-    property::init().await;
+    if property::init().await.is_err() {
+        redshirt_log_interface::emit_log(
+            redshirt_log_interface::Level::Error,
+            "Failed to initialize the framebuffer",
+        );
+    }
 }
@@ -135,7 +135,11 @@ struct Packet2 {
 }
 
 // TODO: make more generic and explicit, with tags and all, to be more robust to code changes
-pub async fn init() {
+/// Configures the framebuffer and clears it.
+///
+/// Returns an error if the property buffers couldn't be allocated or read back, or if the
+/// mailbox couldn't be accessed.
+pub async fn init() -> Result<(), ()> {
     let buffer1 = redshirt_hardware_interface::malloc::PhysicalBuffer::new(Packet1 {
         data: [
             80, // The whole buffer is 80 bytes
@@ -147,18 +151,19 @@ pub async fn init() {
             0, 0, 0, // This pads the message to by 16 byte aligned
         ],
     })
-    .await;
+    .await?;
 
     assert_eq!(buffer1.pointer() % 16, 0);
     mailbox::write_mailbox(mailbox::Message::new(
         8,
         u32::try_from(buffer1.pointer() >> 4).unwrap(),
     ))
-    .await; // TODO: ` | 0x40000000` ?
+    .await // TODO: ` | 0x40000000` ?
+    .map_err(|_| ())?;
 
-    mailbox::read_mailbox().await;
+    mailbox::read_mailbox().await.map_err(|_| ())?;
 
-    let data1 = buffer1.take().await;
+    let data1 = buffer1.take().await?;
     assert_eq!(data1.data[1], 0x80000000);
 
     let actual_width = data1.data[5];
@@ -172,18 +177,19 @@ pub async fn init() {
             0, // This is the end tag
         ],
     })
-    .await;
+    .await?;
 
     assert_eq!(buffer2.pointer() % 16, 0);
     mailbox::write_mailbox(mailbox::Message::new(
         8,
         u32::try_from(buffer2.pointer() >> 4).unwrap(),
     ))
-    .await;
+    .await
+    .map_err(|_| ())?;
 
-    mailbox::read_mailbox().await;
+    mailbox::read_mailbox().await.map_err(|_| ())?;
 
-    let data2 = buffer2.take().await;
+    let data2 = buffer2.take().await?;
     assert_eq!(data2.data[1], 0x80000000);
 
     let fb_addr = data2.data[5];
@@ -199,4 +205,5 @@ pub async fn init() {
         );
     }
     op_builder.send();
+    Ok(())
 }
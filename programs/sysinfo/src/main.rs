@@ -0,0 +1,143 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Implements the `sysinfo` interface.
+//!
+//! The system configuration is read from the kernel command line, through the following
+//! arguments:
+//!
+//! - `hostname=<name>`: name of the machine.
+//! - `tz=<seconds>`: offset, in seconds, to add to the UTC time in order to obtain the local
+//! time.
+//! - `locale=<locale>`: locale of the system, such as `en_US.UTF-8`.
+//!
+//! Missing arguments are reported as an empty string, or as UTC for the timezone.
+
+use redshirt_interface_interface::DecodedInterfaceOrDestroyed;
+use redshirt_syscalls::Decode as _;
+use redshirt_sysinfo_interface::ffi as sysinfo_ffi;
+
+fn main() {
+    redshirt_log_interface::init();
+    redshirt_syscalls::block_on(async_main())
+}
+
+async fn async_main() {
+    let mut registration = redshirt_interface_interface::register_interface(sysinfo_ffi::INTERFACE)
+        .await
+        .unwrap();
+
+    let config = Config::from_command_line(&redshirt_bootargs_interface::command_line().await);
+
+    loop {
+        let interface_event = registration.next_message_raw().await;
+        let msg = match interface_event {
+            DecodedInterfaceOrDestroyed::Interface(msg) => msg,
+            DecodedInterfaceOrDestroyed::ProcessDestroyed(_) => continue,
+            DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => continue,
+        };
+
+        let message_id = match msg.message_id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        match sysinfo_ffi::SysInfoMessage::decode(msg.actual_data) {
+            Ok(sysinfo_ffi::SysInfoMessage::GetHostname) => {
+                redshirt_interface_interface::emit_answer(
+                    message_id,
+                    &sysinfo_ffi::HostnameResponse {
+                        hostname: config.hostname.clone().into_bytes(),
+                    },
+                );
+            }
+            Ok(sysinfo_ffi::SysInfoMessage::GetTimezone) => {
+                redshirt_interface_interface::emit_answer(
+                    message_id,
+                    &sysinfo_ffi::TimezoneResponse {
+                        utc_offset_secs: config.utc_offset_secs,
+                    },
+                );
+            }
+            Ok(sysinfo_ffi::SysInfoMessage::GetLocale) => {
+                redshirt_interface_interface::emit_answer(
+                    message_id,
+                    &sysinfo_ffi::LocaleResponse {
+                        locale: config.locale.clone().into_bytes(),
+                    },
+                );
+            }
+            Err(_) => {
+                log::warn!("Bad sysinfo message from {:?}", msg.emitter_pid);
+                redshirt_interface_interface::emit_message_error(message_id);
+            }
+        }
+    }
+}
+
+/// System configuration reported through the interface.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Config {
+    hostname: String,
+    utc_offset_secs: i32,
+    locale: String,
+}
+
+impl Config {
+    /// Extracts the configuration from the kernel command line.
+    ///
+    /// Arguments that can't be parsed are ignored.
+    fn from_command_line(command_line: &str) -> Config {
+        let mut config = Config::default();
+
+        for (key, value) in redshirt_bootargs_interface::parse(command_line) {
+            match (key, value) {
+                ("hostname", Some(value)) => config.hostname = value.to_owned(),
+                ("tz", Some(value)) => match value.parse() {
+                    Ok(offset) => config.utc_offset_secs = offset,
+                    Err(_) => log::warn!("Invalid timezone offset: {:?}", value),
+                },
+                ("locale", Some(value)) => config.locale = value.to_owned(),
+                _ => {}
+            }
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn parse_command_line() {
+        let config = Config::from_command_line("debug hostname=foo tz=-3600 locale=fr_FR.UTF-8");
+        assert_eq!(
+            config,
+            Config {
+                hostname: "foo".to_owned(),
+                utc_offset_secs: -3600,
+                locale: "fr_FR.UTF-8".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_or_missing_arguments() {
+        let config = Config::from_command_line("hostname tz=abc");
+        assert_eq!(config, Config::default());
+    }
+}
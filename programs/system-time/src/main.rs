@@ -13,10 +13,17 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+//! Implements the `system-time` interface.
+//!
+//! The wall clock is initialized from the real-time clock of the machine, then follows the
+//! monotonic clock.
+
 use redshirt_interface_interface::DecodedInterfaceOrDestroyed;
 use redshirt_syscalls::Decode as _;
 use redshirt_system_time_interface::ffi as sys_time_ffi;
 
+mod rtc;
+
 fn main() {
     redshirt_syscalls::block_on(async_main())
 }
@@ -27,18 +34,39 @@ async fn async_main() {
             .await
             .unwrap();
 
+    // Number of nanoseconds since the Epoch and value of the monotonic clock at the time the
+    // RTC was read. If the RTC can't be read, the system time starts at the Epoch.
+    let rtc_time = u128::from(rtc::read_unix_time().await.unwrap_or(0)) * 1_000_000_000;
+    let rtc_monotonic = redshirt_time_interface::monotonic_clock()
+        .await
+        .expect("failed to query the monotonic clock");
+
     loop {
         let interface_event = registration.next_message_raw().await;
         let msg = match interface_event {
             DecodedInterfaceOrDestroyed::Interface(msg) => msg,
             DecodedInterfaceOrDestroyed::ProcessDestroyed(_) => continue,
+            DecodedInterfaceOrDestroyed::InterfaceRegistered(_) => continue,
         };
 
-        let msg_data = sys_time_ffi::TimeMessage::decode(msg.actual_data).unwrap();
-        let sys_time_ffi::TimeMessage::GetSystem = msg_data;
+        match sys_time_ffi::TimeMessage::decode(msg.actual_data) {
+            Ok(sys_time_ffi::TimeMessage::GetSystem) => {}
+            Err(_) => {
+                if let Some(id) = msg.message_id {
+                    redshirt_interface_interface::emit_message_error(id);
+                }
+                continue;
+            }
+        }
 
         if let Some(id) = msg.message_id {
-            redshirt_interface_interface::emit_answer(id, &0u128);
+            match redshirt_time_interface::monotonic_clock().await {
+                Ok(now) => {
+                    let elapsed = now.saturating_sub(rtc_monotonic);
+                    redshirt_interface_interface::emit_answer(id, &(rtc_time + elapsed));
+                }
+                Err(()) => redshirt_interface_interface::emit_message_error(id),
+            }
         }
     }
 }
@@ -0,0 +1,241 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Reading the CMOS real-time clock found on PC-compatible machines.
+//!
+//! The RTC is accessed through the ports `0x70` (register selection) and `0x71` (register
+//! value). On platforms where it doesn't exist, reading these ports returns garbage or zeroes,
+//! which is detected by [`read_unix_time`] as an invalid date or as an update that never ends.
+
+use redshirt_hardware_interface::HardwareOperationsBuilder;
+
+/// Reads the RTC and returns the number of seconds since the Epoch (January 1st, 1970 at
+/// midnight UTC), or `None` if no valid date could be read.
+///
+/// The RTC is assumed to be in UTC.
+pub async fn read_unix_time() -> Option<u64> {
+    // The RTC might be updating its registers while we read them. We read them until we get
+    // the same values twice in a row.
+    let mut previous = read_registers().await?;
+    for _ in 0..8 {
+        let current = read_registers().await?;
+        if current == previous {
+            return current.to_unix_time();
+        }
+        previous = current;
+    }
+
+    None
+}
+
+/// Raw values of the RTC registers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Registers {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+    century: u8,
+    status_b: u8,
+}
+
+/// Maximum number of times the status register A is read while waiting for an update of the
+/// registers to finish.
+///
+/// An update normally lasts less than 2ms. On platforms without an RTC, reading the port can
+/// return `0xff`, in which case the "update in progress" flag never gets cleared.
+const MAX_UPDATE_IN_PROGRESS_READS: u32 = 10_000;
+
+/// Reads the registers of the RTC. Returns `None` if the hardware handler answered with a
+/// malformed response, or if the RTC stays busy for too long.
+async fn read_registers() -> Option<Registers> {
+    // Wait for the "update in progress" flag of the status register A to be cleared.
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        if attempts > MAX_UPDATE_IN_PROGRESS_READS {
+            return None;
+        }
+
+        let mut status_a = 0;
+        unsafe {
+            let mut ops = HardwareOperationsBuilder::with_capacity(2);
+            ops.port_write_u8(0x70, 0x0a);
+            ops.port_read_u8(0x71, &mut status_a);
+            ops.send().await.ok()?;
+        }
+        if status_a & 0x80 == 0 {
+            break;
+        }
+    }
+
+    // Note that the century register isn't standard. `0x32` is the most common location.
+    let mut values = [0; 8];
+    unsafe {
+        let mut ops = HardwareOperationsBuilder::with_capacity(16);
+        for (register, out) in [0x00, 0x02, 0x04, 0x07, 0x08, 0x09, 0x32, 0x0b]
+            .iter()
+            .zip(values.iter_mut())
+        {
+            ops.port_write_u8(0x70, *register);
+            ops.port_read_u8(0x71, out);
+        }
+        ops.send().await.ok()?;
+    }
+
+    Some(Registers {
+        seconds: values[0],
+        minutes: values[1],
+        hours: values[2],
+        day: values[3],
+        month: values[4],
+        year: values[5],
+        century: values[6],
+        status_b: values[7],
+    })
+}
+
+impl Registers {
+    /// Turns the registers into a number of seconds since the Epoch. Returns `None` if the
+    /// values are invalid.
+    fn to_unix_time(&self) -> Option<u64> {
+        // Bit 2 of status register B indicates binary values instead of BCD, and bit 1
+        // indicates the 24 hours format instead of 12 hours.
+        let binary = self.status_b & 0x04 != 0;
+        let hours_24 = self.status_b & 0x02 != 0;
+        let decode = |value: u8| {
+            if binary {
+                Some(value)
+            } else {
+                decode_bcd(value)
+            }
+        };
+
+        let seconds = decode(self.seconds).filter(|s| *s < 60)?;
+        let minutes = decode(self.minutes).filter(|m| *m < 60)?;
+        let hours = {
+            let raw = decode(self.hours & 0x7f)?;
+            if hours_24 {
+                raw
+            } else {
+                // In the 12 hours format, bit 7 indicates PM, and midnight and noon are 12.
+                let pm = self.hours & 0x80 != 0;
+                (raw % 12) + if pm { 12 } else { 0 }
+            }
+        };
+        if hours >= 24 {
+            return None;
+        }
+        let day = decode(self.day).filter(|d| (1..=31).contains(d))?;
+        let month = decode(self.month).filter(|m| (1..=12).contains(m))?;
+        let year = decode(self.year).filter(|y| *y < 100)?;
+
+        // Assume the 21st century if the century register doesn't contain a plausible value.
+        let century = decode(self.century)
+            .filter(|c| (19..=21).contains(c))
+            .unwrap_or(20);
+        let year = u64::from(century) * 100 + u64::from(year);
+        if year < 1970 {
+            return None;
+        }
+
+        let days = days_since_epoch(year, u64::from(month), u64::from(day));
+        Some(days * 86400 + u64::from(hours) * 3600 + u64::from(minutes) * 60 + u64::from(seconds))
+    }
+}
+
+/// Decodes a binary-coded decimal. Returns `None` if it isn't valid.
+fn decode_bcd(value: u8) -> Option<u8> {
+    let (tens, units) = (value >> 4, value & 0xf);
+    if tens > 9 || units > 9 {
+        return None;
+    }
+    Some(tens * 10 + units)
+}
+
+/// Returns the number of days between the Epoch and the given date of the Gregorian calendar.
+///
+/// `year` must be at least 1970, and `month` between 1 and 12.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Years are counted from March, so that the leap day is at the end of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    // 719468 is the number of days between March 1st of the year 0 and the Epoch.
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{days_since_epoch, Registers};
+
+    #[test]
+    fn epoch() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11017);
+    }
+
+    #[test]
+    fn bcd_24_hours() {
+        // 2021-07-15 13:45:30
+        let registers = Registers {
+            seconds: 0x30,
+            minutes: 0x45,
+            hours: 0x13,
+            day: 0x15,
+            month: 0x07,
+            year: 0x21,
+            century: 0x20,
+            status_b: 0x02,
+        };
+        assert_eq!(registers.to_unix_time(), Some(1626356730));
+    }
+
+    #[test]
+    fn binary_12_hours() {
+        // 2021-07-15 12:05:00 AM, without century register
+        let registers = Registers {
+            seconds: 0,
+            minutes: 5,
+            hours: 12,
+            day: 15,
+            month: 7,
+            year: 21,
+            century: 0,
+            status_b: 0x04,
+        };
+        assert_eq!(registers.to_unix_time(), Some(1626307500));
+    }
+
+    #[test]
+    fn zeroes_are_invalid() {
+        let registers = Registers {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            day: 0,
+            month: 0,
+            year: 0,
+            century: 0,
+            status_b: 0,
+        };
+        assert_eq!(registers.to_unix_time(), None);
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (C) 2019-2021  Pierre Krieger
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Periodically logs the list of processes running on the machine.
+//!
+//! Only does something if the kernel has been started with the `top` argument on its command
+//! line. Requires the capability to use the `process` interface.
+
+use redshirt_process_interface::{ProcessInfo, ProcessState};
+use redshirt_time_interface::Delay;
+use std::{fmt::Write as _, time::Duration};
+
+/// Time between two reports.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+fn main() {
+    redshirt_log_interface::init();
+    redshirt_syscalls::block_on(async_main());
+}
+
+async fn async_main() {
+    let command_line = redshirt_bootargs_interface::command_line().await;
+    if !redshirt_bootargs_interface::parse(&command_line).any(|(key, _)| key == "top") {
+        return;
+    }
+
+    loop {
+        match redshirt_process_interface::list().await {
+            Ok(processes) => log::info!("{}", report(&processes)),
+            Err(()) => {
+                log::error!("Failed to list the running processes");
+                return;
+            }
+        }
+
+        Delay::new(REPORT_INTERVAL).await;
+    }
+}
+
+/// Builds a human-readable table of the given processes.
+fn report(processes: &[ProcessInfo]) -> String {
+    let mut out = format!("{} processes running\n", processes.len());
+    let _ = writeln!(
+        out,
+        "{:<22} {:<16} {:>10} {:>7} {:>7} {:<11}",
+        "PID", "MODULE", "MEM (kiB)", "THREADS", "PENDING", "STATE"
+    );
+
+    for process in processes {
+        let module: String = process.module_hash[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let state = match process.state {
+            ProcessState::Running => "running",
+            ProcessState::Terminating => "terminating",
+        };
+        let _ = writeln!(
+            out,
+            "{:<22} {:<16} {:>10} {:>7} {:>7} {:<11}",
+            format!("{:?}", process.pid),
+            module,
+            process.memory_size / 1024,
+            process.num_threads,
+            process.pending_messages,
+            state
+        );
+    }
+
+    out
+}
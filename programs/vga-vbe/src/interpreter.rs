@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use core::{convert::TryFrom, fmt};
+use core::{convert::TryFrom, fmt, mem};
 
 mod tests;
 
@@ -26,6 +26,9 @@ pub struct Interpreter {
     /// If true, perform I/O ports operations on the actual machine. Otherwise, reading a port
     /// returns 0 and writing a port is a no-op.
     enable_io_operations: bool,
+    /// Set to true if reading the memory of the actual machine has failed. Checked and reset
+    /// after each instruction.
+    memory_read_failed: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -54,6 +57,9 @@ pub enum Error {
 
     /// Code has called the `hlt` instruction, which cannot do anything.
     InterruptNotSupported,
+
+    /// Accessing the memory or the I/O ports of the actual machine has failed.
+    HardwareAccess,
 }
 
 impl fmt::Display for Error {
@@ -61,16 +67,18 @@ impl fmt::Display for Error {
         match self {
             Error::InvalidInstruction => write!(f, "Invalid instruction"),
             Error::InterruptNotSupported => write!(f, "HLT has been called"),
+            Error::HardwareAccess => write!(f, "Failed to access the hardware"),
         }
     }
 }
 
 impl Interpreter {
-    pub async fn from_real_machine() -> Self {
-        let first_mb = unsafe { redshirt_hardware_interface::read(0x0, 0x100000).await };
+    pub async fn from_real_machine() -> Result<Self, Error> {
+        let first_mb = unsafe { redshirt_hardware_interface::read(0x0, 0x100000).await }
+            .map_err(|_| Error::HardwareAccess)?;
         // Small sanity check.
         assert!(first_mb.iter().any(|b| *b != 0));
-        Self::from_memory(first_mb).await
+        Ok(Self::from_memory(first_mb).await)
     }
 
     pub async fn from_memory(first_mb: Vec<u8>) -> Self {
@@ -97,6 +105,7 @@ impl Interpreter {
                 flags: 0b1011000000000010,
             },
             enable_io_operations: true,
+            memory_read_failed: false,
         }
     }
 
@@ -113,7 +122,9 @@ impl Interpreter {
 
     /// Reads bytes from the physical memory.
     ///
-    /// This will read the memory of the actual machine.
+    /// This will read the memory of the actual machine. If that fails, the content of the cache
+    /// is returned instead, and the next instruction being run returns
+    /// [`Error::HardwareAccess`].
     ///
     /// # Panic
     ///
@@ -126,20 +137,22 @@ impl Interpreter {
         // Perform a cache refresh only if we hit the video memory.
         if self.enable_io_operations && addr >= 0xa0000 && addr < 0xc0000 {
             // TODO: asyncify?
-            redshirt_syscalls::block_on(async {
-                unsafe {
-                    redshirt_hardware_interface::read_to(u64::from(addr), out).await;
-                }
+            let result = redshirt_syscalls::block_on(async {
+                unsafe { redshirt_hardware_interface::read_to(u64::from(addr), out).await }
             });
-            self.memory_cache
-                [usize::try_from(addr).unwrap()..usize::try_from(addr + out_len).unwrap()]
-                .copy_from_slice(&out);
-        } else {
-            out.copy_from_slice(
-                &self.memory_cache
-                    [usize::try_from(addr).unwrap()..usize::try_from(addr + out_len).unwrap()],
-            );
+            if result.is_ok() {
+                self.memory_cache
+                    [usize::try_from(addr).unwrap()..usize::try_from(addr + out_len).unwrap()]
+                    .copy_from_slice(&out);
+                return;
+            }
+            self.memory_read_failed = true;
         }
+
+        out.copy_from_slice(
+            &self.memory_cache
+                [usize::try_from(addr).unwrap()..usize::try_from(addr + out_len).unwrap()],
+        );
     }
 
     pub fn read_memory_nul_terminated_str(&mut self, mut addr: u32) -> String {
@@ -257,6 +270,9 @@ impl Interpreter {
             };
 
             self.run_one(&instruction)?;
+            if mem::take(&mut self.memory_read_failed) {
+                return Err(Error::HardwareAccess);
+            }
 
             match instruction.mnemonic() {
                 iced_x86::Mnemonic::Iret if nested_ints == 0 => break Ok(()),
@@ -756,16 +772,19 @@ impl Interpreter {
                             redshirt_syscalls::block_on(redshirt_hardware_interface::port_read_u8(
                                 u32::from(port),
                             ))
+                            .map_err(|_| Error::HardwareAccess)?
                         }),
                         Value::U16(_) => Value::U16(unsafe {
                             redshirt_syscalls::block_on(redshirt_hardware_interface::port_read_u16(
                                 u32::from(port),
                             ))
+                            .map_err(|_| Error::HardwareAccess)?
                         }),
                         Value::U32(_) => Value::U32(unsafe {
                             redshirt_syscalls::block_on(redshirt_hardware_interface::port_read_u32(
                                 u32::from(port),
                             ))
+                            .map_err(|_| Error::HardwareAccess)?
                         }),
                     }
                 } else {
@@ -67,6 +67,7 @@
 //!
 
 use core::convert::TryFrom as _;
+use redshirt_video_output_interface::ffi as vid_ffi;
 
 mod interpreter;
 mod vbe;
@@ -79,7 +80,13 @@ fn main() {
 async fn async_main() {
     let mut pci_locks = Vec::new();
 
-    let pci_devices = redshirt_pci_interface::get_pci_devices().await;
+    let pci_devices = match redshirt_pci_interface::get_pci_devices().await {
+        Ok(d) => d,
+        Err(()) => {
+            log::error!("Failed to retrieve the list of PCI devices");
+            return;
+        }
+    };
     for device in pci_devices {
         // We match any PCI device that self-describes as VGA-compatible.
         match (device.class_code, device.subclass, device.prog_if) {
@@ -108,8 +115,8 @@ async fn async_main() {
         chosen_mode_num,
         width,
         height,
-        linear_framebuffer_location,
-        bytes_per_scan_line,
+        mut linear_framebuffer_location,
+        mut bytes_per_scan_line,
         red_mask_size,
         red_mask_pos,
         green_mask_size,
@@ -148,7 +155,7 @@ async fn async_main() {
         (red_mask_size + green_mask_size + blue_mask_size + reserved_mask_size) % 8,
         0
     );
-    let bytes_per_character =
+    let mut bytes_per_character =
         (red_mask_size + green_mask_size + blue_mask_size + reserved_mask_size) / 8;
 
     log::info!(
@@ -160,16 +167,68 @@ async fn async_main() {
     vbe.set_current_mode(chosen_mode_num).await.unwrap();
     log::info!("Successfully set VESA mode");
 
+    // Modes that the video output can be switched to, with their VBE mode number. Apart from the
+    // current mode, only modes with 32 bits per pixel are offered, as each pixel that we receive
+    // is 4 bytes. VBE doesn't let us know the refresh rate.
+    let mut available_modes = vec![(
+        chosen_mode_num,
+        vid_ffi::Mode {
+            width: u32::from(width),
+            height: u32::from(height),
+            refresh_rate_mhz: 0,
+        },
+    )];
+    for mode in vbe.modes() {
+        let bits_per_pixel = u16::from(mode.red_mask_size())
+            + u16::from(mode.green_mask_size())
+            + u16::from(mode.blue_mask_size())
+            + u16::from(mode.reserved_mask_size());
+        if bits_per_pixel != 32 {
+            continue;
+        }
+
+        let dimensions = (
+            u32::from(mode.pixels_dimensions().0),
+            u32::from(mode.pixels_dimensions().1),
+        );
+        if available_modes
+            .iter()
+            .any(|(_, m)| (m.width, m.height) == dimensions)
+        {
+            continue;
+        }
+
+        available_modes.push((
+            mode.num(),
+            vid_ffi::Mode {
+                width: dimensions.0,
+                height: dimensions.1,
+                refresh_rate_mhz: 0,
+            },
+        ));
+    }
+
     // Register the framebuffer as a video output.
-    let video_output_registration = redshirt_video_output_interface::video_output::register(
+    let video_output_registration = match redshirt_video_output_interface::video_output::register(
         redshirt_video_output_interface::video_output::VideoOutputConfig {
             width: u32::from(width),
             height: u32::from(height),
+            refresh_rate_mhz: 0,
+            modes: available_modes.iter().map(|(_, mode)| *mode).collect(),
             // TODO: proper format
-            format: redshirt_video_output_interface::ffi::Format::R8G8B8X8,
+            format: vid_ffi::Format::R8G8B8X8,
+            // TODO: could be read through the VBE/DDC extension
+            edid: None,
         },
     )
-    .await;
+    .await
+    {
+        Ok(registration) => registration,
+        Err(()) => {
+            log::error!("Failed to register video output");
+            return;
+        }
+    };
 
     // TODO: not implemented in the kernel
     // TODO: should *add* a logging method, rather than set it
@@ -198,6 +257,40 @@ async fn async_main() {
 
     loop {
         let frame = video_output_registration.next_frame().await;
+
+        if let Some(new_mode) = frame.mode {
+            let mode_num = match available_modes.iter().find(|(_, m)| *m == new_mode) {
+                Some((num, _)) => *num,
+                None => {
+                    log::warn!("Compositor requested an unknown mode: {:?}", new_mode);
+                    continue;
+                }
+            };
+
+            log::info!(
+                "Switching to mode 0x{:x}. Dimensions = {}x{}",
+                mode_num,
+                new_mode.width,
+                new_mode.height
+            );
+
+            // The changes that come with the new mode are in its dimensions, and can't be
+            // drawn if switching fails.
+            if let Err(err) = vbe.set_current_mode(mode_num).await {
+                log::error!("Failed to switch VESA mode: {}", err);
+                continue;
+            }
+
+            let mode = vbe.modes().find(|m| m.num() == mode_num).unwrap();
+            linear_framebuffer_location = mode.linear_framebuffer_location();
+            bytes_per_scan_line = mode.bytes_per_scan_line();
+            bytes_per_character = (mode.red_mask_size()
+                + mode.green_mask_size()
+                + mode.blue_mask_size()
+                + mode.reserved_mask_size())
+                / 8;
+        }
+
         if frame.changes.is_empty() {
             continue;
         }
@@ -30,7 +30,7 @@ pub struct VbeContext {
 /// card while this call is in progress or the returned [`VbeContext`] is in use.
 ///
 pub async unsafe fn load_vbe_info() -> Result<VbeContext, Error> {
-    let mut interpreter = interpreter::Interpreter::from_real_machine().await;
+    let mut interpreter = interpreter::Interpreter::from_real_machine().await?;
 
     // We start by asking the BIOS for general information about the graphics device.
     interpreter.set_ax(0x4f00);